@@ -3,10 +3,11 @@ use std::{cell::RefCell, marker::PhantomData};
 use halo2_proofs::{
     circuit::{layouter, AssignedCell, Layouter, SimpleFloorPlanner, Value},
     dev::MockProver,
-    halo2curves::bn256::{Bn256, G1Affine},
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
     plonk::{
-        create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Circuit, Column,
-        ConstraintSystem, Error, Expression, Fixed, Instance, Selector, TableColumn,
+        create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Challenge, Circuit, Column,
+        ConstraintSystem, Error, Expression, FirstPhase, Fixed, Instance, ProvingKey, SecondPhase,
+        Selector, TableColumn, VerifyingKey,
     },
     poly::{
         commitment::Prover,
@@ -19,11 +20,11 @@ use halo2_proofs::{
     },
     transcript::{
         self, Blake2bRead, Blake2bWrite, Challenge255, PoseidonWrite, TranscriptReadBuffer,
+        TranscriptWriterBuffer,
     },
 };
 
 use ff::{Field, PrimeField};
-use rand::rngs::ThreadRng;
 
 struct TestCircuit<F: Field> {
     _ph: PhantomData<F>,
@@ -31,11 +32,21 @@ struct TestCircuit<F: Field> {
     b: Value<u8>, // secret
 }
 
+// operation tags for the shared `fixed_tables` lookup: a row requesting
+// `typ_requested = TYP_AND` only matches table rows appended with
+// `typ = TYP_AND`, so XOR/AND/OR/range-check entries can all live in
+// the same table without colliding.
+const TYP_XOR: u64 = 0;
+const TYP_AND: u64 = 1;
+const TYP_OR: u64 = 2;
+const TYP_RANGE: u64 = 3;
+
 #[derive(Clone, Debug)]
 struct FixedTableChip<F: PrimeField> {
     _ph: PhantomData<F>,
     off: RefCell<usize>,
     sel: Selector,
+    typ_requested: Column<Fixed>,
     typ: TableColumn,
     in1: TableColumn,
     in2: TableColumn,
@@ -43,9 +54,20 @@ struct FixedTableChip<F: PrimeField> {
 }
 
 impl<F: PrimeField> FixedTableChip<F> {
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
-        let sel = meta.compress_selector();
-        let typ = meta.fixed_column();
+    // Tags the table with an operation id (`typ`) and includes it in the
+    // lookup query (`(sel*typ_requested, typ), ...`), so a single table
+    // can hold several disjoint operations (XOR, AND, OR, a range check)
+    // without their entries colliding: a row requesting `typ_requested =
+    // TYP_AND` only ever matches table rows appended with `typ =
+    // TYP_AND`. `typ_requested` is a fixed column the calling gate sets
+    // per row to select which operation that row is querying.
+    fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: Column<Advice>,
+        typ_requested: Column<Fixed>,
+    ) -> Self {
+        let sel = meta.complex_selector();
+        let typ = meta.lookup_table_column();
 
         let in1 = meta.lookup_table_column();
         let in2 = meta.lookup_table_column();
@@ -55,11 +77,13 @@ impl<F: PrimeField> FixedTableChip<F> {
             let w0 = meta.query_advice(advice, Rotation(0)); // current row
             let w1 = meta.query_advice(advice, Rotation(1)); // next row
             let w2 = meta.query_advice(advice, Rotation(2)); // next next row
-            let q_xor = meta.query_selector(q_xor);
+            let typ_req = meta.query_fixed(typ_requested, Rotation::cur());
+            let sel = meta.query_selector(sel);
             vec![
-                (q_xor.clone() * w0, tbl_in1),
-                (q_xor.clone() * w1, tbl_in2),
-                (q_xor.clone() * w2, tbl_out),
+                (sel.clone() * typ_req, typ),
+                (sel.clone() * w0, in1),
+                (sel.clone() * w1, in2),
+                (sel * w2, out),
             ]
         });
 
@@ -67,6 +91,7 @@ impl<F: PrimeField> FixedTableChip<F> {
             _ph: PhantomData,
             off: RefCell::new(0),
             sel,
+            typ_requested,
             typ,
             in1,
             in2,
@@ -126,13 +151,334 @@ impl<F: PrimeField> FixedTableChip<F> {
     }
 }
 
+// ANCHOR: fixed_table_logup
+// A log-derivative (LogUp) alternative to `FixedTableChip::configure`'s
+// plookup: that lookup costs one lookup column per operand and
+// duplicates the whole table once per `typ`, since `meta.lookup` checks
+// `(sel*typ_requested, typ), (sel*w0, in1), (sel*w1, in2), (sel*w2, out)`
+// against the same four table columns for every operation. Here the witness tuple and the
+// table row are each compressed into one field element with a
+// first-phase challenge `alpha`, and a second-phase challenge `beta`
+// plus a running sum `z` check multiset equality between the witness
+// side and a multiplicity-weighted table side - following the same
+// two-phase construction as `session-4.rs`'s `LogUpChip`. Existing
+// callers that want the plain plookup keep using `FixedTableChip::
+// configure`; callers building large tables can opt into this mode via
+// `FixedTableChip::configure_logup` instead.
+//
+// `beta` is sampled (via `challenge_usable_after(FirstPhase)`) only
+// after the witness and table columns from the first phase are
+// committed, so `beta + f_i` and `beta + t_j` are nonzero with
+// overwhelming probability; `op`/`finalize` below `.unwrap()` the
+// inversion, so a genuine collision (negligible probability) panics
+// rather than silently producing an unsatisfiable proof.
+#[derive(Clone, Debug)]
+struct FixedTableLogUpChip<F: Field> {
+    q_enable: Selector,
+    q_table: Selector,
+    q_acc: Selector,
+    q_last: Selector,
+
+    alpha: Challenge,
+    beta: Challenge,
+
+    typ: Column<Fixed>,
+    in1: Column<Advice>,
+    in2: Column<Advice>,
+    out: Column<Advice>,
+    inv: Column<Advice>,
+
+    table_typ: Column<Fixed>,
+    table_in1: Column<Fixed>,
+    table_in2: Column<Fixed>,
+    table_out: Column<Fixed>,
+    mult: Column<Advice>,
+
+    term: Column<Advice>,
+    z: Column<Advice>,
+
+    // one entry per witnessed operation: (typ, in1, in2, out, inv-cell),
+    // kept in raw `u8` form so `finalize` can use it as a hash-map key
+    // when counting multiplicities. Drained by `finalize`, since a real
+    // multi-phase prover calls `synthesize` once per phase and would
+    // otherwise see the same queries pushed again on each call.
+    #[allow(clippy::type_complexity)]
+    queries: RefCell<Vec<(u64, Value<u8>, Value<u8>, Value<u8>, AssignedCell<F, F>)>>,
+    _ph: PhantomData<F>,
+}
+
+impl<F: PrimeField> FixedTableLogUpChip<F> {
+    fn configure(
+        meta: &mut ConstraintSystem<F>,
+        typ: Column<Fixed>,
+        in1: Column<Advice>,
+        in2: Column<Advice>,
+        out: Column<Advice>,
+    ) -> Self {
+        let q_enable = meta.selector();
+        let q_table = meta.selector();
+        let q_acc = meta.selector();
+        let q_last = meta.selector();
+
+        let alpha = meta.challenge_usable_after(FirstPhase);
+        let beta = meta.challenge_usable_after(FirstPhase);
+
+        let inv = meta.advice_column_in(SecondPhase);
+        let mult = meta.advice_column();
+        let term = meta.advice_column_in(SecondPhase);
+        let z = meta.advice_column_in(SecondPhase);
+
+        let table_typ = meta.fixed_column();
+        let table_in1 = meta.fixed_column();
+        let table_in2 = meta.fixed_column();
+        let table_out = meta.fixed_column();
+
+        meta.enable_equality(inv);
+        meta.enable_equality(term);
+        meta.enable_equality(z);
+
+        // per-witness-row inverse correctness: inv_i * (beta + f_i) = 1
+        meta.create_gate("fixed-table logup witness inverse", |meta| {
+            let q_enable = meta.query_selector(q_enable);
+            let typ_q = meta.query_fixed(typ, Rotation::cur());
+            let in1 = meta.query_advice(in1, Rotation::cur());
+            let in2 = meta.query_advice(in2, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+            let inv = meta.query_advice(inv, Rotation::cur());
+            let alpha = meta.query_challenge(alpha);
+            let beta = meta.query_challenge(beta);
+
+            let f = typ_q + alpha.clone() * in1 + alpha.clone() * alpha.clone() * in2
+                + alpha.clone() * alpha.clone() * alpha * out;
+            vec![q_enable * (inv * (beta + f) - Expression::Constant(F::ONE))]
+        });
+
+        // per-table-row multiplicity correctness: (-term_j) * (beta + t_j) = m_j
+        meta.create_gate("fixed-table logup table term", |meta| {
+            let q_table = meta.query_selector(q_table);
+            let typ = meta.query_fixed(table_typ, Rotation::cur());
+            let in1 = meta.query_fixed(table_in1, Rotation::cur());
+            let in2 = meta.query_fixed(table_in2, Rotation::cur());
+            let out = meta.query_fixed(table_out, Rotation::cur());
+            let mult = meta.query_advice(mult, Rotation::cur());
+            let term = meta.query_advice(term, Rotation::cur());
+            let alpha = meta.query_challenge(alpha);
+            let beta = meta.query_challenge(beta);
+
+            let t = typ + alpha.clone() * in1 + alpha.clone() * alpha.clone() * in2
+                + alpha.clone() * alpha.clone() * alpha * out;
+            vec![q_table * (-term * (beta + t) - mult)]
+        });
+
+        // shared running sum: z_{i+1} = z_i + term_i. Witness rows copy
+        // their already-checked `inv` cell straight into `term`; table
+        // rows assign the negated `m_j/(beta + t_j)` into `term`, so one
+        // gate chains both halves of the argument.
+        meta.create_gate("fixed-table logup accumulator", |meta| {
+            let q_acc = meta.query_selector(q_acc);
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            let term = meta.query_advice(term, Rotation::cur());
+            vec![q_acc * (z_next - z_cur - term)]
+        });
+
+        // boundary: the final accumulator value must vanish
+        meta.create_gate("fixed-table logup boundary", |meta| {
+            let q_last = meta.query_selector(q_last);
+            let z = meta.query_advice(z, Rotation::cur());
+            vec![q_last * z]
+        });
+
+        Self {
+            q_enable,
+            q_table,
+            q_acc,
+            q_last,
+            alpha,
+            beta,
+            typ,
+            in1,
+            in2,
+            out,
+            inv,
+            table_typ,
+            table_in1,
+            table_in2,
+            table_out,
+            mult,
+            term,
+            z,
+            queries: RefCell::new(Vec::new()),
+            _ph: PhantomData,
+        }
+    }
+
+    // Query one operation: witnesses `(typ, in1, in2, out)` on a fresh
+    // row and records it so `finalize` can later derive the table
+    // multiplicities. Mirrors `FixedTableChip::op_binary`'s shape, but
+    // actually checks the tuple against the table (unlike the plain
+    // chip's `op_binary`/`op_unary`, which only populate the table).
+    fn op_binary(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        typ: u64,
+        in1: Value<u8>,
+        in2: Value<u8>,
+        out: Value<u8>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let alpha = layouter.get_challenge(self.alpha);
+        let beta = layouter.get_challenge(self.beta);
+        let typ_f = Value::known(F::from(typ));
+
+        layouter.assign_region(
+            || "fixed-table logup op",
+            |mut region| {
+                self.q_enable.enable(&mut region, 0)?;
+                region.assign_fixed(|| "typ", self.typ, 0, || typ_f)?;
+
+                let in1_f = in1.map(|v| F::from(v as u64));
+                let in2_f = in2.map(|v| F::from(v as u64));
+                let out_f = out.map(|v| F::from(v as u64));
+                region.assign_advice(|| "in1", self.in1, 0, || in1_f)?;
+                region.assign_advice(|| "in2", self.in2, 0, || in2_f)?;
+                let out_cell = region.assign_advice(|| "out", self.out, 0, || out_f)?;
+
+                let f = typ_f
+                    .zip(alpha)
+                    .zip(in1_f)
+                    .zip(in2_f)
+                    .zip(out_f)
+                    .map(|((((typ, alpha), in1), in2), out)| {
+                        typ + alpha * in1 + alpha * alpha * in2 + alpha * alpha * alpha * out
+                    });
+                let inv = beta.zip(f).map(|(beta, f)| (beta + f).invert().unwrap());
+                let inv_cell = region.assign_advice(|| "inv", self.inv, 0, || inv)?;
+
+                self.queries
+                    .borrow_mut()
+                    .push((typ, in1, in2, out, inv_cell));
+
+                Ok(out_cell)
+            },
+        )
+    }
+
+    // Must be called once, after every `op_binary` query has been made:
+    // assigns the full table (keyed by `typ`) together with the
+    // witnessed multiplicities, and chains the running-sum accumulator
+    // through both the witness queries and the table rows.
+    fn finalize(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        table: &[(u64, u8, u8, u8)],
+    ) -> Result<(), Error> {
+        let alpha = layouter.get_challenge(self.alpha);
+        let beta = layouter.get_challenge(self.beta);
+
+        // take (rather than borrow) so a later phase's queries, if any,
+        // start from a clean slate instead of compounding onto this one
+        let queries = self.queries.take();
+
+        let mut counts = Some(std::collections::HashMap::<(u64, u8, u8, u8), u64>::new());
+        for (typ, in1, in2, out, _) in queries.iter() {
+            let known = in1.zip(*in2).zip(*out);
+            match (counts.as_mut(), known) {
+                (Some(map), Some(((in1, in2), out))) => {
+                    *map.entry((*typ, in1, in2, out)).or_insert(0) += 1;
+                }
+                _ => counts = None,
+            }
+        }
+
+        layouter.assign_region(
+            || "fixed-table logup accumulator",
+            |mut region| {
+                let mut z = region.assign_advice(|| "z0", self.z, 0, || Value::known(F::ZERO))?;
+                let mut row = 0;
+
+                // witness side: z_{i+1} = z_i + inv_i
+                for (_, _, _, _, inv_cell) in queries.iter() {
+                    self.q_acc.enable(&mut region, row)?;
+                    let term = inv_cell.copy_advice(|| "term", &mut region, self.term, row)?;
+                    let next = z
+                        .value()
+                        .copied()
+                        .zip(term.value().copied())
+                        .map(|(z, t)| z + t);
+                    z = region.assign_advice(|| "z", self.z, row + 1, || next)?;
+                    row += 1;
+                }
+
+                // table side: z_{i+1} = z_i - m_j/(beta + t_j)
+                for &(typ, in1, in2, out) in table.iter() {
+                    self.q_table.enable(&mut region, row)?;
+                    self.q_acc.enable(&mut region, row)?;
+
+                    let typ_f = F::from(typ);
+                    let in1_f = F::from(in1 as u64);
+                    let in2_f = F::from(in2 as u64);
+                    let out_f = F::from(out as u64);
+
+                    region.assign_fixed(|| "typ", self.table_typ, row, || Value::known(typ_f))?;
+                    region.assign_fixed(|| "in1", self.table_in1, row, || Value::known(in1_f))?;
+                    region.assign_fixed(|| "in2", self.table_in2, row, || Value::known(in2_f))?;
+                    region.assign_fixed(|| "out", self.table_out, row, || Value::known(out_f))?;
+
+                    let mult = match &counts {
+                        Some(map) => {
+                            let count = map.get(&(typ, in1, in2, out)).copied().unwrap_or(0);
+                            Value::known(F::from(count))
+                        }
+                        None => Value::unknown(),
+                    };
+                    region.assign_advice(|| "mult", self.mult, row, || mult)?;
+
+                    let t_j = alpha.map(|a| typ_f + a * in1_f + a * a * in2_f + a * a * a * out_f);
+                    let term_val = beta
+                        .zip(t_j)
+                        .zip(mult)
+                        .map(|((beta, t), m)| -(m * (beta + t).invert().unwrap()));
+                    let term = region.assign_advice(|| "term", self.term, row, || term_val)?;
+
+                    let next = z
+                        .value()
+                        .copied()
+                        .zip(term.value().copied())
+                        .map(|(z, t)| z + t);
+                    z = region.assign_advice(|| "z", self.z, row + 1, || next)?;
+                    row += 1;
+                }
+
+                self.q_last.enable(&mut region, row)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+impl<F: PrimeField> FixedTableChip<F> {
+    // Opt-in LogUp mode: same `(typ, in1, in2, out)` relation as
+    // `configure`'s plookup, but backed by a log-derivative argument so
+    // the table is checked once total rather than once per witness row.
+    fn configure_logup(
+        meta: &mut ConstraintSystem<F>,
+        typ: Column<Fixed>,
+        in1: Column<Advice>,
+        in2: Column<Advice>,
+        out: Column<Advice>,
+    ) -> FixedTableLogUpChip<F> {
+        FixedTableLogUpChip::configure(meta, typ, in1, in2, out)
+    }
+}
+// ANCHOR_END: fixed_table_logup
+
 #[derive(Clone, Debug)]
 struct TestConfig<F: PrimeField> {
     _ph: PhantomData<F>,
     advice: Column<Advice>,
     fixed: Column<Fixed>,
     instance: Column<Instance>,
-    fixed_tables: FixedTables<F>,
+    fixed_tables: FixedTableChip<F>,
 }
 
 #[derive(Debug, Clone)]
@@ -167,17 +513,23 @@ impl<F: PrimeField> TestCircuit<F> {
         Ok(Bit4Ranged { var, val })
     }
 
-    fn xor(
+    // shared by `xor`/`and`/`or`: assigns `(w0, w1, w2)` on three
+    // consecutive rows, tags the row with `typ` in `config.fixed` so the
+    // `fixed_tables` lookup checks it against the right sub-table, and
+    // enables the lookup via `config.fixed_tables.sel`.
+    fn op_binary(
         config: &TestConfig<F>,
         layouter: &mut impl Layouter<F>,
+        typ: u64,
         lhs: Bit4Ranged<F>,
         rhs: Bit4Ranged<F>,
+        compute: impl FnOnce(u8, u8) -> u8,
     ) -> Result<Bit4Ranged<F>, Error> {
         layouter.assign_region(
-            || "xor-region",
+            || "binary-op-region",
             |mut region| {
-                // turn on the xor gate
-                config.q_xor.enable(&mut region, 0)?;
+                config.fixed_tables.sel.enable(&mut region, 0)?;
+                region.assign_fixed(|| "typ", config.fixed, 0, || Value::known(F::from(typ)))?;
 
                 // remember: also enforces equality between lhs/rhs and w0/w1
                 let w0 = lhs
@@ -189,7 +541,7 @@ impl<F: PrimeField> TestCircuit<F> {
 
                 let val = lhs
                     .val
-                    .and_then(|in1| rhs.val.and_then(|in2| Value::known(in1 ^ in2)));
+                    .and_then(|in1| rhs.val.and_then(|in2| Value::known(compute(in1, in2))));
 
                 let w2 = region.assign_advice(
                     || "w2",
@@ -202,6 +554,63 @@ impl<F: PrimeField> TestCircuit<F> {
             },
         )
     }
+
+    fn xor(
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        lhs: Bit4Ranged<F>,
+        rhs: Bit4Ranged<F>,
+    ) -> Result<Bit4Ranged<F>, Error> {
+        Self::op_binary(config, layouter, TYP_XOR, lhs, rhs, |a, b| a ^ b)
+    }
+
+    fn and(
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        lhs: Bit4Ranged<F>,
+        rhs: Bit4Ranged<F>,
+    ) -> Result<Bit4Ranged<F>, Error> {
+        Self::op_binary(config, layouter, TYP_AND, lhs, rhs, |a, b| a & b)
+    }
+
+    fn or(
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        lhs: Bit4Ranged<F>,
+        rhs: Bit4Ranged<F>,
+    ) -> Result<Bit4Ranged<F>, Error> {
+        Self::op_binary(config, layouter, TYP_OR, lhs, rhs, |a, b| a | b)
+    }
+
+    // checks `val` is a genuine 4-bit value by looking it up against the
+    // range-check sub-table (typ = TYP_RANGE, in2 fixed to 0, out = in1).
+    fn range_check(
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        val: Bit4Ranged<F>,
+    ) -> Result<Bit4Ranged<F>, Error> {
+        layouter.assign_region(
+            || "range-check-region",
+            |mut region| {
+                config.fixed_tables.sel.enable(&mut region, 0)?;
+                region.assign_fixed(
+                    || "typ",
+                    config.fixed,
+                    0,
+                    || Value::known(F::from(TYP_RANGE)),
+                )?;
+
+                val.var.copy_advice(|| "w0", &mut region, config.advice, 0)?;
+                region.assign_advice(|| "w1", config.advice, 1, || Value::known(F::ZERO))?;
+                val.var.copy_advice(|| "w2", &mut region, config.advice, 2)?;
+
+                Ok(Bit4Ranged {
+                    var: val.var.clone(),
+                    val: val.val,
+                })
+            },
+        )
+    }
 }
 
 impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
@@ -221,7 +630,7 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
         let fixed = meta.fixed_column();
         let instance = meta.instance_column();
 
-        let fixed_tables = FixedTableChip::configure(meta);
+        let fixed_tables = FixedTableChip::configure(meta, advice, fixed);
 
         let tbl_in1 = meta.lookup_table_column();
         let tbl_in2 = meta.lookup_table_column();
@@ -256,15 +665,189 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
         // c = a * b
         let c = Self::xor(&config, &mut layouter, a, b)?;
 
-        // instance[0] = c
+        // instance[0] = c: exports the ciphertext byte as a public input, so
+        // `prove_verify::verify` below checks a committed ciphertext rather
+        // than taking the prover's word for it
         layouter.constrain_instance(c.var.cell(), config.instance, 0)?;
+
+        // exercise the rest of the shared, tag-dispatched table: AND, OR,
+        // and a standalone range check, none of which feed the instance
+        let a = Self::bits(&config, &mut layouter, self.a)?;
+        let b = Self::bits(&config, &mut layouter, self.b)?;
+        Self::and(&config, &mut layouter, a, b)?;
+
+        let a = Self::bits(&config, &mut layouter, self.a)?;
+        let b = Self::bits(&config, &mut layouter, self.b)?;
+        Self::or(&config, &mut layouter, a, b)?;
+
+        let a = Self::bits(&config, &mut layouter, self.a)?;
+        Self::range_check(&config, &mut layouter, a)?;
+
         Ok(())
     }
 }
 
-fn main() {
-    use halo2_proofs::halo2curves::bn256::Fr;
+// ANCHOR: prove_verify
+// Runs the full halo2 backend over bn256 instead of stopping at
+// `MockProver`, reusing the `keygen`/`create_proof`/`verify_proof` plumbing
+// from `chips.rs`'s `prove_verify` module. Unlike `chips.rs`, the public
+// `prove`/`verify` entry points below re-run `keygen` on every call instead
+// of letting the caller cache `pk`/`vk`: the request this implements asks
+// for exactly a `prove(key, pt, params) -> Vec<u8>` / `verify(ct_public,
+// proof, params) -> bool` surface taking only the serialized SRS, so a
+// WASM caller never holds a `ProvingKey`/`VerifyingKey` across calls. Fine
+// for this k=9 toy circuit; a bigger circuit would want `keygen`'s result
+// cached browser-side instead of re-synthesized per call.
+mod prove_verify {
+    use super::*;
+
+    pub fn keygen(params: &ParamsKZG<Bn256>, circuit: &TestCircuit<Fr>) -> (ProvingKey<G1Affine>, VerifyingKey<G1Affine>) {
+        let vk_circuit = circuit.without_witnesses();
+        let vk = keygen_vk(params, &vk_circuit).expect("keygen_vk failed");
+        let pk = keygen_pk(params, vk.clone(), &vk_circuit).expect("keygen_pk failed");
+        (pk, vk)
+    }
+
+    fn make_proof(params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>, circuit: &TestCircuit<Fr>, instance: &[Fr]) -> Vec<u8> {
+        let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+            params,
+            pk,
+            &[circuit],
+            &[&[instance]],
+            rand::thread_rng(),
+            &mut transcript,
+        )
+        .expect("create_proof failed");
+        transcript.finalize()
+    }
+
+    fn check_proof(params: &ParamsKZG<Bn256>, vk: &VerifyingKey<G1Affine>, proof: &[u8], instance: &[Fr]) -> Result<(), Error> {
+        let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof);
+        let strategy = SingleStrategy::new(params);
+        verify_proof::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<_>, _, _, _>(params, vk, strategy, &[&[instance]], &mut transcript)
+    }
+
+    // Proves that the public instance equals `key ^ pt` (the "ciphertext"
+    // `c` this toy circuit computes from the witnessed `key`/`pt`), keygen-ing fresh each call
+    // so the caller only needs the SRS bytes. Panics on a malformed SRS or
+    // a circuit that doesn't satisfy its own constraints - both indicate a
+    // caller bug, not something a `Result` should paper over.
+    pub fn prove(key: u8, pt: u8, params: &[u8]) -> Vec<u8> {
+        let params = ParamsKZG::<Bn256>::read(&mut &params[..]).expect("malformed SRS bytes");
+        let circuit = TestCircuit::<Fr> {
+            _ph: PhantomData,
+            a: Value::known(key),
+            b: Value::known(pt),
+        };
+        let instance = vec![Fr::from_u128((key ^ pt) as u128)];
+
+        let (pk, _vk) = keygen(&params, &circuit);
+        make_proof(&params, &pk, &circuit, &instance)
+    }
+
+    // Verifies `proof` against the public ciphertext byte `ct_public`,
+    // returning `false` rather than propagating `Error` since this is the
+    // one-bit answer a caller (e.g. the WASM wrapper below) actually wants.
+    pub fn verify(ct_public: u8, proof: &[u8], params: &[u8]) -> bool {
+        let params = ParamsKZG::<Bn256>::read(&mut &params[..]).expect("malformed SRS bytes");
+        let vk_circuit = TestCircuit::<Fr> {
+            _ph: PhantomData,
+            a: Value::unknown(),
+            b: Value::unknown(),
+        };
+        let vk = keygen_vk(&params, &vk_circuit).expect("keygen_vk failed");
+        let instance = vec![Fr::from_u128(ct_public as u128)];
 
+        check_proof(&params, &vk, proof, &instance).is_ok()
+    }
+}
+// ANCHOR_END: prove_verify
+
+// ANCHOR: wasm
+// Browser-facing wrappers around `prove_verify`: same signatures, just
+// exposed to JS via wasm-bindgen. The SRS is generated once off-circuit
+// (`ParamsKZG::setup` + `ParamsKZG::write`) and shipped to the browser
+// alongside the WASM bundle rather than regenerated per call, since
+// `setup` is far too slow to run per-proof.
+#[cfg(target_arch = "wasm32")]
+mod wasm {
+    use wasm_bindgen::prelude::wasm_bindgen;
+
+    #[wasm_bindgen]
+    pub fn prove(key: u8, pt: u8, params: &[u8]) -> Vec<u8> {
+        super::prove_verify::prove(key, pt, params)
+    }
+
+    #[wasm_bindgen]
+    pub fn verify(ct_public: u8, proof: &[u8], params: &[u8]) -> bool {
+        super::prove_verify::verify(ct_public, proof, params)
+    }
+}
+// ANCHOR_END: wasm
+
+// ANCHOR: fixed_table_logup_demo
+// A small end-to-end exercise of `FixedTableLogUpChip`, independent of
+// the (still in-progress) AES circuit above: witness two 4-bit XOR
+// operations and check them against the full 4-bit XOR table via the
+// LogUp argument instead of a plookup.
+#[derive(Clone, Debug)]
+struct LogUpDemoConfig<F: PrimeField> {
+    chip: FixedTableLogUpChip<F>,
+}
+
+struct LogUpDemoCircuit<F: Field> {
+    a1: Value<u8>,
+    b1: Value<u8>,
+    a2: Value<u8>,
+    b2: Value<u8>,
+    _ph: PhantomData<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for LogUpDemoCircuit<F> {
+    type Config = LogUpDemoConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        LogUpDemoCircuit {
+            a1: Value::unknown(),
+            b1: Value::unknown(),
+            a2: Value::unknown(),
+            b2: Value::unknown(),
+            _ph: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let typ = meta.fixed_column();
+        let in1 = meta.advice_column();
+        let in2 = meta.advice_column();
+        let out = meta.advice_column();
+
+        let chip = FixedTableChip::configure_logup(meta, typ, in1, in2, out);
+        LogUpDemoConfig { chip }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let table: Vec<(u64, u8, u8, u8)> = (0u8..16)
+            .flat_map(|a| (0u8..16).map(move |b| (0u64, a, b, a ^ b)))
+            .collect();
+
+        for (a, b) in [(self.a1, self.b1), (self.a2, self.b2)] {
+            let out = a.zip(b).map(|(a, b)| a ^ b);
+            config.chip.op_binary(&mut layouter, 0, a, b, out)?;
+        }
+
+        config.chip.finalize(&mut layouter, &table)
+    }
+}
+// ANCHOR_END: fixed_table_logup_demo
+
+fn main() {
     let k = 9;
 
     // run the MockProver
@@ -279,57 +862,24 @@ fn main() {
     let prover = MockProver::run(k, &circuit, vec![instances.clone()]).unwrap();
     prover.verify().unwrap();
 
-    /*
-    let vk_circuit = TestCircuit::<Fr> {
+    // now run the real prover/verifier over bn256, round-tripping the SRS
+    // through bytes the way a WASM caller would
+    let params = ParamsKZG::<Bn256>::setup(k, rand::thread_rng());
+    let mut params_bytes = vec![];
+    params.write(&mut params_bytes).expect("SRS serialization failed");
+
+    let proof = prove_verify::prove(0xe, 0xb, &params_bytes);
+    println!("proof-size: {} bytes", proof.len());
+    assert!(prove_verify::verify(0x5, &proof, &params_bytes));
+
+    // demonstrate the LogUp-based variant of the fixed-table lookup
+    let logup_demo_circuit = LogUpDemoCircuit::<Fr> {
+        a1: Value::known(0x3),
+        b1: Value::known(0xa),
+        a2: Value::known(0xc),
+        b2: Value::known(0x5),
         _ph: PhantomData,
-        a: Value::unknown(),
-        b: Value::unknown(),
     };
-
-    let mut rng = rand::thread_rng();
-    use halo2_proofs::transcript::{Blake2bWrite, Challenge255, TranscriptWriterBuffer};
-        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
-
-        let srs = ParamsKZG::setup(8, &mut rng);
-        let vk = keygen_vk(&srs, &vk_circuit).unwrap(); // public
-        let pk = keygen_pk(&srs, vk.clone(), &circuit).unwrap();
-
-    create_proof::<
-        KZGCommitmentScheme<Bn256>,
-        ProverSHPLONK<'_, Bn256>,
-        Challenge255<G1Affine>,
-        ThreadRng,
-        Blake2bWrite<Vec<u8>, G1Affine, Challenge255<G1Affine>>,
-        TestCircuit<Fr>,
-    >(
-        &srs,
-        &pk,
-        &[circuit],
-        &[&[&instances]],
-        rng,
-        &mut transcript,
-    )
-    .unwrap();
-
-    let pf: Vec<u8> = transcript.finalize(); // public
-
-    println!("proof-size: {:?}", pf.len());
-
-    let mut transcript = Blake2bRead::init(&pf[..]);
-
-    verify_proof::<
-        KZGCommitmentScheme<Bn256>,
-        VerifierSHPLONK<'_, Bn256>,
-        Challenge255<G1Affine>,
-        Blake2bRead<&[u8], G1Affine, Challenge255<G1Affine>>,
-        SingleStrategy<'_, Bn256>,
-    >(
-        &srs,
-        &vk,
-        SingleStrategy::new(&srs),
-        &[&[&instances]],
-        &mut transcript,
-    )
-    .unwrap();
-    */
+    let prover = MockProver::run(9, &logup_demo_circuit, vec![]).unwrap();
+    prover.verify().unwrap();
 }
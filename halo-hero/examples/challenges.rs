@@ -6,7 +6,12 @@ use std::{
 use halo2_proofs::{
     circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
     dev::MockProver,
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
     plonk::{
+        create_proof,
+        keygen_pk,
+        keygen_vk,
+        verify_proof,
         Advice,
         Challenge,
         Circuit,
@@ -19,7 +24,15 @@ use halo2_proofs::{
         SecondPhase,
         Selector,
     },
-    poly::Rotation,
+    poly::{
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            multiopen::{ProverSHPLONK, VerifierSHPLONK},
+            strategy::SingleStrategy,
+        },
+        Rotation, VerificationStrategy,
+    },
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer},
 };
 
 use ff::{Field, PrimeField};
@@ -129,11 +142,54 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
     // ANCHOR_END: synthesize
 }
 
-fn main() {
-    use halo2_proofs::halo2curves::bn256::Fr;
+// ANCHOR: prove_and_verify
+// Runs the full halo2 backend over bn256: keygen -> create_proof ->
+// verify_proof, rather than just checking constraint satisfaction with
+// MockProver. Because `TestCircuit` allocates a `FirstPhase` challenge and
+// assigns into a `SecondPhase` advice column, this exercises the multi-phase
+// commitment scheme end to end, not just a single-phase circuit.
+fn prove_and_verify(k: u32, circuit: &TestCircuit<Fr>) {
+    let mut rng = rand::thread_rng();
+
+    let params = ParamsKZG::<Bn256>::setup(k, &mut rng);
+
+    let vk_circuit = circuit.without_witnesses();
+    let vk = keygen_vk(&params, &vk_circuit).expect("keygen_vk failed");
+    let pk = keygen_pk(&params, vk.clone(), &vk_circuit).expect("keygen_pk failed");
+
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        &params,
+        &pk,
+        &[circuit],
+        &[&[]],
+        rng,
+        &mut transcript,
+    )
+    .expect("create_proof failed");
+    let proof = transcript.finalize();
+
+    println!("proof-size: {} bytes", proof.len());
+
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&proof[..]);
+    let strategy = SingleStrategy::new(&params);
+    verify_proof::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<_>, _, _, _>(
+        &params,
+        &vk,
+        strategy,
+        &[&[]],
+        &mut transcript,
+    )
+    .expect("verify_proof failed");
+}
+// ANCHOR_END: prove_and_verify
 
+fn main() {
     // run the MockProver
     let circuit = TestCircuit::<Fr> { _ph: PhantomData };
     let prover = MockProver::run(10, &circuit, vec![]).unwrap();
     prover.verify().unwrap();
+
+    // now run the real prover/verifier over bn256
+    prove_and_verify(10, &circuit);
 }
@@ -4,8 +4,20 @@ use halo2_proofs::{
     arithmetic,
     circuit::{AssignedCell, Chip, Layouter, SimpleFloorPlanner, Value},
     dev::MockProver,
-    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector},
-    poly::Rotation,
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{
+        create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Circuit, Column, ConstraintSystem, Error,
+        Expression, Fixed, Instance, ProvingKey, Selector, VerifyingKey,
+    },
+    poly::{
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            multiopen::{ProverSHPLONK, VerifierSHPLONK},
+            strategy::SingleStrategy,
+        },
+        Rotation, VerificationStrategy,
+    },
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer},
 };
 
 use ff::Field;
@@ -16,11 +28,23 @@ struct TestCircuit<F: Field> {
 }
 
 // ANCHOR: arithmetic_chip
+// A single "standard" PLONK gate replaces the separate `q_mul`/`q_add`
+// selectors and gate polynomials: `sa*w0 + sb*w1 + sc*w2 + sm*(w0*w1) +
+// sconst = 0`. `add`/`mul`/`mul_const`/`linear_combination`/
+// `assert_constant` are all thin wrappers picking the right fixed
+// coefficients, so a multiply-then-add can fuse into fewer rows than one
+// region per operation. This mirrors the coefficient-driven gate used by
+// Orchard's `PLONKChip` and the `StandardCs` config in halo2's
+// circuit-layout example.
 #[derive(Clone, Debug)]
 struct ArithmeticChip<F: Field> {
     _ph: PhantomData<F>,
-    q_mul: Selector,
-    q_add: Selector,
+    q_enable: Selector,
+    sa: Column<Fixed>,
+    sb: Column<Fixed>,
+    sc: Column<Fixed>,
+    sm: Column<Fixed>,
+    sconst: Column<Fixed>,
     w0: Column<Advice>,
     w1: Column<Advice>,
     w2: Column<Advice>,
@@ -34,31 +58,39 @@ impl<F: Field> ArithmeticChip<F> {
         w1: Column<Advice>,
         w2: Column<Advice>,
     ) -> Self {
-        let q_mul = meta.complex_selector();
-        let q_add = meta.complex_selector();
+        let q_enable = meta.complex_selector();
+        let sa = meta.fixed_column();
+        let sb = meta.fixed_column();
+        let sc = meta.fixed_column();
+        let sm = meta.fixed_column();
+        let sconst = meta.fixed_column();
 
-        // define an addition gate:
-        meta.create_gate("add", |meta| {
+        // sa*w0 + sb*w1 + sc*w2 + sm*(w0*w1) + sconst = 0
+        meta.create_gate("standard", |meta| {
             let w0 = meta.query_advice(w0, Rotation(0));
             let w1 = meta.query_advice(w1, Rotation(0));
             let w2 = meta.query_advice(w2, Rotation(0));
-            let q_add = meta.query_selector(q_add);
-            vec![q_add * (w0 + w1 - w2)]
-        });
 
-        // define a multiplication gate:
-        meta.create_gate("mul", |meta| {
-            let w0 = meta.query_advice(w0, Rotation(0));
-            let w1 = meta.query_advice(w1, Rotation(0));
-            let w2 = meta.query_advice(w2, Rotation(0));
-            let q_mul = meta.query_selector(q_mul);
-            vec![q_mul * (w0 * w1 - w2)]
+            let sa = meta.query_fixed(sa, Rotation(0));
+            let sb = meta.query_fixed(sb, Rotation(0));
+            let sc = meta.query_fixed(sc, Rotation(0));
+            let sm = meta.query_fixed(sm, Rotation(0));
+            let sconst = meta.query_fixed(sconst, Rotation(0));
+
+            let q_enable = meta.query_selector(q_enable);
+
+            let expr = sa * w0.clone() + sb * w1.clone() + sc * w2 + sm * (w0 * w1) + sconst;
+            vec![q_enable * expr]
         });
 
         Self {
             _ph: PhantomData,
-            q_mul,
-            q_add,
+            q_enable,
+            sa,
+            sb,
+            sc,
+            sm,
+            sconst,
             w0,
             w1,
             w2,
@@ -81,7 +113,13 @@ impl<F: Field> ArithmeticChip<F> {
                 let w0 = region.assign_advice(|| "assign w0", self.w0, 0, || w0)?;
                 let w1 = region.assign_advice(|| "assign w1", self.w1, 0, || w1)?;
                 let w2 = region.assign_advice(|| "assign w2", self.w2, 0, || w2)?;
-                self.q_mul.enable(&mut region, 0)?;
+                self.q_enable.enable(&mut region, 0)?;
+
+                region.assign_fixed(|| "sa", self.sa, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "sb", self.sb, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "sc", self.sc, 0, || Value::known(-F::ONE))?;
+                region.assign_fixed(|| "sm", self.sm, 0, || Value::known(F::ONE))?;
+                region.assign_fixed(|| "sconst", self.sconst, 0, || Value::known(F::ZERO))?;
 
                 region.constrain_equal(w0.cell(), lhs.cell())?;
                 region.constrain_equal(w1.cell(), rhs.cell())?;
@@ -107,7 +145,13 @@ impl<F: Field> ArithmeticChip<F> {
                 let w0 = region.assign_advice(|| "assign w0", self.w0, 0, || w0)?;
                 let w1 = region.assign_advice(|| "assign w1", self.w1, 0, || w1)?;
                 let w2 = region.assign_advice(|| "assign w2", self.w2, 0, || w2)?;
-                self.q_add.enable(&mut region, 0)?;
+                self.q_enable.enable(&mut region, 0)?;
+
+                region.assign_fixed(|| "sa", self.sa, 0, || Value::known(F::ONE))?;
+                region.assign_fixed(|| "sb", self.sb, 0, || Value::known(F::ONE))?;
+                region.assign_fixed(|| "sc", self.sc, 0, || Value::known(-F::ONE))?;
+                region.assign_fixed(|| "sm", self.sm, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "sconst", self.sconst, 0, || Value::known(F::ZERO))?;
 
                 region.constrain_equal(w0.cell(), lhs.cell())?;
                 region.constrain_equal(w1.cell(), rhs.cell())?;
@@ -117,6 +161,100 @@ impl<F: Field> ArithmeticChip<F> {
         )
     }
 
+    /// Multiply `lhs` by the fixed constant `c`.
+    fn mul_const(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        lhs: AssignedCell<F, F>,
+        c: F,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "mul_const",
+            |mut region| {
+                let w0 = lhs.value().cloned();
+                let w2 = w0.map(|w0| w0 * c);
+
+                let w0 = region.assign_advice(|| "assign w0", self.w0, 0, || w0)?;
+                region.assign_advice(|| "assign w1", self.w1, 0, || Value::known(F::ZERO))?;
+                let w2 = region.assign_advice(|| "assign w2", self.w2, 0, || w2)?;
+                self.q_enable.enable(&mut region, 0)?;
+
+                region.assign_fixed(|| "sa", self.sa, 0, || Value::known(c))?;
+                region.assign_fixed(|| "sb", self.sb, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "sc", self.sc, 0, || Value::known(-F::ONE))?;
+                region.assign_fixed(|| "sm", self.sm, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "sconst", self.sconst, 0, || Value::known(F::ZERO))?;
+
+                region.constrain_equal(w0.cell(), lhs.cell())?;
+
+                Ok(w2)
+            },
+        )
+    }
+
+    /// Compute `a*ca + b*cb` in one region.
+    fn linear_combination(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        a: AssignedCell<F, F>,
+        ca: F,
+        b: AssignedCell<F, F>,
+        cb: F,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "linear_combination",
+            |mut region| {
+                let w0 = a.value().cloned();
+                let w1 = b.value().cloned();
+                let w2 = w0.and_then(|w0| w1.and_then(|w1| Value::known(w0 * ca + w1 * cb)));
+
+                let w0 = region.assign_advice(|| "assign w0", self.w0, 0, || w0)?;
+                let w1 = region.assign_advice(|| "assign w1", self.w1, 0, || w1)?;
+                let w2 = region.assign_advice(|| "assign w2", self.w2, 0, || w2)?;
+                self.q_enable.enable(&mut region, 0)?;
+
+                region.assign_fixed(|| "sa", self.sa, 0, || Value::known(ca))?;
+                region.assign_fixed(|| "sb", self.sb, 0, || Value::known(cb))?;
+                region.assign_fixed(|| "sc", self.sc, 0, || Value::known(-F::ONE))?;
+                region.assign_fixed(|| "sm", self.sm, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "sconst", self.sconst, 0, || Value::known(F::ZERO))?;
+
+                region.constrain_equal(w0.cell(), a.cell())?;
+                region.constrain_equal(w1.cell(), b.cell())?;
+
+                Ok(w2)
+            },
+        )
+    }
+
+    /// Assert that `cell` holds the fixed constant `c`.
+    fn assert_constant(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        cell: AssignedCell<F, F>,
+        c: F,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "assert_constant",
+            |mut region| {
+                let w0 = region.assign_advice(|| "assign w0", self.w0, 0, || cell.value().cloned())?;
+                region.assign_advice(|| "assign w1", self.w1, 0, || Value::known(F::ZERO))?;
+                region.assign_advice(|| "assign w2", self.w2, 0, || Value::known(F::ZERO))?;
+                self.q_enable.enable(&mut region, 0)?;
+
+                region.assign_fixed(|| "sa", self.sa, 0, || Value::known(F::ONE))?;
+                region.assign_fixed(|| "sb", self.sb, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "sc", self.sc, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "sm", self.sm, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "sconst", self.sconst, 0, || Value::known(-c))?;
+
+                region.constrain_equal(w0.cell(), cell.cell())?;
+
+                Ok(())
+            },
+        )
+    }
+
     fn free(
         &self,
         layouter: &mut impl Layouter<F>,
@@ -145,6 +283,7 @@ struct TestConfig<F: Field + Clone> {
     w0: Column<Advice>,
     w1: Column<Advice>,
     w2: Column<Advice>,
+    instance: Column<Instance>,
 }
 
 impl<F: Field> Circuit<F> for TestCircuit<F> {
@@ -170,6 +309,9 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
         meta.enable_equality(w1);
         meta.enable_equality(w2);
 
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
         let arithmetic_chip = ArithmeticChip::configure(meta, w0, w1, w2);
 
         TestConfig {
@@ -178,6 +320,7 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
             w0,
             w1,
             w2,
+            instance,
         }
     }
 
@@ -198,18 +341,143 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
             .arithmetic_chip
             .add(&mut layouter, a1.clone(), a2.clone())?;
 
+        // a4 = 2*a1 + a3, in one region instead of a `mul_const` followed by an `add`
+        let a4 = config.arithmetic_chip.linear_combination(
+            &mut layouter,
+            a1.clone(),
+            F::ONE + F::ONE,
+            a3.clone(),
+            F::ONE,
+        )?;
+
+        // a5 = 3*a4
+        let a5 = config
+            .arithmetic_chip
+            .mul_const(&mut layouter, a4, F::ONE + F::ONE + F::ONE)?;
+
+        layouter.constrain_instance(a5.cell(), config.instance, 0)?;
+
         Ok(())
     }
 }
 
+// ANCHOR: prove_verify
+// Runs the full halo2 backend over bn256 instead of stopping at
+// `MockProver`: `keygen` produces the (pk, vk) pair, `prove` runs
+// `create_proof` into a `Blake2bWrite`/`Challenge255` transcript, and
+// `verify` runs the matching `verify_proof` against the serialized bytes.
+// `a5` is exposed through `config.instance`, so the verifier actually
+// checks the circuit's public output rather than an empty instance vector.
+mod prove_verify {
+    use super::*;
+
+    pub fn keygen(params: &ParamsKZG<Bn256>, circuit: &TestCircuit<Fr>) -> (ProvingKey<G1Affine>, VerifyingKey<G1Affine>) {
+        let vk_circuit = circuit.without_witnesses();
+        let vk = keygen_vk(params, &vk_circuit).expect("keygen_vk failed");
+        let pk = keygen_pk(params, vk.clone(), &vk_circuit).expect("keygen_pk failed");
+        (pk, vk)
+    }
+
+    pub fn prove(params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>, circuit: &TestCircuit<Fr>, instance: &[Fr]) -> Vec<u8> {
+        let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+            params,
+            pk,
+            &[circuit],
+            &[&[instance]],
+            rand::thread_rng(),
+            &mut transcript,
+        )
+        .expect("create_proof failed");
+        transcript.finalize()
+    }
+
+    pub fn verify(params: &ParamsKZG<Bn256>, vk: &VerifyingKey<G1Affine>, proof: &[u8], instance: &[Fr]) -> Result<(), Error> {
+        let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof);
+        let strategy = SingleStrategy::new(params);
+        verify_proof::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<_>, _, _, _>(params, vk, strategy, &[&[instance]], &mut transcript)
+    }
+}
+// ANCHOR_END: prove_verify
+
+// ANCHOR: analysis
+// Reports how the circuit's `k` relates to its actual resource usage,
+// via the same `dev::cost::CircuitCost` tooling used in ex-suduko.rs,
+// plus `dev::CircuitGates` for the human-readable gate polynomials and a
+// `fits` check that turns `Error::NotEnoughRowsAvailable` into a concrete
+// answer instead of a failed `MockProver::run`.
+#[cfg(feature = "cost-estimator")]
+mod analysis {
+    use super::*;
+    use halo2_proofs::dev::{cost::CircuitCost, CircuitGates};
+    use halo2_proofs::halo2curves::bn256::G1;
+
+    /// Print gate count, column usage, and estimated proof size for `k`.
+    pub fn report(k: u32, circuit: &TestCircuit<Fr>, num_instance: usize) {
+        let cost: CircuitCost<G1, TestCircuit<Fr>> = CircuitCost::measure(k, circuit);
+
+        println!("max gate degree: {:?}", cost.max_deg);
+        println!("advice columns: {:?}", cost.advice_columns);
+        println!("estimated proof size: {:?}", cost.proof_size(num_instance));
+    }
+
+    /// Print the human-readable polynomial for each gate in the circuit.
+    pub fn gates() {
+        let gates = CircuitGates::collect::<Fr, TestCircuit<Fr>>();
+        println!("{}", gates);
+    }
+
+    /// Whether `circuit` fits within `2^k` rows, by running the
+    /// `MockProver` and checking for `Error::NotEnoughRowsAvailable`
+    /// rather than any other failure.
+    pub fn fits(k: u32, circuit: &TestCircuit<Fr>, instance: Vec<Fr>) -> bool {
+        match MockProver::run(k, circuit, vec![instance]) {
+            Ok(_) => true,
+            Err(Error::NotEnoughRowsAvailable { .. }) => false,
+            Err(e) => panic!("unexpected error while checking fit: {e:?}"),
+        }
+    }
+}
+// ANCHOR_END: analysis
+
 fn main() {
-    use halo2_proofs::halo2curves::bn256::Fr;
+    let secret = Fr::from(1337u64);
+
+    // a1 = secret, a2 = a1*a1, a3 = a1+a2, a4 = 2*a1+a3, a5 = 3*a4
+    let a1 = secret;
+    let a2 = a1 * a1;
+    let a3 = a1 + a2;
+    let a4 = a1 * Fr::from(2u64) + a3;
+    let a5 = a4 * Fr::from(3u64);
+    let instance = vec![a5];
 
     // run the MockProver
     let circuit = TestCircuit::<Fr> {
         _ph: PhantomData,
-        secret: Value::known(Fr::from(1337u64)),
+        secret: Value::known(secret),
     };
-    let prover = MockProver::run(8, &circuit, vec![]).unwrap();
+    let prover = MockProver::run(8, &circuit, vec![instance.clone()]).unwrap();
     prover.verify().unwrap();
+
+    // now run the real prover/verifier over bn256
+    let params = ParamsKZG::<Bn256>::setup(8, rand::thread_rng());
+    let (pk, vk) = prove_verify::keygen(&params, &circuit);
+    let proof = prove_verify::prove(&params, &pk, &circuit, &instance);
+    prove_verify::verify(&params, &vk, &proof, &instance).expect("verify_proof failed");
+
+    // tampering with the transcript must make verification fail
+    let mut tampered_proof = proof.clone();
+    *tampered_proof.last_mut().unwrap() ^= 1;
+    assert!(prove_verify::verify(&params, &vk, &tampered_proof, &instance).is_err());
+
+    // tampering with the public instance must make verification fail
+    let wrong_instance = vec![a5 + Fr::ONE];
+    assert!(prove_verify::verify(&params, &vk, &proof, &wrong_instance).is_err());
+
+    #[cfg(feature = "cost-estimator")]
+    {
+        analysis::report(8, &circuit, instance.len());
+        analysis::gates();
+        assert!(analysis::fits(8, &circuit, instance.clone()));
+    }
 }
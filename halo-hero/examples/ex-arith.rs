@@ -14,12 +14,19 @@ use halo2_proofs::{
         Error,
         Expression,
         Fixed,
+        Instance,
         Selector,
+        TableColumn,
     },
     poly::Rotation,
 };
 
-use ff::{Field, PrimeField};
+use ff::{Field, PrimeFieldBits};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+// Limb width for `ArithmeticChip::range_check`'s lookup table.
+const RANGE_LIMB_BITS: usize = 8;
 
 struct TestCircuit<F: Field> {
     _ph: PhantomData<F>,
@@ -79,6 +86,12 @@ struct ArithmeticChip<F: Field> {
     w0: Column<Advice>,
     w1: Column<Advice>,
     w2: Column<Advice>,
+    // `range_check`'s lookup table (all values `0..2^RANGE_LIMB_BITS`) and
+    // the selector gating `w0` into it, one limb per row.
+    range_table: TableColumn,
+    q_range: Selector,
+    // public inputs, bound to cells via `expose_public`.
+    instance: Column<Instance>,
 }
 
 impl<F: Field> ArithmeticChip<F> {
@@ -92,6 +105,7 @@ impl<F: Field> ArithmeticChip<F> {
         c2: Column<Fixed>,
         cm: Column<Fixed>,
         cc: Column<Fixed>,
+        instance: Column<Instance>,
     ) -> Self {
         let q_arith = meta.complex_selector();
 
@@ -122,6 +136,19 @@ impl<F: Field> ArithmeticChip<F> {
             vec![q_arith * expr]
         });
 
+        // `w0` doubles as the range-check limb cell: gated by `q_range`,
+        // every limb assigned there must appear in `range_table`. When
+        // `q_range` is off the expression collapses to `0`, which the
+        // table also contains, so this never constrains the arithmetic
+        // rows that otherwise live in `w0`.
+        let range_table = meta.lookup_table_column();
+        let q_range = meta.complex_selector();
+        meta.lookup("range_check_limb", |meta| {
+            let q_range = meta.query_selector(q_range);
+            let limb = meta.query_advice(w0, Rotation::cur());
+            vec![(q_range * limb, range_table)]
+        });
+
         Self {
             _ph: PhantomData,
             q_arith,
@@ -133,6 +160,9 @@ impl<F: Field> ArithmeticChip<F> {
             w0,
             w1,
             w2,
+            range_table,
+            q_range,
+            instance,
         }
     }
 
@@ -224,32 +254,135 @@ impl<F: Field> ArithmeticChip<F> {
         )
     }
 
-    /// Assert equal
+    /// Assert a `Variable` equals a compile-time constant, by loading the
+    /// constant into its own genuinely-constrained cell (`load_constant`)
+    /// and comparing the two as ordinary `Variable`s (`eq_var`).
     fn eq_consant(
         &self,
         layouter: &mut impl Layouter<F>,
         constant: F,
         variable: Variable<F>,
     ) -> Result<(), Error> {
+        let c = self.load_constant(layouter, constant)?;
+        self.eq_var(layouter, variable, c)
+    }
+
+    /// Allocates `c` into a cell bound by the arithmetic gate to the
+    /// fixed column `cc`, rather than merely folding it into a
+    /// `Variable`'s `add` term -- the resulting cell copy-constrains
+    /// like any other `Variable`, e.g. into `expose_public`.
+    fn load_constant(&self, layouter: &mut impl Layouter<F>, c: F) -> Result<Variable<F>, Error> {
         layouter.assign_region(
-            || "eq_constant",
+            || "load_constant",
             |mut region| {
-                // turn on the arithmetic gate
                 self.q_arith.enable(&mut region, 0)?;
 
-                variable
-                    .val
-                    .copy_advice(|| "val", &mut region, self.w0, 0)?;
-
-                let delta = variable.add - constant;
-
+                let val = region.assign_advice(|| "const", self.w0, 0, || Value::known(c))?;
                 region.assign_advice(|| "junk1", self.w1, 0, || Value::known(F::ZERO))?;
                 region.assign_advice(|| "junk2", self.w2, 0, || Value::known(F::ZERO))?;
 
-                region.assign_fixed(|| "c0", self.c0, 0, || Value::known(variable.mul))?;
+                region.assign_fixed(|| "c0", self.c0, 0, || Value::known(F::ONE))?;
+                region.assign_fixed(|| "c1", self.c1, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "c2", self.c2, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "cc", self.cc, 0, || Value::known(-c))?;
+                region.assign_fixed(|| "cm", self.cm, 0, || Value::known(F::ZERO))?;
+
+                Ok(Variable {
+                    mul: F::ONE,
+                    add: F::ZERO,
+                    val,
+                })
+            },
+        )
+    }
+
+    /// Materializes an affine `Variable`'s value into a fresh
+    /// raw-identity cell (`mul = F::ONE`, `add = F::ZERO`), via the same
+    /// row shape `add` uses. Needed wherever halo2 itself only lets us
+    /// copy-constrain raw cells, e.g. `expose_public`'s
+    /// `constrain_instance`.
+    fn materialize(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        var: Variable<F>,
+    ) -> Result<Variable<F>, Error> {
+        layouter.assign_region(
+            || "materialize",
+            |mut region| {
+                self.q_arith.enable(&mut region, 0)?;
+
+                var.val.copy_advice(|| "val", &mut region, self.w0, 0)?;
+                region.assign_advice(|| "junk", self.w1, 0, || Value::known(F::ZERO))?;
+                let val = region.assign_advice(|| "out", self.w2, 0, || var.value())?;
+
+                region.assign_fixed(|| "c0", self.c0, 0, || Value::known(var.mul))?;
                 region.assign_fixed(|| "c1", self.c1, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "c2", self.c2, 0, || Value::known(-F::ONE))?;
+                region.assign_fixed(|| "cc", self.cc, 0, || Value::known(var.add))?;
+                region.assign_fixed(|| "cm", self.cm, 0, || Value::known(F::ZERO))?;
+
+                Ok(Variable {
+                    mul: F::ONE,
+                    add: F::ZERO,
+                    val,
+                })
+            },
+        )
+    }
+
+    /// Binds `var` to the public instance column at absolute row `row`.
+    fn expose_public(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        var: Variable<F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        let var = self.materialize(layouter, var)?;
+        layouter.constrain_instance(var.val.cell(), self.instance, row)
+    }
+
+    /// Loads `range_check`'s lookup table with every value in
+    /// `0..2^RANGE_LIMB_BITS`. Must be called once per circuit synthesis,
+    /// before any `range_check` call.
+    fn load_range_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "range table",
+            |mut table| {
+                for value in 0..(1usize << RANGE_LIMB_BITS) {
+                    table.assign_cell(
+                        || "val_in_range",
+                        self.range_table,
+                        value,
+                        || Value::known(F::from(value as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Asserts two `Variable`s hold the same field element, via one
+    /// arithmetic-gate row computing `lhs - rhs == 0` -- the same shape
+    /// `eq_consant` uses, just with both sides variable.
+    fn eq_var(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        lhs: Variable<F>,
+        rhs: Variable<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "eq_var",
+            |mut region| {
+                self.q_arith.enable(&mut region, 0)?;
+
+                lhs.val.copy_advice(|| "lhs", &mut region, self.w0, 0)?;
+                rhs.val.copy_advice(|| "rhs", &mut region, self.w1, 0)?;
+                region.assign_advice(|| "junk", self.w2, 0, || Value::known(F::ZERO))?;
+
+                region.assign_fixed(|| "c0", self.c0, 0, || Value::known(lhs.mul))?;
+                region.assign_fixed(|| "c1", self.c1, 0, || Value::known(-rhs.mul))?;
                 region.assign_fixed(|| "c2", self.c2, 0, || Value::known(F::ZERO))?;
-                region.assign_fixed(|| "cc", self.cc, 0, || Value::known(delta))?;
+                region.assign_fixed(|| "cc", self.cc, 0, || Value::known(lhs.add - rhs.add))?;
                 region.assign_fixed(|| "cm", self.cm, 0, || Value::known(F::ZERO))?;
 
                 Ok(())
@@ -257,6 +390,104 @@ impl<F: Field> ArithmeticChip<F> {
         )
     }
 
+    /// Recombines `vars[0] + base*vars[1] + base^2*vars[2] + ...` into a
+    /// single `Variable`, via one `add`-shaped arithmetic-gate row per
+    /// element after the first. Shared by `range_check` (`base =
+    /// 2^RANGE_LIMB_BITS`) and `to_bits`/`from_bits` (`base = 2`).
+    fn weighted_sum(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        vars: &[Variable<F>],
+        base: F,
+    ) -> Result<Variable<F>, Error> {
+        let mut acc = vars[0].clone();
+        let mut power = F::ONE;
+        for var in vars.iter().skip(1) {
+            power *= base;
+            let scaled = Variable {
+                mul: var.mul * power,
+                add: var.add * power,
+                val: var.val.clone(),
+            };
+            acc = self.add(layouter, acc, scaled)?;
+        }
+        Ok(acc)
+    }
+
+    /// Proves `var` lies in `[0, 2^n_bits)` via a lookup-backed limb
+    /// decomposition: `var` is split into `ceil(n_bits / RANGE_LIMB_BITS)`
+    /// little-endian `RANGE_LIMB_BITS`-wide limbs, each looked up in
+    /// `range_table`, then recombined through the existing arithmetic gate
+    /// (one `add`-shaped row per limb, running the weighted sum as an
+    /// accumulator) and asserted equal to `var` via `eq_var`.
+    ///
+    /// Invariant: when `n_bits` is not a multiple of `RANGE_LIMB_BITS`, the
+    /// last limb is still checked against the full `RANGE_LIMB_BITS`-wide
+    /// table, so the effective bound is rounded up to the next multiple of
+    /// `RANGE_LIMB_BITS` rather than `2^n_bits` exactly -- an exact bound
+    /// needs either a second, narrower table for that limb or a
+    /// `check_bounded`-style excess check layered on top.
+    fn range_check(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        var: Variable<F>,
+        n_bits: usize,
+    ) -> Result<Variable<F>, Error>
+    where
+        F: PrimeFieldBits,
+    {
+        let num_limbs = (n_bits + RANGE_LIMB_BITS - 1) / RANGE_LIMB_BITS;
+
+        let limb_vals: Value<Vec<F>> = var.value().map(|v| {
+            let bits = v.to_le_bits();
+            bits.iter()
+                .take(num_limbs * RANGE_LIMB_BITS)
+                .collect::<Vec<_>>()
+                .chunks(RANGE_LIMB_BITS)
+                .map(|chunk| {
+                    let mut acc = 0u64;
+                    for (i, bit) in chunk.iter().enumerate() {
+                        if **bit {
+                            acc |= 1 << i;
+                        }
+                    }
+                    F::from(acc)
+                })
+                .collect()
+        });
+
+        let limb_cells = layouter.assign_region(
+            || "range_check limbs",
+            |mut region| {
+                let mut cells = Vec::with_capacity(num_limbs);
+                for i in 0..num_limbs {
+                    self.q_range.enable(&mut region, i)?;
+                    let cell = region.assign_advice(
+                        || format!("limb[{}]", i),
+                        self.w0,
+                        i,
+                        || limb_vals.as_ref().map(|l| l[i]),
+                    )?;
+                    cells.push(cell);
+                }
+                Ok(cells)
+            },
+        )?;
+
+        let limbs: Vec<Variable<F>> = limb_cells
+            .into_iter()
+            .map(|val| Variable {
+                mul: F::ONE,
+                add: F::ZERO,
+                val,
+            })
+            .collect();
+        let acc = self.weighted_sum(layouter, &limbs, F::from(1u64 << RANGE_LIMB_BITS))?;
+
+        self.eq_var(layouter, acc, var.clone())?;
+        Ok(var)
+    }
+
     // ANCHOR: bit
     /// Allocate a bit-constrained variable.
     fn bit(
@@ -291,8 +522,8 @@ impl<F: Field> ArithmeticChip<F> {
                 region.constrain_equal(w0.cell(), w1.cell())?;
 
                 region.assign_fixed(|| "c0", self.c0, 0, || Value::known(F::ZERO))?;
-                region.assign_fixed(|| "c1", self.c0, 0, || Value::known(-F::ONE))?;
-                region.assign_fixed(|| "c2", self.c0, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "c1", self.c1, 0, || Value::known(-F::ONE))?;
+                region.assign_fixed(|| "c2", self.c2, 0, || Value::known(F::ZERO))?;
                 region.assign_fixed(|| "cc", self.cc, 0, || Value::known(F::ZERO))?;
                 region.assign_fixed(|| "cm", self.cm, 0, || Value::known(F::ONE))?;
 
@@ -304,7 +535,459 @@ impl<F: Field> ArithmeticChip<F> {
             },
         )
     }
+
+    /// Decomposes `var` into `n` little-endian `bit`-constrained
+    /// `Variable`s whose weighted sum recomposes to `var`, checked via
+    /// `weighted_sum` and `eq_var`.
+    ///
+    /// Returns `Error::Synthesis` instead of silently wrapping when `n`
+    /// exceeds the field's bit capacity.
+    fn to_bits(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        var: Variable<F>,
+        n: usize,
+    ) -> Result<Vec<Variable<F>>, Error>
+    where
+        F: PrimeFieldBits,
+    {
+        if n > F::CAPACITY as usize {
+            return Err(Error::Synthesis);
+        }
+
+        let bit_vals: Value<Vec<bool>> = var.value().map(|v| {
+            let le_bits = v.to_le_bits();
+            le_bits.iter().take(n).map(|b| *b).collect()
+        });
+
+        let mut bits = Vec::with_capacity(n);
+        for i in 0..n {
+            bits.push(self.bit(layouter, bit_vals.as_ref().map(|b| b[i]))?);
+        }
+
+        let acc = self.weighted_sum(layouter, &bits, F::from(2u64))?;
+        self.eq_var(layouter, acc, var.clone())?;
+
+        Ok(bits)
+    }
+
+    /// Recomposes little-endian boolean `Variable`s (as produced by
+    /// `to_bits`, or individually via `bit`) into a single `Variable`,
+    /// via the same `weighted_sum` accumulator `to_bits` checks against.
+    fn from_bits(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        bits: &[Variable<F>],
+    ) -> Result<Variable<F>, Error> {
+        self.weighted_sum(layouter, bits, F::from(2u64))
+    }
+
+    /// Selects `a` when `cond = 1` and `b` when `cond = 0`, without
+    /// constraining `cond` to be boolean (callers that need that should
+    /// also run it through `bit`). Computed as `cond * (a - b) + b`: one
+    /// `add` row for `d = a - b`, one `mul` row for `cond * d`, and one
+    /// more `add` row to shift back by `b`.
+    fn select(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        cond: Variable<F>,
+        a: Variable<F>,
+        b: Variable<F>,
+    ) -> Result<Variable<F>, Error> {
+        let d = self.add(layouter, a, b.clone() * (-F::ONE))?;
+        let t = self.mul(layouter, cond, d)?;
+        self.add(layouter, t, b)
+    }
+
+    /// Conditionally swaps `a` and `b`: returns `(b, a)` when `cond = 1`
+    /// and `(a, b)` when `cond = 0`. Built from two `select` calls.
+    fn cond_swap(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        cond: Variable<F>,
+        a: Variable<F>,
+        b: Variable<F>,
+    ) -> Result<(Variable<F>, Variable<F>), Error> {
+        let left = self.select(layouter, cond.clone(), b.clone(), a.clone())?;
+        let right = self.select(layouter, cond, a, b)?;
+        Ok((left, right))
+    }
+
+    /// Re-asserts that a `Variable`'s value is `0` or `1`, via one
+    /// `v^2 - v = 0` row. Generalizes `bit`'s gate (which only ever sees
+    /// a raw-identity cell) to cover the affine `Variable`s the boolean
+    /// ops below can produce, e.g. `not`'s `1 - x`.
+    fn assert_bit(&self, layouter: &mut impl Layouter<F>, var: &Variable<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "assert_bit",
+            |mut region| {
+                self.q_arith.enable(&mut region, 0)?;
+
+                let w0 = var.val.copy_advice(|| "v0", &mut region, self.w0, 0)?;
+                let w1 = var.val.copy_advice(|| "v1", &mut region, self.w1, 0)?;
+                region.assign_advice(|| "junk", self.w2, 0, || Value::known(F::ZERO))?;
+                region.constrain_equal(w0.cell(), w1.cell())?;
+
+                // (mul*val + add)^2 - (mul*val + add)
+                //   = mul^2 * val^2 + (2*mul*add - mul) * val + (add^2 - add)
+                region.assign_fixed(
+                    || "c0",
+                    self.c0,
+                    0,
+                    || Value::known(F::from(2u64) * var.mul * var.add - var.mul),
+                )?;
+                region.assign_fixed(|| "c1", self.c1, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "c2", self.c2, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(
+                    || "cc",
+                    self.cc,
+                    0,
+                    || Value::known(var.add * var.add - var.add),
+                )?;
+                region.assign_fixed(|| "cm", self.cm, 0, || Value::known(var.mul * var.mul))?;
+
+                Ok(())
+            },
+        )
+    }
+
+    /// Shared row for `or`/`xor`: computes `x + y + k*(x*y)` for a
+    /// caller-supplied product coefficient `k` (`-1` for `or`, `-2` for
+    /// `xor`). Assumes `x`/`y` are raw-identity `Variable`s (`mul =
+    /// F::ONE`, `add = F::ZERO`), as `bit` and the other boolean ops
+    /// produce.
+    fn linear_or_xor(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        x: Variable<F>,
+        y: Variable<F>,
+        k: F,
+    ) -> Result<Variable<F>, Error> {
+        layouter.assign_region(
+            || "linear_or_xor",
+            |mut region| {
+                self.q_arith.enable(&mut region, 0)?;
+
+                x.val.copy_advice(|| "x", &mut region, self.w0, 0)?;
+                y.val.copy_advice(|| "y", &mut region, self.w1, 0)?;
+
+                let val = region.assign_advice(
+                    || "out",
+                    self.w2,
+                    0,
+                    || x.value() + y.value() + Value::known(k) * x.value() * y.value(),
+                )?;
+
+                region.assign_fixed(|| "c0", self.c0, 0, || Value::known(F::ONE))?;
+                region.assign_fixed(|| "c1", self.c1, 0, || Value::known(F::ONE))?;
+                region.assign_fixed(|| "c2", self.c2, 0, || Value::known(-F::ONE))?;
+                region.assign_fixed(|| "cc", self.cc, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "cm", self.cm, 0, || Value::known(k))?;
+
+                Ok(Variable {
+                    mul: F::ONE,
+                    add: F::ZERO,
+                    val,
+                })
+            },
+        )
+    }
+
+    /// Logical AND of two boolean `Variable`s: `x*y`, the same row `mul`
+    /// already produces for raw-identity inputs (`cm = 1`). The result
+    /// is re-asserted as a bit so it composes safely into further
+    /// boolean ops.
+    fn and(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        x: Variable<F>,
+        y: Variable<F>,
+    ) -> Result<Variable<F>, Error> {
+        let out = self.mul(layouter, x, y)?;
+        self.assert_bit(layouter, &out)?;
+        Ok(out)
+    }
+
+    /// Logical OR: `x + y - xy`, one `linear_or_xor` row (`k = -1`),
+    /// re-asserted as a bit.
+    fn or(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        x: Variable<F>,
+        y: Variable<F>,
+    ) -> Result<Variable<F>, Error> {
+        let out = self.linear_or_xor(layouter, x, y, -F::ONE)?;
+        self.assert_bit(layouter, &out)?;
+        Ok(out)
+    }
+
+    /// Logical XOR: `x + y - 2xy`, one `linear_or_xor` row (`k = -2`),
+    /// re-asserted as a bit.
+    fn xor(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        x: Variable<F>,
+        y: Variable<F>,
+    ) -> Result<Variable<F>, Error> {
+        let out = self.linear_or_xor(layouter, x, y, -F::from(2u64))?;
+        self.assert_bit(layouter, &out)?;
+        Ok(out)
+    }
+
+    /// Logical NOT: `1 - x`, the affine `Mul`/`Add` already on
+    /// `Variable` (no new row for the value itself), with the result
+    /// re-asserted as a bit so it composes safely into further boolean
+    /// ops.
+    fn not(&self, layouter: &mut impl Layouter<F>, x: Variable<F>) -> Result<Variable<F>, Error> {
+        let out = (x * (-F::ONE)) + F::ONE;
+        self.assert_bit(layouter, &out)?;
+        Ok(out)
+    }
+}
+
+// A `Variable`-compatible Poseidon sponge, built entirely out of
+// `ArithmeticChip`'s single generic gate rather than a dedicated
+// selector of its own -- so it composes with `select`/`cond_swap`/the
+// boolean ops above the same way: everything is just `Variable<F>` in,
+// `Variable<F>` out. Round constants and the MDS matrix are generated
+// from a seeded RNG rather than a real Grain LFSR, matching
+// `session-9.rs`'s Poseidon chip -- this toy instance makes no security
+// claim.
+mod poseidon {
+    use super::*;
+
+    /// Poseidon parameters for a state of width `T` (rate `T - 1`):
+    /// `R_F` full rounds (S-box on every lane) and `R_P` partial rounds
+    /// (S-box on lane 0 only), split half-before/half-after the partial
+    /// rounds, as in real Poseidon.
+    pub trait Spec<F: Field, const T: usize> {
+        const R_F: usize;
+        const R_P: usize;
+
+        fn round_constants() -> Vec<[F; T]>;
+        fn mds() -> [[F; T]; T];
+    }
+
+    /// A toy width-3 `Spec`: round constants and the (Cauchy) MDS matrix
+    /// are pseudorandom, seeded the same way `session-9.rs`'s Poseidon
+    /// chip seeds its own -- not derived from a real Grain LFSR.
+    pub struct Toy3;
+
+    impl<F: Field> Spec<F, 3> for Toy3 {
+        const R_F: usize = 8;
+        const R_P: usize = 16;
+
+        fn round_constants() -> Vec<[F; 3]> {
+            let mut rng = ChaCha8Rng::seed_from_u64(0x517cc1b727220a95);
+            (0..Self::R_F + Self::R_P)
+                .map(|_| [(); 3].map(|_| F::random(&mut rng)))
+                .collect()
+        }
+
+        fn mds() -> [[F; 3]; 3] {
+            let mut rng = ChaCha8Rng::seed_from_u64(0x9e3779b97f4a7c15);
+            let xi: [F; 3] = [(); 3].map(|_| F::random(&mut rng));
+            let yi: [F; 3] = [(); 3].map(|_| F::random(&mut rng));
+            let mut matrix = [[F::ZERO; 3]; 3];
+            for i in 0..3 {
+                for j in 0..3 {
+                    matrix[i][j] = (xi[i] + yi[j]).invert().unwrap();
+                }
+            }
+            matrix
+        }
+    }
+
+    // `ConstantLength` domain separation: fold the absorbed length into
+    // the capacity lane once, up front, mirroring `session-9.rs`'s
+    // Poseidon chip.
+    fn domain_tag<F: Field>(len: usize) -> F {
+        F::from(len as u64)
+    }
+
+    /// A Poseidon sponge built on top of an existing `ArithmeticChip`:
+    /// the S-box is three `mul` calls (three rows: square, square,
+    /// multiply) and the MDS mix is a chained `add` accumulation per
+    /// lane, reusing the same `cm`/`c0`/`c1`/`c2` coefficients every
+    /// other gadget in this file does.
+    pub struct PoseidonChip<F: Field, S, const T: usize> {
+        arith: ArithmeticChip<F>,
+        mds: [[F; T]; T],
+        round_constants: Vec<[F; T]>,
+        _spec: PhantomData<S>,
+    }
+
+    impl<F: Field, const T: usize, S: Spec<F, T>> PoseidonChip<F, S, T> {
+        pub fn new(arith: ArithmeticChip<F>) -> Self {
+            Self {
+                arith,
+                mds: S::mds(),
+                round_constants: S::round_constants(),
+                _spec: PhantomData,
+            }
+        }
+
+        /// `x^5`: square (`x^2`), square again (`x^4`), then multiply by
+        /// `x` (`x^5`) -- three arithmetic-gate rows.
+        fn sbox(
+            &self,
+            layouter: &mut impl Layouter<F>,
+            x: Variable<F>,
+        ) -> Result<Variable<F>, Error> {
+            let x2 = self.arith.mul(layouter, x.clone(), x.clone())?;
+            let x4 = self.arith.mul(layouter, x2.clone(), x2)?;
+            self.arith.mul(layouter, x4, x)
+        }
+
+        /// `state' = M . state`: one chained-`add` accumulation per
+        /// output lane. Scaling a `Variable` by a compile-time constant
+        /// is free (the same affine trick `weighted_sum` uses), so each
+        /// lane costs `T - 1` rows.
+        fn mix(
+            &self,
+            layouter: &mut impl Layouter<F>,
+            state: &[Variable<F>; T],
+        ) -> Result<[Variable<F>; T], Error> {
+            let scale = |var: &Variable<F>, c: F| Variable {
+                mul: var.mul * c,
+                add: var.add * c,
+                val: var.val.clone(),
+            };
+
+            let mut out = Vec::with_capacity(T);
+            for row in &self.mds {
+                let mut acc = scale(&state[0], row[0]);
+                for (i, var) in state.iter().enumerate().skip(1) {
+                    let term = scale(var, row[i]);
+                    acc = self.arith.add(layouter, acc, term)?;
+                }
+                out.push(acc);
+            }
+            Ok(out.try_into().unwrap_or_else(|_| unreachable!()))
+        }
+
+        /// One full Poseidon permutation: `R_F + R_P` rounds of (1)
+        /// adding the round constants (free, affine), (2) the `x^5`
+        /// S-box on every lane in full rounds or just lane 0 in partial
+        /// rounds, and (3) the MDS mix.
+        fn permute(
+            &self,
+            layouter: &mut impl Layouter<F>,
+            mut state: [Variable<F>; T],
+        ) -> Result<[Variable<F>; T], Error> {
+            let half_full = S::R_F / 2;
+            for (round, rc) in self.round_constants.iter().enumerate() {
+                for (lane, c) in state.iter_mut().zip(rc.iter()) {
+                    *lane = lane.clone() + *c;
+                }
+
+                let full = round < half_full || round >= half_full + S::R_P;
+                if full {
+                    for lane in state.iter_mut() {
+                        *lane = self.sbox(layouter, lane.clone())?;
+                    }
+                } else {
+                    state[0] = self.sbox(layouter, state[0].clone())?;
+                }
+
+                state = self.mix(layouter, &state)?;
+            }
+            Ok(state)
+        }
+
+        /// A `ConstantLength`-padded sponge hash: absorbs `inputs` in
+        /// `T - 1`-sized chunks (the last one implicitly zero-padded),
+        /// running the permutation between chunks, then squeezes the
+        /// capacity lane as the single output element.
+        pub fn hash(
+            &self,
+            layouter: &mut impl Layouter<F>,
+            inputs: &[Variable<F>],
+        ) -> Result<Variable<F>, Error> {
+            let rate = T - 1;
+
+            let mut state = Vec::with_capacity(T);
+            state.push(
+                self.arith
+                    .load_constant(layouter, domain_tag(inputs.len()))?,
+            );
+            for _ in 1..T {
+                state.push(self.arith.load_constant(layouter, F::ZERO)?);
+            }
+            let mut state: [Variable<F>; T] = state.try_into().unwrap_or_else(|_| unreachable!());
+
+            if inputs.is_empty() {
+                state = self.permute(layouter, state)?;
+                return Ok(state[0].clone());
+            }
+
+            for chunk in inputs.chunks(rate) {
+                for (i, val) in chunk.iter().enumerate() {
+                    state[1 + i] = self
+                        .arith
+                        .add(layouter, state[1 + i].clone(), val.clone())?;
+                }
+                state = self.permute(layouter, state)?;
+            }
+
+            Ok(state[0].clone())
+        }
+    }
+
+    /// Host-side mirror of `PoseidonChip::hash`, for callers (like
+    /// `main`) that need the expected output without building a
+    /// circuit.
+    pub fn hash_host<F: Field, S: Spec<F, T>, const T: usize>(inputs: &[F]) -> F {
+        let mds = S::mds();
+        let rc = S::round_constants();
+        let rate = T - 1;
+        let half_full = S::R_F / 2;
+
+        let permute = |mut state: [F; T]| -> [F; T] {
+            for (round, round_rc) in rc.iter().enumerate() {
+                for (lane, c) in state.iter_mut().zip(round_rc.iter()) {
+                    *lane = *lane + *c;
+                }
+
+                let full = round < half_full || round >= half_full + S::R_P;
+                if full {
+                    for lane in state.iter_mut() {
+                        *lane = *lane * *lane * *lane * *lane * *lane;
+                    }
+                } else {
+                    state[0] = state[0] * state[0] * state[0] * state[0] * state[0];
+                }
+
+                let mut next = [F::ZERO; T];
+                for (i, row) in mds.iter().enumerate() {
+                    let mut acc = F::ZERO;
+                    for (j, m) in row.iter().enumerate() {
+                        acc = acc + *m * state[j];
+                    }
+                    next[i] = acc;
+                }
+                state = next;
+            }
+            state
+        };
+
+        let mut state = [F::ZERO; T];
+        state[0] = domain_tag(inputs.len());
+
+        if inputs.is_empty() {
+            return permute(state)[0];
+        }
+
+        for chunk in inputs.chunks(rate) {
+            for (i, v) in chunk.iter().enumerate() {
+                state[1 + i] = state[1 + i] + *v;
+            }
+            state = permute(state);
+        }
+        state[0]
+    }
 }
+use poseidon::{PoseidonChip, Toy3};
 
 #[derive(Clone, Debug)]
 struct TestConfig<F: Field + Clone> {
@@ -312,7 +995,7 @@ struct TestConfig<F: Field + Clone> {
     arithmetic_chip: ArithmeticChip<F>,
 }
 
-impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
+impl<F: PrimeFieldBits> Circuit<F> for TestCircuit<F> {
     type Config = TestConfig<F>;
     type FloorPlanner = SimpleFloorPlanner;
 
@@ -335,12 +1018,16 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
         let cc = meta.fixed_column();
         let cm = meta.fixed_column();
 
+        let instance = meta.instance_column();
+
         // enable equality constraints
         meta.enable_equality(w0);
         meta.enable_equality(w1);
         meta.enable_equality(w2);
+        meta.enable_equality(instance);
 
-        let arithmetic_chip = ArithmeticChip::configure(meta, w0, w1, w2, c0, c1, c2, cc, cm);
+        let arithmetic_chip =
+            ArithmeticChip::configure(meta, w0, w1, w2, c0, c1, c2, cc, cm, instance);
 
         TestConfig {
             _ph: PhantomData,
@@ -353,10 +1040,94 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
         config: Self::Config, //
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
+        config.arithmetic_chip.load_range_table(&mut layouter)?;
+
         let a1 = config
             .arithmetic_chip
             .free(&mut layouter, self.secret.clone())?;
 
+        // `secret` is claimed to fit in 16 bits; a witness that doesn't
+        // makes this lookup-backed check fail.
+        config
+            .arithmetic_chip
+            .range_check(&mut layouter, a1.clone(), 16)?;
+
+        // round-trip `a1` through `to_bits`/`from_bits` and check the
+        // recomposition matches the original variable.
+        let a1_bits = config
+            .arithmetic_chip
+            .to_bits(&mut layouter, a1.clone(), 16)?;
+        let a1_recomposed = config.arithmetic_chip.from_bits(&mut layouter, &a1_bits)?;
+        config
+            .arithmetic_chip
+            .eq_var(&mut layouter, a1_recomposed, a1.clone())?;
+
+        // `select`/`cond_swap` demo: with `cond = 1`, `select` picks its
+        // first argument and `cond_swap` swaps its two inputs.
+        let cond_true = config
+            .arithmetic_chip
+            .bit(&mut layouter, Value::known(true))?;
+        let selected = config.arithmetic_chip.select(
+            &mut layouter,
+            cond_true.clone(),
+            a1.clone(),
+            a1_recomposed.clone(),
+        )?;
+        config
+            .arithmetic_chip
+            .eq_var(&mut layouter, selected, a1.clone())?;
+
+        let zero = config
+            .arithmetic_chip
+            .free(&mut layouter, Value::known(F::ZERO))?;
+        let (swapped_lo, swapped_hi) =
+            config
+                .arithmetic_chip
+                .cond_swap(&mut layouter, cond_true, a1.clone(), zero.clone())?;
+        config
+            .arithmetic_chip
+            .eq_var(&mut layouter, swapped_lo, zero)?;
+        config
+            .arithmetic_chip
+            .eq_var(&mut layouter, swapped_hi, a1.clone())?;
+
+        // boolean logic demo: `true AND false = false`,
+        // `true XOR false = true`, `NOT true = false`.
+        let bit_true = config
+            .arithmetic_chip
+            .bit(&mut layouter, Value::known(true))?;
+        let bit_false = config
+            .arithmetic_chip
+            .bit(&mut layouter, Value::known(false))?;
+
+        let anded =
+            config
+                .arithmetic_chip
+                .and(&mut layouter, bit_true.clone(), bit_false.clone())?;
+        config
+            .arithmetic_chip
+            .eq_var(&mut layouter, anded, bit_false.clone())?;
+
+        let ored = config
+            .arithmetic_chip
+            .or(&mut layouter, bit_true.clone(), bit_false.clone())?;
+        config
+            .arithmetic_chip
+            .eq_var(&mut layouter, ored, bit_true.clone())?;
+
+        let xored =
+            config
+                .arithmetic_chip
+                .xor(&mut layouter, bit_true.clone(), bit_false.clone())?;
+        config
+            .arithmetic_chip
+            .eq_var(&mut layouter, xored, bit_true.clone())?;
+
+        let negated = config.arithmetic_chip.not(&mut layouter, bit_true)?;
+        config
+            .arithmetic_chip
+            .eq_var(&mut layouter, negated, bit_false)?;
+
         let a2 = config
             .arithmetic_chip
             .add(&mut layouter, a1.clone(), a1.clone())?;
@@ -365,9 +1136,21 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
             .arithmetic_chip
             .mul(&mut layouter, a1.clone(), a2.clone())?;
 
+        config.arithmetic_chip.eq_consant(
+            &mut layouter,
+            F::from_u128(1337 * (1337 + 1337)),
+            a3.clone(),
+        )?;
+
+        // expose `a3` so the verifier can check it against a public input.
+        config.arithmetic_chip.expose_public(&mut layouter, a3, 0)?;
+
+        // hash `a1` and expose the digest as a second public input.
+        let poseidon_chip = PoseidonChip::<F, Toy3, 3>::new(config.arithmetic_chip.clone());
+        let digest = poseidon_chip.hash(&mut layouter, &[a1.clone()])?;
         config
             .arithmetic_chip
-            .eq_consant(&mut layouter, F::from_u128(1337 * (1337 + 1337)), a3)?;
+            .expose_public(&mut layouter, digest, 1)?;
 
         Ok(())
     }
@@ -381,6 +1164,27 @@ fn main() {
         _ph: PhantomData,
         secret: Value::known(Fr::from(1337u64)),
     };
-    let prover = MockProver::run(8, &circuit, vec![]).unwrap();
+    let secret = Fr::from(1337u64);
+    let instance = vec![
+        secret * (secret + secret),
+        poseidon::hash_host::<Fr, Toy3, 3>(&[secret]),
+    ];
+    let prover = MockProver::run(10, &circuit, vec![instance]).unwrap();
     prover.verify().unwrap();
+
+    // `secret` no longer fits in 16 bits, so `range_check` rejects it
+    // (the rest of the circuit's arithmetic also no longer matches the
+    // compile-time constant `eq_consant` checks against, but the range
+    // check alone is already enough to fail `verify`).
+    let bad_circuit = TestCircuit::<Fr> {
+        _ph: PhantomData,
+        secret: Value::known(Fr::from(1u64 << 20)),
+    };
+    let bad_secret = Fr::from(1u64 << 20);
+    let bad_instance = vec![
+        bad_secret * (bad_secret + bad_secret),
+        poseidon::hash_host::<Fr, Toy3, 3>(&[bad_secret]),
+    ];
+    let prover = MockProver::run(10, &bad_circuit, vec![bad_instance]).unwrap();
+    assert!(prover.verify().is_err());
 }
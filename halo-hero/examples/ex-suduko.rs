@@ -5,16 +5,18 @@ use std::{
 };
 
 use halo2_proofs::{
-    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    circuit::{AssignedCell, Chip, Layouter, SimpleFloorPlanner, Value},
     dev::MockProver,
     plonk::{
         Advice, Challenge, Circuit, Column, ConstraintSystem, Error, Expression, FirstPhase, Fixed,
-        SecondPhase, Selector,
+        Instance, SecondPhase, Selector, TableColumn,
     },
     poly::Rotation,
 };
 
 use ff::{Field, PrimeField};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 
 const DIM: usize = 9;
 const SQR: usize = 3;
@@ -33,7 +35,6 @@ const SUDUKO: [[u8; DIM]; DIM] = [
 
 struct TestCircuit<F: Field> {
     _ph: PhantomData<F>,
-    suduko: [[u8; DIM]; DIM],
     solutation: Value<[[u8; DIM]; DIM]>,
 }
 
@@ -142,8 +143,94 @@ impl<F: Field> ChallengeChip<F> {
     }
 }
 
+// ANCHOR: arithmetic_instructions
+// Gadget/chip separation, following the pattern used by the ECC gadgets:
+// gadgets such as `eval_vanish` are written generically against
+// `impl ArithmeticInstructions<F>` instead of the concrete
+// `ArithmeticChip` (the Poseidon chip below still holds a concrete
+// `ArithmeticChip`), so the same logic can be re-instantiated over the
+// second-phase chip (or any future backend chip) without duplication.
+trait ArithmeticInstructions<F: Field>: Chip<F> {
+    /// Multiply two variables
+    fn mul(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        lhs: &Variable<F>,
+        rhs: &Variable<F>,
+    ) -> Result<Variable<F>, Error>;
+
+    /// Add two variables
+    fn add(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        lhs: &Variable<F>,
+        rhs: &Variable<F>,
+    ) -> Result<Variable<F>, Error>;
+
+    fn sub(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        lhs: &Variable<F>,
+        rhs: &Variable<F>,
+    ) -> Result<Variable<F>, Error>;
+
+    /// Allocate a free variable.
+    fn free(&self, layouter: &mut impl Layouter<F>, value: Value<F>) -> Result<Variable<F>, Error>;
+
+    fn constant(&self, layouter: &mut impl Layouter<F>, constant: F) -> Result<Variable<F>, Error>;
+
+    fn eq(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        lhs: &Variable<F>,
+        rhs: &Variable<F>,
+    ) -> Result<(), Error>;
+
+    /// Assert equal
+    fn eq_consant(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        constant: F,
+        variable: Variable<F>,
+    ) -> Result<(), Error>;
+
+    /// Materialize `variable`'s affine value (`mul * val + add`) into a
+    /// fresh cell with no affine offset, so it can be copy-constrained
+    /// elsewhere (e.g. against a public instance cell, which only ever
+    /// constrains a raw cell, not an affine transform of one).
+    fn normalize(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        variable: &Variable<F>,
+    ) -> Result<Variable<F>, Error>;
+
+    /// Constrain `variable` equal to the public input at `row` of `instance`.
+    fn eq_instance(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        variable: &Variable<F>,
+        instance: Column<Instance>,
+        row: usize,
+    ) -> Result<(), Error>;
+
+    /// Evaluate the vanishing polynomial `prod_i (term_i - alpha)` as one
+    /// running-product region, instead of a chain of individual `mul`
+    /// regions. Each term (and `alpha`) is normalized first so its raw cell
+    /// can be copy-constrained into the accumulator region.
+    fn grand_product(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        alpha: &Variable<F>,
+        terms: &[Variable<F>],
+    ) -> Result<Variable<F>, Error>;
+
+    /// Allocate a bit-constrained variable.
+    fn bit(&self, layouter: &mut impl Layouter<F>, value: Value<bool>) -> Result<Variable<F>, Error>;
+}
+// ANCHOR_END: arithmetic_instructions
+
 #[derive(Clone, Debug)]
-struct ArithmeticChip<F: Field> {
+struct ArithmeticConfig<F: Field> {
     _ph: PhantomData<F>,
     q_arith: Selector,
     cm: Column<Fixed>,
@@ -154,6 +241,28 @@ struct ArithmeticChip<F: Field> {
     w0: Column<Advice>,
     w1: Column<Advice>,
     w2: Column<Advice>,
+    q_gp: Selector,
+    gp_term: Column<Advice>,
+    gp_alpha: Column<Advice>,
+    gp_acc: Column<Advice>,
+}
+
+#[derive(Clone, Debug)]
+struct ArithmeticChip<F: Field> {
+    config: ArithmeticConfig<F>,
+}
+
+impl<F: Field> Chip<F> for ArithmeticChip<F> {
+    type Config = ArithmeticConfig<F>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
 }
 
 impl<F: Field> ArithmeticChip<F> {
@@ -197,46 +306,78 @@ impl<F: Field> ArithmeticChip<F> {
             vec![q_arith * expr]
         });
 
+        // ANCHOR: grand_product_gate
+        // a dedicated running-product accumulator: one n+1-row region with
+        // z[0] = 1 and z[i+1] = z[i] * (term[i] - alpha), so a whole
+        // vanishing-polynomial evaluation costs one region instead of one
+        // `mul` region per term
+        let q_gp = meta.complex_selector();
+        let gp_term = meta.advice_column();
+        let gp_alpha = meta.advice_column();
+        let gp_acc = meta.advice_column();
+
+        meta.enable_equality(gp_term);
+        meta.enable_equality(gp_alpha);
+        meta.enable_equality(gp_acc);
+
+        meta.create_gate("grand-product", |meta| {
+            let q_gp = meta.query_selector(q_gp);
+            let term = meta.query_advice(gp_term, Rotation::cur());
+            let alpha = meta.query_advice(gp_alpha, Rotation::cur());
+            let acc_cur = meta.query_advice(gp_acc, Rotation::cur());
+            let acc_next = meta.query_advice(gp_acc, Rotation::next());
+            vec![q_gp * (acc_next - acc_cur * (term - alpha))]
+        });
+        // ANCHOR_END: grand_product_gate
+
         Self {
-            _ph: PhantomData,
-            q_arith,
-            cm,
-            c0,
-            c1,
-            c2,
-            cc,
-            w0,
-            w1,
-            w2,
+            config: ArithmeticConfig {
+                _ph: PhantomData,
+                q_arith,
+                cm,
+                c0,
+                c1,
+                c2,
+                cc,
+                w0,
+                w1,
+                w2,
+                q_gp,
+                gp_term,
+                gp_alpha,
+                gp_acc,
+            },
         }
     }
+}
 
-    /// Multiply two variables
+impl<F: PrimeField> ArithmeticInstructions<F> for ArithmeticChip<F> {
     fn mul(
         &self,
         layouter: &mut impl Layouter<F>,
         lhs: &Variable<F>,
         rhs: &Variable<F>,
     ) -> Result<Variable<F>, Error> {
+        let config = self.config();
         layouter.assign_region(
             || "mul",
             |mut region| {
                 // turn on the arithmetic gate
-                self.q_arith.enable(&mut region, 0)?;
+                config.q_arith.enable(&mut region, 0)?;
 
                 // (c0 * w0 + cc1) * (c1 * w1 + cc2)
                 // c0 * c1 * (w0 * w1) + c0 * cc2 * w0 + c1 * cc1 * w1 + cc1 * cc2
-                lhs.val.copy_advice(|| "lhs", &mut region, self.w0, 0)?;
-                rhs.val.copy_advice(|| "rhs", &mut region, self.w1, 0)?;
+                lhs.val.copy_advice(|| "lhs", &mut region, config.w0, 0)?;
+                rhs.val.copy_advice(|| "rhs", &mut region, config.w1, 0)?;
 
                 let val =
-                    region.assign_advice(|| "res", self.w2, 0, || lhs.value() * rhs.value())?;
+                    region.assign_advice(|| "res", config.w2, 0, || lhs.value() * rhs.value())?;
 
-                region.assign_fixed(|| "c0", self.c0, 0, || Value::known(lhs.mul * rhs.add))?;
-                region.assign_fixed(|| "c1", self.c1, 0, || Value::known(rhs.mul * lhs.add))?;
-                region.assign_fixed(|| "c2", self.c2, 0, || Value::known(-F::ONE))?;
-                region.assign_fixed(|| "cc", self.cc, 0, || Value::known(lhs.add * rhs.add))?;
-                region.assign_fixed(|| "cm", self.cm, 0, || Value::known(lhs.mul * rhs.mul))?;
+                region.assign_fixed(|| "c0", config.c0, 0, || Value::known(lhs.mul * rhs.add))?;
+                region.assign_fixed(|| "c1", config.c1, 0, || Value::known(rhs.mul * lhs.add))?;
+                region.assign_fixed(|| "c2", config.c2, 0, || Value::known(-F::ONE))?;
+                region.assign_fixed(|| "cc", config.cc, 0, || Value::known(lhs.add * rhs.add))?;
+                region.assign_fixed(|| "cm", config.cm, 0, || Value::known(lhs.mul * rhs.mul))?;
 
                 Ok(Variable {
                     mul: F::ONE,
@@ -247,30 +388,30 @@ impl<F: Field> ArithmeticChip<F> {
         )
     }
 
-    /// Add two variables
     fn add(
         &self,
         layouter: &mut impl Layouter<F>,
         lhs: &Variable<F>,
         rhs: &Variable<F>,
     ) -> Result<Variable<F>, Error> {
+        let config = self.config();
         layouter.assign_region(
             || "add",
             |mut region| {
                 // turn on the arithmetic gate
-                self.q_arith.enable(&mut region, 0)?;
+                config.q_arith.enable(&mut region, 0)?;
 
-                lhs.val.copy_advice(|| "lhs", &mut region, self.w0, 0)?;
-                rhs.val.copy_advice(|| "rhs", &mut region, self.w1, 0)?;
+                lhs.val.copy_advice(|| "lhs", &mut region, config.w0, 0)?;
+                rhs.val.copy_advice(|| "rhs", &mut region, config.w1, 0)?;
 
                 let val =
-                    region.assign_advice(|| "res", self.w2, 0, || lhs.value() + rhs.value())?;
+                    region.assign_advice(|| "res", config.w2, 0, || lhs.value() + rhs.value())?;
 
-                region.assign_fixed(|| "c0", self.c0, 0, || Value::known(lhs.mul))?;
-                region.assign_fixed(|| "c1", self.c1, 0, || Value::known(rhs.mul))?;
-                region.assign_fixed(|| "c2", self.c2, 0, || Value::known(-F::ONE))?;
-                region.assign_fixed(|| "cc", self.cc, 0, || Value::known(lhs.add + rhs.add))?;
-                region.assign_fixed(|| "cm", self.cm, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "c0", config.c0, 0, || Value::known(lhs.mul))?;
+                region.assign_fixed(|| "c1", config.c1, 0, || Value::known(rhs.mul))?;
+                region.assign_fixed(|| "c2", config.c2, 0, || Value::known(-F::ONE))?;
+                region.assign_fixed(|| "cc", config.cc, 0, || Value::known(lhs.add + rhs.add))?;
+                region.assign_fixed(|| "cm", config.cm, 0, || Value::known(F::ZERO))?;
 
                 Ok(Variable {
                     mul: F::ONE,
@@ -291,15 +432,15 @@ impl<F: Field> ArithmeticChip<F> {
         self.add(layouter, lhs, &minus)
     }
 
-    /// Allocate a free variable.
     fn free(&self, layouter: &mut impl Layouter<F>, value: Value<F>) -> Result<Variable<F>, Error> {
+        let config = self.config();
         layouter.assign_region(
             || "free",
             |mut region| {
                 // no need to turn on anything
-                let val = region.assign_advice(|| "free", self.w0, 0, || value)?;
-                region.assign_advice(|| "junk1", self.w1, 0, || Value::known(F::ZERO))?;
-                region.assign_advice(|| "junk2", self.w2, 0, || Value::known(F::ZERO))?;
+                let val = region.assign_advice(|| "free", config.w0, 0, || value)?;
+                region.assign_advice(|| "junk1", config.w1, 0, || Value::known(F::ZERO))?;
+                region.assign_advice(|| "junk2", config.w2, 0, || Value::known(F::ZERO))?;
                 Ok(Variable {
                     mul: F::ONE,
                     add: F::ZERO,
@@ -310,21 +451,22 @@ impl<F: Field> ArithmeticChip<F> {
     }
 
     fn constant(&self, layouter: &mut impl Layouter<F>, constant: F) -> Result<Variable<F>, Error> {
+        let config = self.config();
         layouter.assign_region(
             || "constant",
             |mut region| {
                 // turn on the arithmetic gate
-                self.q_arith.enable(&mut region, 0)?;
+                config.q_arith.enable(&mut region, 0)?;
 
-                let val = region.assign_advice(|| "val", self.w0, 0, || Value::known(constant))?;
-                region.assign_advice(|| "junk1", self.w1, 0, || Value::known(F::ZERO))?;
-                region.assign_advice(|| "junk2", self.w2, 0, || Value::known(F::ZERO))?;
+                let val = region.assign_advice(|| "val", config.w0, 0, || Value::known(constant))?;
+                region.assign_advice(|| "junk1", config.w1, 0, || Value::known(F::ZERO))?;
+                region.assign_advice(|| "junk2", config.w2, 0, || Value::known(F::ZERO))?;
 
-                region.assign_fixed(|| "c0", self.c0, 0, || Value::known(F::ONE))?;
-                region.assign_fixed(|| "c1", self.c1, 0, || Value::known(F::ZERO))?;
-                region.assign_fixed(|| "c2", self.c2, 0, || Value::known(F::ZERO))?;
-                region.assign_fixed(|| "cc", self.cc, 0, || Value::known(-constant))?;
-                region.assign_fixed(|| "cm", self.cm, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "c0", config.c0, 0, || Value::known(F::ONE))?;
+                region.assign_fixed(|| "c1", config.c1, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "c2", config.c2, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "cc", config.cc, 0, || Value::known(-constant))?;
+                region.assign_fixed(|| "cm", config.cm, 0, || Value::known(F::ZERO))?;
 
                 Ok(Variable {
                     mul: F::ONE,
@@ -341,98 +483,180 @@ impl<F: Field> ArithmeticChip<F> {
         lhs: &Variable<F>,
         rhs: &Variable<F>,
     ) -> Result<(), Error> {
+        let config = self.config();
         layouter.assign_region(
             || "eq",
             |mut region| {
                 // turn on the arithmetic gate
-                self.q_arith.enable(&mut region, 0)?;
+                config.q_arith.enable(&mut region, 0)?;
 
-                lhs.val.copy_advice(|| "lhs", &mut region, self.w0, 0)?;
-                rhs.val.copy_advice(|| "rhs", &mut region, self.w1, 0)?;
-                region.assign_advice(|| "junk2", self.w2, 0, || Value::known(F::ZERO))?;
+                lhs.val.copy_advice(|| "lhs", &mut region, config.w0, 0)?;
+                rhs.val.copy_advice(|| "rhs", &mut region, config.w1, 0)?;
+                region.assign_advice(|| "junk2", config.w2, 0, || Value::known(F::ZERO))?;
 
                 let delta = lhs.add - rhs.add;
 
-                region.assign_fixed(|| "c0", self.c0, 0, || Value::known(lhs.mul))?;
-                region.assign_fixed(|| "c1", self.c1, 0, || Value::known(-rhs.mul))?;
-                region.assign_fixed(|| "c2", self.c2, 0, || Value::known(F::ZERO))?;
-                region.assign_fixed(|| "cc", self.cc, 0, || Value::known(delta))?;
-                region.assign_fixed(|| "cm", self.cm, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "c0", config.c0, 0, || Value::known(lhs.mul))?;
+                region.assign_fixed(|| "c1", config.c1, 0, || Value::known(-rhs.mul))?;
+                region.assign_fixed(|| "c2", config.c2, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "cc", config.cc, 0, || Value::known(delta))?;
+                region.assign_fixed(|| "cm", config.cm, 0, || Value::known(F::ZERO))?;
 
                 Ok(())
             },
         )
     }
 
-    /// Assert equal
     fn eq_consant(
         &self,
         layouter: &mut impl Layouter<F>,
         constant: F,
         variable: Variable<F>,
     ) -> Result<(), Error> {
+        let config = self.config();
         layouter.assign_region(
             || "eq_constant",
             |mut region| {
                 // turn on the arithmetic gate
-                self.q_arith.enable(&mut region, 0)?;
+                config.q_arith.enable(&mut region, 0)?;
 
                 variable
                     .val
-                    .copy_advice(|| "val", &mut region, self.w0, 0)?;
+                    .copy_advice(|| "val", &mut region, config.w0, 0)?;
 
                 let delta = variable.add - constant;
 
-                region.assign_advice(|| "junk1", self.w1, 0, || Value::known(F::ZERO))?;
-                region.assign_advice(|| "junk2", self.w2, 0, || Value::known(F::ZERO))?;
+                region.assign_advice(|| "junk1", config.w1, 0, || Value::known(F::ZERO))?;
+                region.assign_advice(|| "junk2", config.w2, 0, || Value::known(F::ZERO))?;
 
-                region.assign_fixed(|| "c0", self.c0, 0, || Value::known(variable.mul))?;
-                region.assign_fixed(|| "c1", self.c1, 0, || Value::known(F::ZERO))?;
-                region.assign_fixed(|| "c2", self.c2, 0, || Value::known(F::ZERO))?;
-                region.assign_fixed(|| "cc", self.cc, 0, || Value::known(delta))?;
-                region.assign_fixed(|| "cm", self.cm, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "c0", config.c0, 0, || Value::known(variable.mul))?;
+                region.assign_fixed(|| "c1", config.c1, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "c2", config.c2, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "cc", config.cc, 0, || Value::known(delta))?;
+                region.assign_fixed(|| "cm", config.cm, 0, || Value::known(F::ZERO))?;
 
                 Ok(())
             },
         )
     }
 
-    /// Allocate a bit-constrained variable.
-    fn bit(
+    fn normalize(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        variable: &Variable<F>,
+    ) -> Result<Variable<F>, Error> {
+        let config = self.config();
+        layouter.assign_region(
+            || "normalize",
+            |mut region| {
+                config.q_arith.enable(&mut region, 0)?;
+
+                variable.val.copy_advice(|| "val", &mut region, config.w0, 0)?;
+                region.assign_advice(|| "junk1", config.w1, 0, || Value::known(F::ZERO))?;
+                let val = region.assign_advice(|| "normalized", config.w2, 0, || variable.value())?;
+
+                region.assign_fixed(|| "c0", config.c0, 0, || Value::known(variable.mul))?;
+                region.assign_fixed(|| "c1", config.c1, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "c2", config.c2, 0, || Value::known(-F::ONE))?;
+                region.assign_fixed(|| "cc", config.cc, 0, || Value::known(variable.add))?;
+                region.assign_fixed(|| "cm", config.cm, 0, || Value::known(F::ZERO))?;
+
+                Ok(Variable {
+                    mul: F::ONE,
+                    add: F::ZERO,
+                    val,
+                })
+            },
+        )
+    }
+
+    fn eq_instance(
         &self,
         layouter: &mut impl Layouter<F>,
-        value: Value<bool>,
+        variable: &Variable<F>,
+        instance: Column<Instance>,
+        row: usize,
+    ) -> Result<(), Error> {
+        let normalized = self.normalize(layouter, variable)?;
+        layouter.constrain_instance(normalized.val.cell(), instance, row)
+    }
+
+    fn grand_product(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        alpha: &Variable<F>,
+        terms: &[Variable<F>],
     ) -> Result<Variable<F>, Error> {
+        let config = self.config();
+        let alpha = self.normalize(layouter, alpha)?;
+        let terms = terms
+            .iter()
+            .map(|term| self.normalize(layouter, term))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        layouter.assign_region(
+            || "grand-product",
+            |mut region| {
+                let mut acc =
+                    region.assign_advice(|| "z0", config.gp_acc, 0, || Value::known(F::ONE))?;
+
+                for (i, term) in terms.iter().enumerate() {
+                    config.q_gp.enable(&mut region, i)?;
+
+                    term.val.copy_advice(|| "term", &mut region, config.gp_term, i)?;
+                    alpha.val.copy_advice(|| "alpha", &mut region, config.gp_alpha, i)?;
+
+                    let next = acc
+                        .value()
+                        .copied()
+                        .zip(term.val.value().copied())
+                        .zip(alpha.val.value().copied())
+                        .map(|((z, t), a)| z * (t - a));
+
+                    acc = region.assign_advice(|| "z-next", config.gp_acc, i + 1, || next)?;
+                }
+
+                Ok(Variable {
+                    mul: F::ONE,
+                    add: F::ZERO,
+                    val: acc,
+                })
+            },
+        )
+    }
+
+    fn bit(&self, layouter: &mut impl Layouter<F>, value: Value<bool>) -> Result<Variable<F>, Error> {
+        let config = self.config();
         layouter.assign_region(
             || "bit",
             |mut region| {
                 // turn on the arithmetic gate
-                self.q_arith.enable(&mut region, 0)?;
+                config.q_arith.enable(&mut region, 0)?;
 
                 // (v1 - 1) * v1 = v1^2 - v1
                 let w0 = region.assign_advice(
                     || "bit0",
-                    self.w0,
+                    config.w0,
                     0,
                     || value.map(|b| if b { F::ONE } else { F::ZERO }),
                 )?;
 
                 let w1 = region.assign_advice(
                     || "bit1",
-                    self.w1,
+                    config.w1,
                     0,
                     || value.map(|b| if b { F::ONE } else { F::ZERO }),
                 )?;
 
-                region.assign_advice(|| "junk", self.w2, 0, || Value::known(F::ZERO))?;
+                region.assign_advice(|| "junk", config.w2, 0, || Value::known(F::ZERO))?;
 
                 region.constrain_equal(w0.cell(), w1.cell())?;
 
-                region.assign_fixed(|| "c0", self.c0, 0, || Value::known(F::ZERO))?;
-                region.assign_fixed(|| "c1", self.c0, 0, || Value::known(-F::ONE))?;
-                region.assign_fixed(|| "c2", self.c0, 0, || Value::known(F::ZERO))?;
-                region.assign_fixed(|| "cc", self.cc, 0, || Value::known(F::ZERO))?;
-                region.assign_fixed(|| "cm", self.cm, 0, || Value::known(F::ONE))?;
+                region.assign_fixed(|| "c0", config.c0, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "c1", config.c1, 0, || Value::known(-F::ONE))?;
+                region.assign_fixed(|| "c2", config.c2, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "cc", config.cc, 0, || Value::known(F::ZERO))?;
+                region.assign_fixed(|| "cm", config.cm, 0, || Value::known(F::ONE))?;
 
                 Ok(Variable {
                     mul: F::ONE,
@@ -444,12 +668,274 @@ impl<F: Field> ArithmeticChip<F> {
     }
 }
 
+// ANCHOR: range_chip
+// Constrains a `Variable` to lie in `1..=DIM`, via a fixed lookup table.
+// Nothing so far actually forces a free (empty) cell to hold a digit rather
+// than an arbitrary field element; the sudoku rules are only enforced
+// relative to each other (row/column/block), so an out-of-range witness
+// would otherwise slip through.
+#[derive(Clone, Debug)]
+struct RangeChip<F: Field> {
+    _ph: PhantomData<F>,
+    q_range: Selector,
+    table: TableColumn,
+    w: Column<Advice>,
+    mul: Column<Fixed>,
+    add: Column<Fixed>,
+}
+
+impl<F: PrimeField> RangeChip<F> {
+    fn configure(meta: &mut ConstraintSystem<F>, w: Column<Advice>) -> Self {
+        let q_range = meta.complex_selector();
+        let table = meta.lookup_table_column();
+        let mul = meta.fixed_column();
+        let add = meta.fixed_column();
+
+        meta.enable_equality(w);
+
+        // `Variable` carries affine `mul`/`add` coefficients over its
+        // underlying cell, so the lookup expression folds those in
+        // (`mul*v + add`) rather than requiring the caller to normalize first
+        meta.lookup("digit range-check", |meta| {
+            let q_range = meta.query_selector(q_range);
+            let w = meta.query_advice(w, Rotation::cur());
+            let mul = meta.query_fixed(mul, Rotation::cur());
+            let add = meta.query_fixed(add, Rotation::cur());
+            // rows with `q_range = 0` look up the constant 1, which is in the table
+            let off = Expression::Constant(F::ONE) - q_range.clone();
+            vec![(q_range * (mul * w + add) + off, table)]
+        });
+
+        Self {
+            _ph: PhantomData,
+            q_range,
+            table,
+            w,
+            mul,
+            add,
+        }
+    }
+
+    // the table holds `1..=DIM`
+    fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "digit range table",
+            |mut table| {
+                for i in 1..=DIM {
+                    table.assign_cell(
+                        || "digit",
+                        self.table,
+                        i - 1,
+                        || Value::known(F::from_u128(i as u128)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn in_table(&self, layouter: &mut impl Layouter<F>, var: &Variable<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "range-check",
+            |mut region| {
+                self.q_range.enable(&mut region, 0)?;
+                var.val.copy_advice(|| "val", &mut region, self.w, 0)?;
+                region.assign_fixed(|| "mul", self.mul, 0, || Value::known(var.mul))?;
+                region.assign_fixed(|| "add", self.add, 0, || Value::known(var.add))?;
+                Ok(())
+            },
+        )
+    }
+}
+// ANCHOR_END: range_chip
+
+// ANCHOR: poseidon_chip
+// A toy Pow5-style Poseidon sponge (width 3, rate 2) that commits the
+// solution grid to a single public field element, using the same
+// Cauchy-MDS / random-round-constant construction as the one benchmarked
+// in the poseidon example. Unlike that chip, this one has no dedicated
+// lookup table: the x^5 S-box is just three chained `ArithmeticChip::mul`
+// calls reusing the `w0*w1` multiplication pattern, round-constant
+// addition and MDS scaling are free affine rewrites of `Variable` (no
+// extra row), and only the MDS cross-terms cost an `add` region.
+const POSEIDON_WIDTH: usize = 3;
+const POSEIDON_RATE: usize = POSEIDON_WIDTH - 1;
+const POSEIDON_FULL_ROUNDS: usize = 8;
+const POSEIDON_PARTIAL_ROUNDS: usize = 8;
+const POSEIDON_ROUNDS: usize = POSEIDON_FULL_ROUNDS + POSEIDON_PARTIAL_ROUNDS;
+
+// a round is "full" (S-box on every lane) at the start and end of the
+// schedule, and "partial" (S-box on lane 0 only) in between
+fn poseidon_round_is_full(round: usize) -> bool {
+    round < POSEIDON_FULL_ROUNDS / 2 || round >= POSEIDON_ROUNDS - POSEIDON_FULL_ROUNDS / 2
+}
+
+// Cauchy matrix
+fn poseidon_matrix<F: Field>() -> [[F; POSEIDON_WIDTH]; POSEIDON_WIDTH] {
+    let mut matrix = [[F::ZERO; POSEIDON_WIDTH]; POSEIDON_WIDTH];
+    let mut rng = ChaCha8Rng::seed_from_u64(0x8badf00d);
+    let xi = [F::random(&mut rng), F::random(&mut rng), F::random(&mut rng)];
+    let yi = [F::random(&mut rng), F::random(&mut rng), F::random(&mut rng)];
+    for i in 0..POSEIDON_WIDTH {
+        for j in 0..POSEIDON_WIDTH {
+            matrix[i][j] = (xi[i] + yi[j]).invert().unwrap();
+        }
+    }
+    matrix
+}
+
+fn poseidon_round_constants<F: Field>() -> [[F; POSEIDON_WIDTH]; POSEIDON_ROUNDS] {
+    let mut round_constants = [[F::ZERO; POSEIDON_WIDTH]; POSEIDON_ROUNDS];
+    let mut rng = ChaCha8Rng::seed_from_u64(0xdeadc0de);
+    for i in 0..POSEIDON_ROUNDS {
+        for j in 0..POSEIDON_WIDTH {
+            round_constants[i][j] = F::random(&mut rng);
+        }
+    }
+    round_constants
+}
+
+// pure reference permutation, used both to pick the chip's witness and to
+// compute the expected digest outside the circuit (e.g. for the public
+// instance in `main`)
+fn poseidon_permute<F: Field>(
+    mat: &[[F; POSEIDON_WIDTH]; POSEIDON_WIDTH],
+    rc: &[[F; POSEIDON_WIDTH]; POSEIDON_ROUNDS],
+    mut st: [F; POSEIDON_WIDTH],
+) -> [F; POSEIDON_WIDTH] {
+    fn sbox<F: Field>(x: F) -> F {
+        x * x * x * x * x
+    }
+
+    for (round, rc) in rc.iter().enumerate() {
+        for i in 0..POSEIDON_WIDTH {
+            st[i] += rc[i];
+        }
+        if poseidon_round_is_full(round) {
+            for i in 0..POSEIDON_WIDTH {
+                st[i] = sbox(st[i]);
+            }
+        } else {
+            st[0] = sbox(st[0]);
+        }
+        let mut next = [F::ZERO; POSEIDON_WIDTH];
+        for i in 0..POSEIDON_WIDTH {
+            for j in 0..POSEIDON_WIDTH {
+                next[i] += mat[i][j] * st[j];
+            }
+        }
+        st = next;
+    }
+    st
+}
+
+fn poseidon_hash<F: Field>(inputs: &[F]) -> F {
+    let mat = poseidon_matrix();
+    let rc = poseidon_round_constants();
+    let mut st = [F::ZERO; POSEIDON_WIDTH];
+    for chunk in inputs.chunks(POSEIDON_RATE) {
+        for (i, elem) in chunk.iter().enumerate() {
+            st[i] += *elem;
+        }
+        st = poseidon_permute(&mat, &rc, st);
+    }
+    st[0]
+}
+
+#[derive(Clone, Debug)]
+struct PoseidonChip<F: Field> {
+    arith: ArithmeticChip<F>,
+    matrix: [[F; POSEIDON_WIDTH]; POSEIDON_WIDTH],
+    round_constants: [[F; POSEIDON_WIDTH]; POSEIDON_ROUNDS],
+}
+
+impl<F: PrimeField> PoseidonChip<F> {
+    // reuses `arith`'s columns/gate as-is; nothing new needs to be wired
+    // into the constraint system
+    fn configure(arith: ArithmeticChip<F>) -> Self {
+        Self {
+            arith,
+            matrix: poseidon_matrix(),
+            round_constants: poseidon_round_constants(),
+        }
+    }
+
+    // x^5 = ((x^2)^2) * x
+    fn sbox(&self, layouter: &mut impl Layouter<F>, x: &Variable<F>) -> Result<Variable<F>, Error> {
+        let x2 = self.arith.mul(layouter, x, x)?;
+        let x4 = self.arith.mul(layouter, &x2, &x2)?;
+        self.arith.mul(layouter, &x4, x)
+    }
+
+    fn round(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        state: [Variable<F>; POSEIDON_WIDTH],
+        round: usize,
+    ) -> Result<[Variable<F>; POSEIDON_WIDTH], Error> {
+        // round-constant addition is a free affine rewrite of `Variable`
+        let rc = self.round_constants[round];
+        let mut boxed: [Variable<F>; POSEIDON_WIDTH] =
+            [state[0].clone() + rc[0], state[1].clone() + rc[1], state[2].clone() + rc[2]];
+
+        if poseidon_round_is_full(round) {
+            for i in 0..POSEIDON_WIDTH {
+                boxed[i] = self.sbox(layouter, &boxed[i])?;
+            }
+        } else {
+            boxed[0] = self.sbox(layouter, &boxed[0])?;
+        }
+
+        // MDS mix: state'_i = sum_j matrix[i][j] * boxed[j]; the per-lane
+        // scaling is a free affine rewrite, only the cross-term additions
+        // cost a region
+        let mut next = Vec::with_capacity(POSEIDON_WIDTH);
+        for i in 0..POSEIDON_WIDTH {
+            let mut acc = boxed[0].clone() * self.matrix[i][0];
+            for j in 1..POSEIDON_WIDTH {
+                let term = boxed[j].clone() * self.matrix[i][j];
+                acc = self.arith.add(layouter, &acc, &term)?;
+            }
+            next.push(acc);
+        }
+
+        Ok([next[0].clone(), next[1].clone(), next[2].clone()])
+    }
+
+    /// Absorb `inputs` in `RATE`-sized chunks and return the first rate
+    /// lane of the final state as the digest, compatible with the
+    /// existing `eq`/`eq_consant` assertions.
+    fn hash(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        inputs: &[Variable<F>],
+    ) -> Result<Variable<F>, Error> {
+        let zero = self.arith.constant(layouter, F::ZERO)?;
+        let mut state = [zero.clone(), zero.clone(), zero];
+
+        for chunk in inputs.chunks(POSEIDON_RATE) {
+            for (i, elem) in chunk.iter().enumerate() {
+                state[i] = self.arith.add(layouter, &state[i], elem)?;
+            }
+            for round in 0..POSEIDON_ROUNDS {
+                state = self.round(layouter, state, round)?;
+            }
+        }
+
+        Ok(state[0].clone())
+    }
+}
+// ANCHOR_END: poseidon_chip
+
 #[derive(Clone, Debug)]
 struct TestConfig<F: Field + Clone> {
     _ph: PhantomData<F>,
     phase1_chip: ArithmeticChip<F>,
     phase2_chip: ArithmeticChip<F>,
     challenge_chip: ChallengeChip<F>,
+    range_chip: RangeChip<F>,
+    poseidon_chip: PoseidonChip<F>,
+    instance: Column<Instance>,
 }
 
 impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
@@ -460,7 +946,6 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
         TestCircuit {
             _ph: PhantomData,
             solutation: Value::unknown(),
-            suduko: SUDUKO,
         }
     }
 
@@ -476,10 +961,13 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
         let cc = meta.fixed_column();
         let cm = meta.fixed_column();
 
+        let instance = meta.instance_column();
+
         // enable equality constraints
         meta.enable_equality(w0);
         meta.enable_equality(w1);
         meta.enable_equality(w2);
+        meta.enable_equality(instance);
 
         let alpha = meta.challenge_usable_after(FirstPhase);
 
@@ -498,11 +986,21 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
 
         let challenge_chip = ChallengeChip::configure(meta, alpha, w0_phase2);
 
+        let range_w = meta.advice_column();
+        let range_chip = RangeChip::configure(meta, range_w);
+
+        // reuses `phase1_chip`'s gate/columns, so nothing further needs to
+        // be allocated in the constraint system for it
+        let poseidon_chip = PoseidonChip::configure(phase1_chip.clone());
+
         TestConfig {
             _ph: PhantomData,
             phase1_chip,
             phase2_chip,
             challenge_chip,
+            range_chip,
+            poseidon_chip,
+            instance,
         }
     }
 
@@ -511,25 +1009,42 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
         config: Self::Config, //
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
-        // load/fix the suduko
+        config.range_chip.load(&mut layouter)?;
+
+        // load the solution: clue cells are additionally constrained equal
+        // to a public instance below, so the same circuit/verifying key
+        // checks a solution against whichever puzzle the verifier supplies,
+        // instead of the puzzle being baked in at compile time
         let mut cells = vec![];
+        let mut clue_row = 0;
         for i in 0..DIM {
             let mut row = vec![];
             for j in 0..DIM {
-                let cell = match self.suduko[i][j] {
-                    0 => config.phase1_chip.free(
-                        &mut layouter,
-                        self.solutation.map(|sol| F::from_u128(sol[i][j] as u128)),
-                    ),
-                    fixed => config
+                let cell = config.phase1_chip.free(
+                    &mut layouter,
+                    self.solutation.map(|sol| F::from_u128(sol[i][j] as u128)),
+                )?;
+                config.range_chip.in_table(&mut layouter, &cell)?;
+                if SUDUKO[i][j] != 0 {
+                    config
                         .phase1_chip
-                        .constant(&mut layouter, F::from_u128(fixed as u128)),
-                }?;
+                        .eq_instance(&mut layouter, &cell, config.instance, clue_row)?;
+                    clue_row += 1;
+                }
                 row.push(cell);
             }
             cells.push(row)
         }
 
+        // commit the whole solution grid to a single public field element,
+        // so the puzzle's instance can be accompanied by a succinct
+        // "solution to H" commitment without revealing the grid itself
+        let flat_cells: Vec<Variable<F>> = cells.iter().flatten().cloned().collect();
+        let digest = config.poseidon_chip.hash(&mut layouter, &flat_cells)?;
+        config
+            .phase1_chip
+            .eq_instance(&mut layouter, &digest, config.instance, clue_row)?;
+
         // distinct constraints
         let mut distinct = vec![];
 
@@ -595,18 +1110,53 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
 
 fn eval_vanish<F: PrimeField>(
     layouter: &mut impl Layouter<F>,
-    chip: &ArithmeticChip<F>,
+    chip: &impl ArithmeticInstructions<F>,
     alpha: &Variable<F>,
     terms: &[Variable<F>],
 ) -> Result<Variable<F>, Error> {
-    let mut poly = chip.constant(layouter, F::ONE)?;
-    for term in terms.iter() {
-        let mono = chip.sub(layouter, term, alpha)?;
-        poly = chip.mul(layouter, &poly, &mono)?;
-    }
-    Ok(poly)
+    chip.grand_product(layouter, alpha, terms)
 }
 
+// ANCHOR: circuit_layout
+// Renders the circuit's column/region layout to a PNG via the `dev-graph`
+// feature's plotters backend, so learners can see how the phase-2 columns
+// and the challenge gate are actually placed relative to phase-1, rather
+// than just reading it off the `configure` source.
+#[cfg(feature = "dev-graph")]
+fn render_layout(k: u32, circuit: &TestCircuit<Fr>, path: &str) {
+    use plotters::prelude::*;
+
+    let root = BitMapBackend::new(path, (1920, 1080)).into_drawing_area();
+    root.fill(&WHITE).unwrap();
+    let root = root
+        .titled("Sudoku Circuit Layout", ("sans-serif", 20))
+        .unwrap();
+
+    halo2_proofs::dev::CircuitLayout::default()
+        .show_labels(true)
+        .render(k, circuit, &root)
+        .unwrap();
+}
+// ANCHOR_END: circuit_layout
+
+// ANCHOR: circuit_cost
+// Runs the circuit through `CircuitCost` to report the max gate degree,
+// the advice column count, and an estimated proof size for a given `k`, so learners get
+// concrete feedback on how the `mul`/`add`-per-region layout inflates row
+// usage compared to the grand-product's single batched region.
+#[cfg(feature = "cost-estimator")]
+fn report_circuit_cost(k: u32, circuit: &TestCircuit<Fr>, num_instance: usize) {
+    use halo2_proofs::dev::cost::CircuitCost;
+    use halo2_proofs::halo2curves::bn256::G1;
+
+    let cost: CircuitCost<G1, TestCircuit<Fr>> = CircuitCost::measure(k, circuit);
+
+    println!("max gate degree: {:?}", cost.max_deg);
+    println!("advice columns: {:?}", cost.advice_columns);
+    println!("estimated proof size: {:?}", cost.proof_size(num_instance));
+}
+// ANCHOR_END: circuit_cost
+
 fn main() {
     use halo2_proofs::halo2curves::bn256::Fr;
 
@@ -649,12 +1199,34 @@ fn main() {
         }
     }
 
+    // the puzzle, supplied as a public instance rather than baked into the circuit
+    let mut instance: Vec<Fr> = SUDUKO
+        .iter()
+        .flatten()
+        .filter(|&&clue| clue != 0)
+        .map(|&clue| Fr::from_u128(clue as u128))
+        .collect();
+
+    // the Poseidon commitment to the solution, checked against the same
+    // digest the circuit computes from the witness
+    let solution_flat: Vec<Fr> = SOLUTION
+        .iter()
+        .flatten()
+        .map(|&v| Fr::from_u128(v as u128))
+        .collect();
+    instance.push(poseidon_hash(&solution_flat));
+
     // run the MockProver
     let circuit = TestCircuit::<Fr> {
         _ph: PhantomData,
         solutation: Value::known(SOLUTION),
-        suduko: SUDUKO,
     };
-    let prover = MockProver::run(14, &circuit, vec![]).unwrap();
+    let prover = MockProver::run(14, &circuit, vec![instance.clone()]).unwrap();
     prover.verify().unwrap();
+
+    #[cfg(feature = "dev-graph")]
+    render_layout(14, &circuit, "sudoku-layout.png");
+
+    #[cfg(feature = "cost-estimator")]
+    report_circuit_cost(14, &circuit, instance.len());
 }
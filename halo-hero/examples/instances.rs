@@ -3,16 +3,83 @@ use std::marker::PhantomData;
 use halo2_proofs::{
     circuit::{Layouter, SimpleFloorPlanner, Value},
     dev::MockProver,
-    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector},
-    poly::Rotation,
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{
+        create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Circuit, Column,
+        ConstraintSystem, Error, Expression, Instance, ProvingKey, Selector, TableColumn,
+        VerifyingKey,
+    },
+    poly::{
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            multiopen::{ProverSHPLONK, VerifierSHPLONK},
+            strategy::SingleStrategy,
+        },
+        Rotation, VerificationStrategy,
+    },
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+    },
+    SerdeFormat,
 };
 
 use ff::{Field, PrimeField};
 
+// default step count, used by `main` to build the demo circuits
 const STEPS: usize = 30;
 
+// ANCHOR: precompute_rows
+// Number of rows handed to each worker thread when `parallel-syn` is enabled.
+#[cfg(feature = "parallel-syn")]
+const CHUNK_SIZE: usize = 1 << 10;
+
+// `Region` is not `Send`, so `assign_advice` itself cannot be called from
+// worker threads. What we *can* parallelize is deriving the per-row
+// `Value<F>`s from the witness: split the row range into contiguous chunks,
+// fill each chunk's slice of the output vectors from a crossbeam scoped
+// thread, then hand the fully-materialized vectors back to the (single)
+// layouter thread, which assigns them purely by offset.
+#[cfg(feature = "parallel-syn")]
+fn precompute_rows<F: Field>(
+    fib_seq: &Value<Vec<F>>,
+    flg_seq: &Value<Vec<bool>>,
+    idx_seq: &Value<Vec<usize>>,
+    steps: usize,
+) -> (Vec<Value<F>>, Vec<Value<F>>, Vec<Value<F>>) {
+    let mut fib = vec![Value::unknown(); steps];
+    let mut flg = vec![Value::unknown(); steps];
+    let mut idx = vec![Value::unknown(); steps];
+
+    crossbeam::scope(|scope| {
+        let chunks = fib
+            .chunks_mut(CHUNK_SIZE)
+            .zip(flg.chunks_mut(CHUNK_SIZE))
+            .zip(idx.chunks_mut(CHUNK_SIZE))
+            .enumerate();
+
+        for (chunk_idx, ((fib_chunk, flg_chunk), idx_chunk)) in chunks {
+            let base = chunk_idx * CHUNK_SIZE;
+            scope.spawn(move |_| {
+                for offset in 0..fib_chunk.len() {
+                    let i = base + offset;
+                    fib_chunk[offset] = fib_seq.as_ref().map(|v| v[i]);
+                    flg_chunk[offset] = flg_seq
+                        .as_ref()
+                        .map(|v| if v[i] { F::ONE } else { F::ZERO });
+                    idx_chunk[offset] = idx_seq.as_ref().map(|v| F::from_u128(v[i] as u128));
+                }
+            });
+        }
+    })
+    .expect("a `precompute_rows` worker thread panicked");
+
+    (fib, flg, idx)
+}
+// ANCHOR_END: precompute_rows
+
 struct TestCircuit<F: Field> {
     _ph: PhantomData<F>,
+    steps: usize,
     fib_seq: Value<Vec<F>>,
     idx_seq: Value<Vec<usize>>,
     flg_seq: Value<Vec<bool>>,
@@ -21,39 +88,65 @@ struct TestCircuit<F: Field> {
 #[derive(Clone, Debug)]
 struct TestConfig<F: Field + Clone> {
     _ph: PhantomData<F>,
+    steps: usize,
     fib: Column<Advice>,
     flag: Column<Advice>,
     index: Column<Advice>,
     q_fib: Selector,
     instance: Column<Instance>,
+    // fixed lookup table holding every value in [0, steps], used to prove
+    // `index` is bounded rather than trusting honest witness generation
+    index_table: TableColumn,
 }
 
 impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
     type Config = TestConfig<F>;
     type FloorPlanner = SimpleFloorPlanner;
+    type Params = usize;
 
     fn without_witnesses(&self) -> Self {
         TestCircuit {
             _ph: PhantomData,
+            steps: self.steps,
             fib_seq: Value::unknown(),
             idx_seq: Value::unknown(),
             flg_seq: Value::unknown(),
         }
     }
 
+    // the number of Fibonacci steps is a runtime parameter: it sizes the
+    // synthesis loop below rather than being baked into the binary as a const
+    fn params(&self) -> Self::Params {
+        self.steps
+    }
+
+    fn configure(_meta: &mut ConstraintSystem<F>) -> Self::Config {
+        unreachable!("TestCircuit::Params != (); configure_with_params is called instead")
+    }
+
     // ANCHOR: columns
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+    fn configure_with_params(meta: &mut ConstraintSystem<F>, steps: Self::Params) -> Self::Config {
         let fib = meta.advice_column();
         let flag = meta.advice_column();
         let index = meta.advice_column();
         let q_fib = meta.complex_selector();
         let instance = meta.instance_column();
+        let index_table = meta.lookup_table_column();
 
         meta.enable_equality(fib);
         meta.enable_equality(instance);
         meta.enable_equality(index);
         // ANCHOR_END: columns
 
+        // ANCHOR: index_range_check
+        // bound `index` to [0, steps] via a fixed lookup table, so the range
+        // is provable rather than relying on an honest witness
+        meta.lookup("index range-check", |meta| {
+            let index = meta.query_advice(index, Rotation::cur());
+            vec![(index, index_table)]
+        });
+        // ANCHOR_END: index_range_check
+
         // define a new gate:
         // ANCHOR: gate
         meta.create_gate("fibonacci", |meta| {
@@ -71,6 +164,7 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
 
             // indicator
             let bit = meta.query_advice(flag, Rotation(0));
+            let bit_next = meta.query_advice(flag, Rotation(1));
             let not_bit = Expression::Constant(F::ONE) - bit.clone();
 
             vec![
@@ -84,17 +178,22 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
                 // OR, maintain the value / index
                 enable.clone() * not_bit.clone() * (w1.clone() - w2.clone()),
                 enable.clone() * not_bit.clone() * (idx1.clone() - idx0.clone()),
+                // flag is monotone non-increasing: once padding starts
+                // (flag = 0) it can never flip back to a real step (flag = 1)
+                enable * not_bit * bit_next,
             ]
         });
         // ANCHOR_END: gate
 
         TestConfig {
             _ph: PhantomData,
+            steps,
             q_fib,
             fib,
             index,
             flag,
             instance,
+            index_table,
         }
     }
 
@@ -104,46 +203,85 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
         config: Self::Config, //
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
+        let steps = config.steps;
+
+        // ANCHOR: assign_index_table
+        // populate the fixed table with every value in [0, steps], so the
+        // `index range-check` lookup above actually has something to check against
+        layouter.assign_table(
+            || "index range-check table",
+            |mut table| {
+                for i in 0..=steps {
+                    table.assign_cell(
+                        || "index-table-value",
+                        config.index_table,
+                        i,
+                        || Value::known(F::from_u128(i as u128)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+        // ANCHOR_END: assign_index_table
+
         let instances = layouter.assign_region(
             || "fibonacci-steps",
             |mut region| {
-                // apply the "step" gate STEPS = 5 times
+                // apply the "step" gate `steps` times
                 let mut fib_cells = Vec::new();
                 let mut flg_cells = Vec::new();
                 let mut idx_cells = Vec::new();
 
-                for i in 0..STEPS {
+                // with `parallel-syn`, the per-row `Value<F>`s are derived up
+                // front across worker threads; without it, each row's values
+                // are derived inline as they're assigned
+                #[cfg(feature = "parallel-syn")]
+                let (fib_vals, flg_vals, idx_vals) =
+                    precompute_rows(&self.fib_seq, &self.flg_seq, &self.idx_seq, steps);
+
+                for i in 0..steps {
                     // turn on the gate
                     config.q_fib.enable(&mut region, i)?;
 
                     // assign the fibonacci value
                     // ANCHOR: assign_fib
+                    #[cfg(feature = "parallel-syn")]
+                    let fib_val = fib_vals[i];
+                    #[cfg(not(feature = "parallel-syn"))]
+                    let fib_val = self.fib_seq.as_ref().map(|v| v[i]);
                     fib_cells.push(region.assign_advice(
                         || "assign-fib",
                         config.fib,
                         i,
-                        || self.fib_seq.as_ref().map(|v| v[i]),
+                        || fib_val,
                     )?);
                     // ANCHOR_END: assign_fib
 
                     // assign the flag
+                    #[cfg(feature = "parallel-syn")]
+                    let flg_val = flg_vals[i];
+                    #[cfg(not(feature = "parallel-syn"))]
+                    let flg_val = self
+                        .flg_seq
+                        .as_ref()
+                        .map(|v| if v[i] { F::ONE } else { F::ZERO });
                     flg_cells.push(region.assign_advice(
                         || "assign-bit",
                         config.flag,
                         i,
-                        || {
-                            self.flg_seq
-                                .as_ref()
-                                .map(|v| if v[i] { F::ONE } else { F::ZERO })
-                        },
+                        || flg_val,
                     )?);
 
                     // assign the index
+                    #[cfg(feature = "parallel-syn")]
+                    let idx_val = idx_vals[i];
+                    #[cfg(not(feature = "parallel-syn"))]
+                    let idx_val = self.idx_seq.as_ref().map(|v| F::from_u128(v[i] as u128));
                     idx_cells.push(region.assign_advice(
                         || "assign-idx",
                         config.index,
                         i,
-                        || self.idx_seq.as_ref().map(|v| F::from_u128(v[i] as u128)),
+                        || idx_val,
                     )?);
                 }
 
@@ -152,11 +290,11 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
                 idx_cells.push(region.assign_advice(
                     || "assign-fib",
                     config.index,
-                    STEPS,
+                    steps,
                     || {
                         self.idx_seq
                             .as_ref()
-                            .map(|v| F::from_u128(v[STEPS] as u128))
+                            .map(|v| F::from_u128(v[steps] as u128))
                     },
                 )?);
 
@@ -165,22 +303,22 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
                 fib_cells.push(region.assign_advice(
                     || "assign-fib",
                     config.fib,
-                    STEPS,
-                    || self.fib_seq.as_ref().map(|v| v[STEPS]),
+                    steps,
+                    || self.fib_seq.as_ref().map(|v| v[steps]),
                 )?);
 
                 fib_cells.push(region.assign_advice(
                     || "assign-fib",
                     config.fib,
-                    STEPS + 1,
-                    || self.fib_seq.as_ref().map(|v| v[STEPS + 1]),
+                    steps + 1,
+                    || self.fib_seq.as_ref().map(|v| v[steps + 1]),
                 )?);
 
                 // sanity check
 
-                assert_eq!(flg_cells.len(), STEPS);
-                assert_eq!(idx_cells.len(), STEPS + 1);
-                assert_eq!(fib_cells.len(), STEPS + 2);
+                assert_eq!(flg_cells.len(), steps);
+                assert_eq!(idx_cells.len(), steps + 1);
+                assert_eq!(fib_cells.len(), steps + 2);
 
                 // enforce instances
 
@@ -207,10 +345,475 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
     // ANCHOR_END: synthesize
 }
 
-fn main() {
-    use halo2_proofs::halo2curves::bn256::Fr;
+// ANCHOR: prove_and_verify
+// Runs the full halo2 backend over bn256: keygen -> create_proof -> verify_proof,
+// rather than just checking constraint satisfaction with MockProver.
+fn prove_and_verify(k: u32, circuit: &TestCircuit<Fr>, instances: Vec<Fr>) {
+    let mut rng = rand::thread_rng();
+
+    let params = ParamsKZG::<Bn256>::setup(k, &mut rng);
+
+    let vk_circuit = circuit.without_witnesses();
+    let vk = keygen_vk(&params, &vk_circuit).expect("keygen_vk failed");
+    let pk = keygen_pk(&params, vk.clone(), &vk_circuit).expect("keygen_pk failed");
+
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        &params,
+        &pk,
+        &[circuit],
+        &[&[&instances]],
+        rng,
+        &mut transcript,
+    )
+    .expect("create_proof failed");
+    let proof = transcript.finalize();
+
+    println!("proof-size: {} bytes", proof.len());
+
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&proof[..]);
+    let strategy = SingleStrategy::new(&params);
+    verify_proof::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<_>, _, _, _>(
+        &params,
+        &vk,
+        strategy,
+        &[&[&instances]],
+        &mut transcript,
+    )
+    .expect("verify_proof failed");
+}
+// ANCHOR_END: prove_and_verify
+
+// ANCHOR: artifact_io
+// `keygen_vk`/`keygen_pk` above hand back in-process structs that
+// `create_proof`/`verify_proof` immediately consume; a real deployment
+// instead writes `vk`/`pk`/the proof to disk once and reloads them in
+// whatever process proves or verifies later. `VerifyingKey::read` and
+// `ProvingKey::read` take the circuit's `Params` rather than a witnessed
+// `TestCircuit`, so the verifier side never needs to reconstruct (or even
+// link against) anything but the `ConstraintSystem` shape.
+fn write_vk(vk: &VerifyingKey<G1Affine>) -> Vec<u8> {
+    let mut bytes = vec![];
+    vk.write(&mut bytes, SerdeFormat::RawBytes)
+        .expect("vk serialization failed");
+    bytes
+}
+
+fn read_vk(bytes: &[u8], steps: usize) -> VerifyingKey<G1Affine> {
+    VerifyingKey::read::<_, TestCircuit<Fr>>(&mut &bytes[..], SerdeFormat::RawBytes, steps)
+        .expect("vk deserialization failed")
+}
+
+fn write_pk(pk: &ProvingKey<G1Affine>) -> Vec<u8> {
+    let mut bytes = vec![];
+    pk.write(&mut bytes, SerdeFormat::RawBytes)
+        .expect("pk serialization failed");
+    bytes
+}
+
+fn read_pk(bytes: &[u8], steps: usize) -> ProvingKey<G1Affine> {
+    ProvingKey::read::<_, TestCircuit<Fr>>(&mut &bytes[..], SerdeFormat::RawBytes, steps)
+        .expect("pk deserialization failed")
+}
+
+// Length-prefixes the proof so it can sit next to vk/pk bytes in a single
+// blob without needing an external framing format.
+fn write_proof(proof: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 + proof.len());
+    out.extend_from_slice(&(proof.len() as u64).to_le_bytes());
+    out.extend_from_slice(proof);
+    out
+}
+
+fn read_proof(bytes: &[u8]) -> Vec<u8> {
+    let len = u64::from_le_bytes(bytes[..8].try_into().unwrap()) as usize;
+    bytes[8..8 + len].to_vec()
+}
+
+// Rebuilds a `Blake2bRead` transcript from a stored proof blob and runs
+// `verify_proof` against it: the "prover and verifier don't share memory"
+// path, since everything here came from bytes rather than the structs
+// `prove_and_verify` computed above.
+fn verify_from_bytes(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proof_bytes: &[u8],
+    instances: &[Fr],
+) -> Result<(), Error> {
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof_bytes);
+    let strategy = SingleStrategy::new(params);
+    verify_proof::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<_>, _, _, _>(
+        params,
+        vk,
+        strategy,
+        &[&[instances]],
+        &mut transcript,
+    )
+}
+
+// Exercises the round trip end to end: keygen -> write vk/pk/proof -> drop
+// everything -> read back from bytes alone -> verify succeeds. Also
+// flips a single proof byte to confirm the stored artifacts actually
+// constrain verification rather than rubber-stamping anything handed to
+// `verify_from_bytes`.
+fn artifact_round_trip_demo(k: u32, steps: usize, circuit: &TestCircuit<Fr>, instances: Vec<Fr>) {
+    let mut rng = rand::thread_rng();
+    let params = ParamsKZG::<Bn256>::setup(k, &mut rng);
+
+    let vk_circuit = circuit.without_witnesses();
+    let vk = keygen_vk(&params, &vk_circuit).expect("keygen_vk failed");
+    let pk = keygen_pk(&params, vk.clone(), &vk_circuit).expect("keygen_pk failed");
+
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        &params,
+        &pk,
+        &[circuit],
+        &[&[&instances]],
+        rng,
+        &mut transcript,
+    )
+    .expect("create_proof failed");
+    let proof = transcript.finalize();
+
+    let mut params_bytes = vec![];
+    params
+        .write(&mut params_bytes)
+        .expect("SRS serialization failed");
+    let vk_bytes = write_vk(&vk);
+    let pk_bytes = write_pk(&pk);
+    let proof_blob = write_proof(&proof);
+
+    println!(
+        "artifact sizes: vk {} bytes, pk {} bytes, proof {} bytes",
+        vk_bytes.len(),
+        pk_bytes.len(),
+        proof.len()
+    );
+
+    // drop everything the prover computed in-process; only the bytes survive
+    // (the SRS is public, shared setup data, so it round-trips through
+    // bytes exactly like vk/pk/proof rather than being regenerated)
+    drop((params, vk, pk, proof));
+
+    let params =
+        ParamsKZG::<Bn256>::read(&mut &params_bytes[..]).expect("SRS deserialization failed");
+    let vk = read_vk(&vk_bytes, steps);
+    let restored_proof = read_proof(&proof_blob);
+    verify_from_bytes(&params, &vk, &restored_proof, &instances).expect("verify_from_bytes failed");
+
+    // a flipped proof byte must fail verification
+    let mut corrupted_proof = restored_proof.clone();
+    corrupted_proof[0] ^= 1;
+    assert!(verify_from_bytes(&params, &vk, &corrupted_proof, &instances).is_err());
+
+    // `pk_bytes` round-trips the same way, for a process that needs to
+    // keep proving without re-running `keygen_pk`
+    let _pk = read_pk(&pk_bytes, steps);
+}
+// ANCHOR_END: artifact_io
+
+// ANCHOR: rot_circuit
+// A rotation-optimized variant of `TestCircuit`: instead of three parallel
+// advice columns (`fib`, `flag`, `index`), everything is interleaved into a
+// single advice column and the gate recovers each field via `Rotation`
+// rather than a dedicated column. Packing stores, for logical row `i`,
+// `fib[i]` at offset `3*i`, `flag[i]` at offset `3*i+1`, and `index[i]` at
+// offset `3*i+2`, so the gate reaches `fib[i+1]`/`fib[i+2]` at `Rotation(3)`/
+// `Rotation(6)` and `flag[i+1]`/`index[i+1]` at `Rotation(4)`/`Rotation(5)`.
+// This trades three narrow columns for one column spanning three times as
+// many rows.
+struct RotTestCircuit<F: Field> {
+    _ph: PhantomData<F>,
+    steps: usize,
+    fib_seq: Value<Vec<F>>,
+    idx_seq: Value<Vec<usize>>,
+    flg_seq: Value<Vec<bool>>,
+}
+
+#[derive(Clone, Debug)]
+struct RotConfig<F: Field + Clone> {
+    _ph: PhantomData<F>,
+    steps: usize,
+    packed: Column<Advice>,
+    q_fib: Selector,
+    // enabled only at the offsets where `packed` holds an `index` value, so
+    // the `index range-check` lookup below doesn't also check `fib`/`flag`
+    q_index: Selector,
+    instance: Column<Instance>,
+    // fixed lookup table holding every value in [0, steps], mirroring
+    // `TestConfig::index_table` so this circuit is no less constrained
+    // than the one it's meant to compare against
+    index_table: TableColumn,
+}
+
+impl<F: PrimeField> Circuit<F> for RotTestCircuit<F> {
+    type Config = RotConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = usize;
+
+    fn without_witnesses(&self) -> Self {
+        RotTestCircuit {
+            _ph: PhantomData,
+            steps: self.steps,
+            fib_seq: Value::unknown(),
+            idx_seq: Value::unknown(),
+            flg_seq: Value::unknown(),
+        }
+    }
+
+    fn params(&self) -> Self::Params {
+        self.steps
+    }
+
+    fn configure(_meta: &mut ConstraintSystem<F>) -> Self::Config {
+        unreachable!("RotTestCircuit::Params != (); configure_with_params is called instead")
+    }
+
+    fn configure_with_params(meta: &mut ConstraintSystem<F>, steps: Self::Params) -> Self::Config {
+        let packed = meta.advice_column();
+        let q_fib = meta.complex_selector();
+        let q_index = meta.complex_selector();
+        let instance = meta.instance_column();
+        let index_table = meta.lookup_table_column();
+
+        meta.enable_equality(packed);
+        meta.enable_equality(instance);
+
+        // bound the `index` slots packed into `packed` to [0, steps], same
+        // as `TestConfig`'s `index range-check` lookup; gated on `q_index`
+        // so it only ever checks the index slot, not the fib/flag slots
+        // that also live in this column
+        meta.lookup("index range-check (rotation-optimized)", |meta| {
+            let q_index = meta.query_selector(q_index);
+            let index = meta.query_advice(packed, Rotation::cur());
+            vec![(q_index * index, index_table)]
+        });
+
+        meta.create_gate("fibonacci (rotation-optimized)", |meta| {
+            let enable = meta.query_selector(q_fib);
+
+            // fib[i], fib[i+1], fib[i+2]
+            let w0 = meta.query_advice(packed, Rotation(0));
+            let w1 = meta.query_advice(packed, Rotation(3));
+            let w2 = meta.query_advice(packed, Rotation(6));
+
+            // flag[i], flag[i+1]
+            let bit = meta.query_advice(packed, Rotation(1));
+            let bit_next = meta.query_advice(packed, Rotation(4));
+            let not_bit = Expression::Constant(F::ONE) - bit.clone();
+
+            // index[i], index[i+1]
+            let idx0 = meta.query_advice(packed, Rotation(2));
+            let idx1 = meta.query_advice(packed, Rotation(5));
+
+            vec![
+                enable.clone() * bit.clone() * not_bit.clone(),
+                enable.clone() * bit.clone() * (w0.clone() + w1.clone() - w2.clone()),
+                enable.clone()
+                    * bit.clone()
+                    * (idx1.clone() - idx0.clone() - Expression::Constant(F::ONE)),
+                enable.clone() * not_bit.clone() * (w1.clone() - w2.clone()),
+                enable.clone() * not_bit.clone() * (idx1.clone() - idx0.clone()),
+                enable * not_bit * bit_next,
+            ]
+        });
 
+        RotConfig {
+            _ph: PhantomData,
+            steps,
+            packed,
+            q_fib,
+            q_index,
+            instance,
+            index_table,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let steps = config.steps;
+
+        // populate the fixed table with every value in [0, steps], so the
+        // `index range-check (rotation-optimized)` lookup above actually has
+        // something to check against
+        layouter.assign_table(
+            || "index range-check table (rotation-optimized)",
+            |mut table| {
+                for i in 0..=steps {
+                    table.assign_cell(
+                        || "index-table-value",
+                        config.index_table,
+                        i,
+                        || Value::known(F::from_u128(i as u128)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+
+        let instances = layouter.assign_region(
+            || "fibonacci-steps (packed)",
+            |mut region| {
+                let mut fib_cells = Vec::new();
+                let mut idx_cells = Vec::new();
+
+                for i in 0..steps {
+                    config.q_fib.enable(&mut region, 3 * i)?;
+                    config.q_index.enable(&mut region, 3 * i + 2)?;
+
+                    let fib_val = self.fib_seq.as_ref().map(|v| v[i]);
+                    fib_cells.push(region.assign_advice(
+                        || "assign-fib",
+                        config.packed,
+                        3 * i,
+                        || fib_val,
+                    )?);
+
+                    let flg_val = self
+                        .flg_seq
+                        .as_ref()
+                        .map(|v| if v[i] { F::ONE } else { F::ZERO });
+                    region.assign_advice(|| "assign-bit", config.packed, 3 * i + 1, || flg_val)?;
+
+                    let idx_val = self.idx_seq.as_ref().map(|v| F::from_u128(v[i] as u128));
+                    idx_cells.push(region.assign_advice(
+                        || "assign-idx",
+                        config.packed,
+                        3 * i + 2,
+                        || idx_val,
+                    )?);
+                }
+
+                // the last two logical rows only need `fib` (and row `steps`
+                // its `index`); the gate on row `steps - 1` reads row `steps`'s
+                // `flag` slot as `bit_next`, so it is assigned zero
+                for i in steps..steps + 2 {
+                    let fib_val = self.fib_seq.as_ref().map(|v| v[i]);
+                    fib_cells.push(region.assign_advice(
+                        || "assign-fib",
+                        config.packed,
+                        3 * i,
+                        || fib_val,
+                    )?);
+
+                    if i < steps + 1 {
+                        region.assign_advice(
+                            || "assign-bit",
+                            config.packed,
+                            3 * i + 1,
+                            || Value::known(F::ZERO),
+                        )?;
+
+                        config.q_index.enable(&mut region, 3 * i + 2)?;
+
+                        let idx_val = self.idx_seq.as_ref().map(|v| F::from_u128(v[i] as u128));
+                        idx_cells.push(region.assign_advice(
+                            || "assign-idx",
+                            config.packed,
+                            3 * i + 2,
+                            || idx_val,
+                        )?);
+                    }
+                }
+
+                assert_eq!(idx_cells.len(), steps + 1);
+                assert_eq!(fib_cells.len(), steps + 2);
+
+                Ok([
+                    fib_cells[0].cell(),
+                    fib_cells[1].cell(),
+                    idx_cells[0].cell(),
+                    fib_cells.last().unwrap().cell(),
+                    idx_cells.last().unwrap().cell(),
+                ])
+            },
+        )?;
+
+        for (i, cell) in instances.into_iter().enumerate() {
+            layouter.constrain_instance(cell, config.instance, i)?;
+        }
+
+        Ok(())
+    }
+}
+// ANCHOR_END: rot_circuit
+
+// ANCHOR: evm_verifier
+// Renders a Solidity verifier contract for this circuit's verifying key,
+// suitable for the "submit a proof on-chain" step of the CTF workflow.
+// Requires the `halo2_solidity_verifier` crate, which targets the KZG/bn256
+// backend used throughout this file.
+fn gen_evm_verifier(
+    params: &ParamsKZG<Bn256>,
+    vk: &halo2_proofs::plonk::VerifyingKey<G1Affine>,
+    num_instance: usize,
+) -> String {
+    use halo2_solidity_verifier::{BatchOpenScheme::Bdfg21, SolidityGenerator};
+
+    let generator = SolidityGenerator::new(params, vk, Bdfg21, num_instance);
+    let (_vk_contract, verifier_contract) = generator
+        .render_separately()
+        .expect("failed to render solidity verifier");
+    verifier_contract
+}
+
+// Lays out a proof and its public instances as the `uint256[]` calldata an
+// EVM verifier contract expects: the five public Fibonacci instances
+// (`fib_start0`, `fib_start1`, start index, claimed result, step count)
+// followed by the proof bytes.
+fn gen_evm_calldata(proof: &[u8], instances: &[Fr]) -> Vec<u8> {
+    halo2_solidity_verifier::encode_calldata(None, proof, instances)
+}
+
+// Runs keygen -> create_proof -> solidity-verifier generation for
+// `TestCircuit`, so a user can go from `MockProver` all the way to an
+// on-chain verifier contract and its calldata. The EVM verifier expects a
+// Keccak256-based transcript rather than the Blake2b one used by
+// `prove_and_verify` above, since that's what the generated contract hashes.
+fn prove_for_onchain_verifier(k: u32, circuit: &TestCircuit<Fr>, instances: Vec<Fr>) {
+    use halo2_solidity_verifier::Keccak256Transcript;
+
+    let mut rng = rand::thread_rng();
+    let params = ParamsKZG::<Bn256>::setup(k, &mut rng);
+
+    let vk_circuit = circuit.without_witnesses();
+    let vk = keygen_vk(&params, &vk_circuit).expect("keygen_vk failed");
+    let pk = keygen_pk(&params, vk.clone(), &vk_circuit).expect("keygen_pk failed");
+
+    let mut transcript = Keccak256Transcript::new(vec![]);
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+        &params,
+        &pk,
+        &[circuit],
+        &[&[&instances]],
+        rng,
+        &mut transcript,
+    )
+    .expect("create_proof failed");
+    let proof = transcript.finalize();
+
+    let verifier_contract = gen_evm_verifier(&params, &vk, instances.len());
+    let calldata = gen_evm_calldata(&proof, &instances);
+
+    println!(
+        "solidity verifier: {} bytes of source",
+        verifier_contract.len()
+    );
+    println!(
+        "evm calldata ({} public inputs): {} bytes",
+        instances.len(),
+        calldata.len()
+    );
+}
+// ANCHOR_END: evm_verifier
+
+fn main() {
     // ANCHOR: witness_gen
+    let steps = STEPS; // the number of rows in the padded Fibonacci sequence
     let fib_steps = 20; // the number of Fibonacci steps we want to prove
     let fib_start0 = Fr::from(1u64); // first Fibonacci number
     let fib_start1 = Fr::from(1u64); // second Fibonacci number
@@ -219,7 +822,7 @@ fn main() {
     let mut flg_seq = vec![];
     let mut idx_seq = vec![0];
     let mut fib_seq = vec![fib_start0, fib_start1];
-    for idx in 1..=STEPS {
+    for idx in 1..=steps {
         if idx <= fib_steps {
             // generate the Fibonacci sequence
             let f0 = fib_seq[fib_seq.len() - 2];
@@ -238,6 +841,7 @@ fn main() {
     // create the circuit
     let circuit = TestCircuit::<Fr> {
         _ph: PhantomData,
+        steps,
         fib_seq: Value::known(fib_seq.clone()),
         flg_seq: Value::known(flg_seq.clone()),
         idx_seq: Value::known(idx_seq.clone()),
@@ -245,10 +849,10 @@ fn main() {
     // ANCHOR_END: witness_gen
 
     // print the assigment
-    assert_eq!(flg_seq.len(), STEPS);
-    assert_eq!(idx_seq.len(), STEPS + 1);
-    assert_eq!(fib_seq.len(), STEPS + 2);
-    for i in 0..STEPS + 2 {
+    assert_eq!(flg_seq.len(), steps);
+    assert_eq!(idx_seq.len(), steps + 1);
+    assert_eq!(fib_seq.len(), steps + 2);
+    for i in 0..steps + 2 {
         println!(
             "{:3}: {:32} {:5} {:5}",
             i,
@@ -270,18 +874,49 @@ fn main() {
     // ANCHOR: run
     // run the MockProver
     let fib_result = fib_seq.last().unwrap().clone();
-    let prover = MockProver::run(
-        10,
-        &circuit,
-        vec![vec![
-            fib_start0,                       // first Fibonacci number
-            fib_start1,                       // second Fibonacci number
-            Fr::from_u128(0 as u128),         // start index
-            fib_result,                       // claimed result
-            Fr::from_u128(fib_steps as u128), // after this number of steps
-        ]],
-    )
-    .unwrap();
+    let instances = vec![
+        fib_start0,                       // first Fibonacci number
+        fib_start1,                       // second Fibonacci number
+        Fr::from_u128(0 as u128),         // start index
+        fib_result,                       // claimed result
+        Fr::from_u128(fib_steps as u128), // after this number of steps
+    ];
+    let prover = MockProver::run(10, &circuit, vec![instances.clone()]).unwrap();
     prover.verify().unwrap();
     // ANCHOR_END: run
+
+    // now run the real prover/verifier over bn256
+    prove_and_verify(10, &circuit, instances.clone());
+
+    // same circuit, but round-tripping vk/pk/proof through bytes as if
+    // the prover and verifier were separate processes
+    artifact_round_trip_demo(10, steps, &circuit, instances.clone());
+
+    // ANCHOR: rot_compare
+    // build the rotation-optimized variant over the same witness and compare
+    // circuit dimensions: 3 advice columns x (steps+2) rows vs. 1 advice
+    // column x 3*(steps+1)+1 rows (the last logical row only uses its `fib` slot)
+    let rot_circuit = RotTestCircuit::<Fr> {
+        _ph: PhantomData,
+        steps,
+        fib_seq: Value::known(fib_seq.clone()),
+        flg_seq: Value::known(flg_seq.clone()),
+        idx_seq: Value::known(idx_seq.clone()),
+    };
+    let rot_prover = MockProver::run(10, &rot_circuit, vec![instances.clone()]).unwrap();
+    rot_prover.verify().unwrap();
+
+    println!(
+        "unoptimized:         3 advice columns x {:4} rows used",
+        steps + 2
+    );
+    println!(
+        "rotation-optimized:  1 advice column  x {:4} rows used",
+        3 * (steps + 1) + 1
+    );
+    // ANCHOR_END: rot_compare
+
+    // render a Solidity verifier for this same circuit and encode the
+    // calldata a contract would need to check it on-chain
+    prove_for_onchain_verifier(10, &circuit, instances);
 }
@@ -5,8 +5,9 @@ use halo2_proofs::{
     dev::MockProver,
     halo2curves::bn256::{Bn256, G1Affine},
     plonk::{
-        create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Circuit, Column,
-        ConstraintSystem, Error, Expression, Fixed, Instance, Selector, TableColumn,
+        create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Challenge, Circuit, Column,
+        ConstraintSystem, Error, Expression, FirstPhase, Fixed, Instance, SecondPhase, Selector,
+        TableColumn,
     },
     poly::{
         commitment::Prover,
@@ -25,6 +26,7 @@ use rand::rngs::ThreadRng;
 
 struct TestCircuit<F: Field> {
     _ph: PhantomData<F>,
+    bits: usize,  // table is `2^bits` x `2^bits`, a runtime circuit parameter
     a: Value<u8>, // secret
     b: Value<u8>, // secret
 }
@@ -35,11 +37,12 @@ struct TestConfig<F: PrimeField> {
     advice: Column<Advice>,
     fixed: Column<Fixed>,
     instance: Column<Instance>,
+    bits: usize,
 
     // XOR table:
     // | a | b | a ^ b |
-    // for a in 0..16:
-    //    for b in 0..16:
+    // for a in 0..2^bits:
+    //    for b in 0..2^bits:
     //        tbl[i] = (a, b, a ^ b)
     //
     // Check:
@@ -48,8 +51,8 @@ struct TestConfig<F: PrimeField> {
     //
     // Implies:
     //
-    // lhs in [0, 16)
-    // rhs in [0, 16)
+    // lhs in [0, 2^bits)
+    // rhs in [0, 2^bits)
     // out = lhs ^ rhs
     tbl_in1: TableColumn,
     tbl_in2: TableColumn,
@@ -311,16 +314,26 @@ impl<F: PrimeField> TestCircuit<F> {
 impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
     type Config = TestConfig<F>;
     type FloorPlanner = SimpleFloorPlanner;
+    type Params = usize;
 
     fn without_witnesses(&self) -> Self {
         TestCircuit {
             _ph: PhantomData,
+            bits: self.bits,
             a: Value::unknown(),
             b: Value::unknown(),
         }
     }
 
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+    fn params(&self) -> Self::Params {
+        self.bits
+    }
+
+    fn configure(_meta: &mut ConstraintSystem<F>) -> Self::Config {
+        unreachable!("TestCircuit::Params != (); configure_with_params is called instead")
+    }
+
+    fn configure_with_params(meta: &mut ConstraintSystem<F>, bits: Self::Params) -> Self::Config {
         let advice = meta.advice_column();
         let fixed = meta.fixed_column();
         let instance = meta.instance_column();
@@ -354,6 +367,7 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
             fixed,
             advice,
             instance,
+            bits,
             tbl_in1,
             tbl_in2,
             tbl_out,
@@ -369,12 +383,13 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
         // fill in the fixed table
+        let side = 1u128 << config.bits;
         layouter.assign_table(
             || "xor-table",
             |mut table| {
                 let mut row = 0;
-                for in1 in 0..16 {
-                    for in2 in 0..16 {
+                for in1 in 0..side {
+                    for in2 in 0..side {
                         table.assign_cell(
                             || "in1",
                             config.tbl_in1,
@@ -415,6 +430,240 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
     }
 }
 
+// the same RLC-style compression `RLCChip`/`ShuffleChip` use in the
+// session-7 example, duplicated here since each example is self-contained
+fn compute_rlc<F: Field, const N: usize>(challenge: F, row: [F; N]) -> F {
+    let mut rlc = F::ZERO;
+    let mut c = F::ONE;
+    for v in row.iter() {
+        rlc += *v * c;
+        c *= challenge;
+    }
+    rlc
+}
+
+// ANCHOR: dynamic_lookup_xor
+// Folds the `tbl_in1`/`tbl_in2`/`tbl_out` three-column lookup above into a
+// single-column "dynamic lookup": with arity 3 the fixed version spends
+// one `TableColumn` per tuple element, which doesn't scale to wider
+// tuples. Here both the witness side and the table side are compressed
+// with a challenge `alpha` into one scalar each -- `c = w0 + alpha*w1 +
+// alpha^2*w2` for the witness, `t = in1 + alpha*in2 + alpha^2*out` for
+// every table row -- so the lookup only ever needs one input column and
+// one table column, no matter how wide the tuple gets.
+//
+// The table itself also moves from a fixed `TableColumn` (populated once
+// via `assign_table`, before any challenge exists) to an ordinary
+// SecondPhase advice column, since its contents now depend on `alpha`,
+// which can only be drawn after the FirstPhase commitments. That rules
+// out `meta.lookup` (which requires a `TableColumn` on the table side);
+// `meta.lookup_any`, used the same way session-4's `DynamicLookupChip`
+// uses it for an advice-backed table, is what this needs instead.
+#[derive(Clone, Debug)]
+struct DynamicLookupChip<F: Field> {
+    q_xor: Selector,
+    q_table: Selector,
+    alpha: Challenge,
+    advice: Column<Advice>, // w0/w1/w2 at Rotation(0)/(1)/(2), same layout as the fixed-table xor
+    input: Column<Advice>,  // compressed witness scalar c
+    table: Column<Advice>,  // compressed table scalar t
+    _ph: PhantomData<F>,
+}
+
+impl<F: PrimeField> DynamicLookupChip<F> {
+    fn configure(meta: &mut ConstraintSystem<F>, advice: Column<Advice>) -> Self {
+        let q_xor = meta.complex_selector();
+        let q_table = meta.complex_selector();
+        let alpha = meta.challenge_usable_after(FirstPhase);
+        let input = meta.advice_column_in(SecondPhase);
+        let table = meta.advice_column_in(SecondPhase);
+
+        meta.create_gate("xor input compression", |meta| {
+            let q_xor = meta.query_selector(q_xor);
+            let alpha = meta.query_challenge(alpha);
+            let w0 = meta.query_advice(advice, Rotation(0));
+            let w1 = meta.query_advice(advice, Rotation(1));
+            let w2 = meta.query_advice(advice, Rotation(2));
+            let c = meta.query_advice(input, Rotation(0));
+            vec![q_xor * (c - (w0 + alpha.clone() * w1 + alpha.clone() * alpha * w2))]
+        });
+
+        meta.lookup_any("dynamic xor lookup", |meta| {
+            let q_xor = meta.query_selector(q_xor);
+            let q_table = meta.query_selector(q_table);
+            let c = meta.query_advice(input, Rotation(0));
+            let t = meta.query_advice(table, Rotation::cur());
+            vec![(q_xor * c, q_table * t)]
+        });
+
+        DynamicLookupChip {
+            q_xor,
+            q_table,
+            alpha,
+            advice,
+            input,
+            table,
+            _ph: PhantomData,
+        }
+    }
+
+    // Fills the 16x16 compressed xor table into `table`, one row per
+    // `(in1, in2)` pair -- the SecondPhase analogue of `assign_table`
+    // above, except it's witnessed like any other advice column since it
+    // depends on `alpha`.
+    fn assign_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        let alpha = layouter.get_challenge(self.alpha);
+        layouter.assign_region(
+            || "dynamic xor table",
+            |mut region| {
+                let mut row = 0;
+                for in1 in 0..16u128 {
+                    for in2 in 0..16u128 {
+                        self.q_table.enable(&mut region, row)?;
+                        let t = alpha.map(|a| {
+                            compute_rlc(
+                                a,
+                                [
+                                    F::from_u128(in1),
+                                    F::from_u128(in2),
+                                    F::from_u128(in1 ^ in2),
+                                ],
+                            )
+                        });
+                        region.assign_advice(|| "t", self.table, row, || t)?;
+                        row += 1;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    // same three-row `w0`/`w1`/`w2` layout as the fixed-table `xor`
+    // above, plus the compressed witness cell `c`
+    fn xor(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        lhs: Bit4Ranged<F>,
+        rhs: Bit4Ranged<F>,
+    ) -> Result<Bit4Ranged<F>, Error> {
+        let alpha = layouter.get_challenge(self.alpha);
+        layouter.assign_region(
+            || "dynamic xor-region",
+            |mut region| {
+                self.q_xor.enable(&mut region, 0)?;
+
+                let w0 = lhs.var.copy_advice(|| "w0", &mut region, self.advice, 0)?;
+                let w1 = rhs.var.copy_advice(|| "w1", &mut region, self.advice, 1)?;
+
+                let val = lhs
+                    .val
+                    .and_then(|in1| rhs.val.and_then(|in2| Value::known(in1 ^ in2)));
+
+                let w2 = region.assign_advice(
+                    || "w2",
+                    self.advice,
+                    2,
+                    || val.map(|v| F::from_u128(v as u128)),
+                )?;
+
+                let c = alpha
+                    .zip(w0.value().copied())
+                    .zip(w1.value().copied())
+                    .zip(w2.value().copied())
+                    .map(|(((a, w0), w1), w2)| compute_rlc(a, [w0, w1, w2]));
+                region.assign_advice(|| "c", self.input, 0, || c)?;
+
+                Ok(Bit4Ranged { var: w2, val })
+            },
+        )
+    }
+}
+// ANCHOR_END: dynamic_lookup_xor
+
+// Exercises `DynamicLookupChip` end-to-end with the same inputs/instance
+// as `TestCircuit` above, to show the compressed single-column lookup
+// computes the same xor as the fixed three-column version.
+#[derive(Clone, Debug)]
+struct DynamicXorConfig<F: PrimeField> {
+    _ph: PhantomData<F>,
+    advice: Column<Advice>,
+    fixed: Column<Fixed>,
+    instance: Column<Instance>,
+    xor: DynamicLookupChip<F>,
+    arith: ArithmeticChip<F>,
+}
+
+struct DynamicXorCircuit<F: Field> {
+    _ph: PhantomData<F>,
+    a: Value<u8>,
+    b: Value<u8>,
+}
+
+impl<F: PrimeField> DynamicXorCircuit<F> {
+    fn bits(
+        config: &DynamicXorConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        val: Value<u8>,
+    ) -> Result<Bit4Ranged<F>, Error> {
+        let var = config
+            .arith
+            .free(layouter, val.map(|v| F::from_u128(v as u128)))?;
+        Ok(Bit4Ranged { var, val })
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for DynamicXorCircuit<F> {
+    type Config = DynamicXorConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        DynamicXorCircuit {
+            _ph: PhantomData,
+            a: Value::unknown(),
+            b: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = meta.advice_column();
+        let fixed = meta.fixed_column();
+        let instance = meta.instance_column();
+
+        let xor = DynamicLookupChip::configure(meta, advice);
+
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+
+        let arith = ArithmeticChip::configure(meta, advice, fixed);
+
+        DynamicXorConfig {
+            _ph: PhantomData,
+            advice,
+            fixed,
+            instance,
+            xor,
+            arith,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.xor.assign_table(&mut layouter)?;
+
+        let a = Self::bits(&config, &mut layouter, self.a)?;
+        let b = Self::bits(&config, &mut layouter, self.b)?;
+
+        let c = config.xor.xor(&mut layouter, a, b)?;
+
+        layouter.constrain_instance(c.var.cell(), config.instance, 0)?;
+        Ok(())
+    }
+}
+
 fn main() {
     use halo2_proofs::halo2curves::bn256::Fr;
 
@@ -423,6 +672,7 @@ fn main() {
     // run the MockProver
     let circuit = TestCircuit::<Fr> {
         _ph: PhantomData,
+        bits: 4,
         a: Value::known(0xe),
         b: Value::known(0xb),
     };
@@ -436,6 +686,7 @@ fn main() {
 
     let vk_circuit = TestCircuit::<Fr> {
         _ph: PhantomData,
+        bits: 4,
         a: Value::unknown(),
         b: Value::unknown(),
     };
@@ -485,4 +736,28 @@ fn main() {
         &mut transcript,
     )
     .unwrap();
+
+    // same `TestCircuit` code path, a different `Params` (3-bit/8x8 table
+    // instead of 4-bit/16x16): `configure_with_params` sizes the xor table
+    // purely from the runtime parameter, no recompilation needed
+    println!("check witness (3-bit table)");
+    let small_circuit = TestCircuit::<Fr> {
+        _ph: PhantomData,
+        bits: 3,
+        a: Value::known(0x5),
+        b: Value::known(0x3),
+    };
+    let small_instances = vec![Fr::from_u128(0x6 as u128)];
+    let prover = MockProver::run(k, &small_circuit, vec![small_instances]).unwrap();
+    prover.verify().unwrap();
+
+    println!("check dynamic xor equivalence");
+
+    let dyn_circuit = DynamicXorCircuit::<Fr> {
+        _ph: PhantomData,
+        a: Value::known(0xe),
+        b: Value::known(0xb),
+    };
+    let prover = MockProver::run(k, &dyn_circuit, vec![instances.clone()]).unwrap();
+    prover.verify().unwrap();
 }
@@ -1,4 +1,4 @@
-use std::{iter, marker::PhantomData};
+use std::{cell::RefCell, iter, marker::PhantomData};
 
 use halo2_proofs::{
     circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
@@ -45,6 +45,17 @@ struct LookupChip<F: Field> {
     input1: Column<Advice>,
     input2: Column<Advice>,
     output: Column<Advice>,
+
+    // nibble decomposition, used to shrink the XOR table from 256x256
+    // down to 16x16: `q_nibble` enforces `n_value = 16*n_hi + n_lo` and
+    // range-checks `n_hi`/`n_lo` against the 0..16 `range` table.
+    range: TableColumn,
+    q_nibble: Selector,
+    q_range: Selector,
+    n_value: Column<Advice>,
+    n_hi: Column<Advice>,
+    n_lo: Column<Advice>,
+
     _ph: PhantomData<F>,
 }
 
@@ -93,6 +104,41 @@ impl<F: PrimeField> LookupChip<F> {
             ]
         });
 
+        let range = meta.lookup_table_column();
+        let q_nibble = meta.selector();
+        let q_range = meta.complex_selector();
+        let n_value = meta.advice_column();
+        let n_hi = meta.advice_column();
+        let n_lo = meta.advice_column();
+
+        meta.enable_equality(n_value);
+        meta.enable_equality(n_hi);
+        meta.enable_equality(n_lo);
+
+        // n_value = 16 * n_hi + n_lo
+        meta.create_gate("nibble compose", |meta| {
+            let q_nibble = meta.query_selector(q_nibble);
+            let value = meta.query_advice(n_value, Rotation::cur());
+            let hi = meta.query_advice(n_hi, Rotation::cur());
+            let lo = meta.query_advice(n_lo, Rotation::cur());
+            vec![q_nibble * (value - (hi * F::from(16) + lo))]
+        });
+
+        // only needed when splitting an arbitrary byte (`decompose`): a
+        // `compose`'d value is already a valid nibble by construction,
+        // since it comes straight out of `xor_nibble_table`'s output
+        meta.lookup("nibble hi range", |meta| {
+            let q_range = meta.query_selector(q_range);
+            let hi = meta.query_advice(n_hi, Rotation::cur());
+            vec![(q_range.clone() * hi, range)]
+        });
+
+        meta.lookup("nibble lo range", |meta| {
+            let q_range = meta.query_selector(q_range);
+            let lo = meta.query_advice(n_lo, Rotation::cur());
+            vec![(q_range * lo, range)]
+        });
+
         Self {
             enable,
             typ,
@@ -103,55 +149,28 @@ impl<F: PrimeField> LookupChip<F> {
             input1,
             input2,
             output,
+            range,
+            q_nibble,
+            q_range,
+            n_value,
+            n_hi,
+            n_lo,
             _ph: PhantomData,
         }
     }
 
-    // Populate the lookup table with the required operation for AES
+    // Populate the lookup table with the required operation for AES. Unlike
+    // `LogUpChip` (which assigns only the distinct operations actually
+    // queried, each with its multiplicity), a plookup table is fixed at
+    // keygen and must hold every tuple any witness could query, so a byte-level XOR table would force
+    // `k >= 17`. Instead this table only ever sees 4-bit XOR entries
+    // (16x16 = 256 rows); `xor` below decomposes each byte into nibbles
+    // to use it.
     fn initialize(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
-        let mut entries = Vec::new();
-
-        // XOR
-        for i in 0..=0xff {
-            for j in 0..=0xff {
-                entries.push((
-                    TYP_XOR,
-                    F::from_u128(i as u128),
-                    F::from_u128(j as u128),
-                    F::from_u128(i ^ j),
-                ));
-            }
-        }
-
-        // MUL2
-        for inp in 0..=0xff {
-            entries.push((
-                TYP_MUL2,
-                F::from_u128(inp as u128),
-                F::from_u128(0),
-                F::from_u128(op_mul2(inp) as u128),
-            ));
-        }
-
-        // MUL3
-        for inp in 0..=0xff {
-            entries.push((
-                TYP_MUL3,
-                F::from_u128(inp as u128),
-                F::from_u128(0),
-                F::from_u128(op_mul3(inp) as u128),
-            ));
-        }
-
-        // SBOX
-        for inp in 0..=0xff {
-            entries.push((
-                TYP_SBOX,
-                F::from_u128(inp as u128),
-                F::from_u128(0),
-                F::from_u128(SBOX[inp as usize] as u128),
-            ));
-        }
+        let entries = aes_unary_op_table()
+            .into_iter()
+            .chain(xor_nibble_table())
+            .collect::<Vec<_>>();
 
         layouter.assign_table(
             || "aes lookups",
@@ -164,48 +183,145 @@ impl<F: PrimeField> LookupChip<F> {
 
                 // add the rest of the entries
                 let mut nxt = 1;
-                for (typ, inp1, inp2, outp) in entries.iter().cloned() {
+                for (typ, inp1, inp2, outp) in entries.iter().copied() {
                     tbl.assign_cell(
                         || "typ",
                         self.typ,
                         nxt,
                         || Value::known(F::from_u128(typ as u128)),
                     )?;
-                    tbl.assign_cell(|| "in1", self.in1, nxt, || Value::known(inp1))?;
-                    tbl.assign_cell(|| "in2", self.in2, nxt, || Value::known(inp2))?;
-                    tbl.assign_cell(|| "out", self.out, nxt, || Value::known(outp))?;
+                    tbl.assign_cell(
+                        || "in1",
+                        self.in1,
+                        nxt,
+                        || Value::known(F::from_u128(inp1 as u128)),
+                    )?;
+                    tbl.assign_cell(
+                        || "in2",
+                        self.in2,
+                        nxt,
+                        || Value::known(F::from_u128(inp2 as u128)),
+                    )?;
+                    tbl.assign_cell(
+                        || "out",
+                        self.out,
+                        nxt,
+                        || Value::known(F::from_u128(outp as u128)),
+                    )?;
                     nxt += 1;
                 }
                 Ok(())
             },
+        )?;
+
+        layouter.assign_table(
+            || "nibble range",
+            |mut tbl| {
+                for v in 0..0x10u64 {
+                    tbl.assign_cell(|| "range", self.range, v as usize, || {
+                        Value::known(F::from_u128(v as u128))
+                    })?;
+                }
+                Ok(())
+            },
         )
     }
 
-    fn xor(
+    // splits `byte` into (hi, lo) nibbles with `byte = 16*hi + lo`, each
+    // range-checked against the 0..16 `range` table
+    fn decompose(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        byte: Byte<F>,
+    ) -> Result<(Byte<F>, Byte<F>), Error> {
+        layouter.assign_region(
+            || "nibble decompose",
+            |mut reg| {
+                self.q_nibble.enable(&mut reg, 0)?;
+                self.q_range.enable(&mut reg, 0)?;
+                byte.cell.copy_advice(|| "value", &mut reg, self.n_value, 0)?;
+
+                let hi_val = byte.value.map(|v| v >> 4);
+                let lo_val = byte.value.map(|v| v & 0xf);
+
+                let hi_cell = reg.assign_advice(
+                    || "hi",
+                    self.n_hi,
+                    0,
+                    || hi_val.map(|v| F::from_u128(v as u128)),
+                )?;
+                let lo_cell = reg.assign_advice(
+                    || "lo",
+                    self.n_lo,
+                    0,
+                    || lo_val.map(|v| F::from_u128(v as u128)),
+                )?;
+
+                Ok((
+                    Byte {
+                        value: hi_val,
+                        cell: hi_cell,
+                    },
+                    Byte {
+                        value: lo_val,
+                        cell: lo_cell,
+                    },
+                ))
+            },
+        )
+    }
+
+    // recombines a (hi, lo) nibble pair into a single byte, `byte = 16*hi + lo`
+    fn compose(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        hi: Byte<F>,
+        lo: Byte<F>,
+    ) -> Result<Byte<F>, Error> {
+        layouter.assign_region(
+            || "nibble compose",
+            |mut reg| {
+                self.q_nibble.enable(&mut reg, 0)?;
+                hi.cell.copy_advice(|| "hi", &mut reg, self.n_hi, 0)?;
+                lo.cell.copy_advice(|| "lo", &mut reg, self.n_lo, 0)?;
+
+                let value = hi.value.zip(lo.value).map(|(hi, lo)| (hi << 4) | lo);
+                let cell = reg.assign_advice(
+                    || "value",
+                    self.n_value,
+                    0,
+                    || value.map(|v| F::from_u128(v as u128)),
+                )?;
+
+                Ok(Byte { value, cell })
+            },
+        )
+    }
+
+    // looks up a single binary operation of the given `typ` against the
+    // op table, e.g. the 4-bit XOR used by `xor` below
+    fn lookup_binary(
         &self,
         layouter: &mut impl Layouter<F>,
+        typ: u64,
         inp1: Byte<F>,
         inp2: Byte<F>,
+        compute: impl Fn(u8, u8) -> u8,
     ) -> Result<Byte<F>, Error> {
         layouter.assign_region(
-            || "xor",
+            || "binary op",
             |mut reg| {
                 self.enable.enable(&mut reg, 0)?;
                 reg.assign_fixed(
                     || "typ",
                     self.entryt,
                     0,
-                    || Value::known(F::from_u128(TYP_XOR as u128)),
+                    || Value::known(F::from_u128(typ as u128)),
                 )?;
                 inp1.cell.copy_advice(|| "inp1", &mut reg, self.input1, 0)?;
                 inp2.cell.copy_advice(|| "inp2", &mut reg, self.input2, 0)?;
 
-                // compute value = inp1 ^ inp2
-                let value = inp1
-                    .value
-                    .and_then(|a| inp2.value.and_then(|b| Value::known(a ^ b)));
-
-                // assign value to output
+                let value = inp1.value.zip(inp2.value).map(|(a, b)| compute(a, b));
                 let assigned = reg.assign_advice(
                     || "out",
                     self.output,
@@ -221,6 +337,21 @@ impl<F: PrimeField> LookupChip<F> {
         )
     }
 
+    // xor, via nibble decomposition: split both inputs into (hi, lo)
+    // nibbles, look up each 4-bit XOR in the 16x16 table, then recombine
+    fn xor(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        inp1: Byte<F>,
+        inp2: Byte<F>,
+    ) -> Result<Byte<F>, Error> {
+        let (hi1, lo1) = self.decompose(layouter, inp1)?;
+        let (hi2, lo2) = self.decompose(layouter, inp2)?;
+        let hi_xor = self.lookup_binary(layouter, TYP_XOR_NIBBLE, hi1, hi2, |a, b| a ^ b)?;
+        let lo_xor = self.lookup_binary(layouter, TYP_XOR_NIBBLE, lo1, lo2, |a, b| a ^ b)?;
+        self.compose(layouter, hi_xor, lo_xor)
+    }
+
     fn sbox(&self, layouter: &mut impl Layouter<F>, inp: Byte<F>) -> Result<Byte<F>, Error> {
         //
         layouter.assign_region(
@@ -404,10 +535,255 @@ impl<F: PrimeField> LookupChip<F> {
     }
 }
 
+// ANCHOR: dynamic_lookup
+// A *dynamic* lookup: unlike `configure`'s `TableColumn`s (a fixed table,
+// populated once by `initialize` before any witness exists), both sides
+// here are advice columns gated by their own selector. That lets the
+// "table" side depend on witnessed data -- a per-proof S-box, or any
+// operation table only known at proving time -- rather than being baked
+// into the circuit ahead of synthesis. Mirrors halo2's
+// `frontend_backend_split` test's `s_lookup`/`s_ltable` dynamic lookup:
+// `meta.lookup_any` checks `[1, a, b]` (gated by `s_lookup`) against
+// `[1, d, c]` (gated by `s_ltable`) -- the table side's columns land in
+// swapped order purely because that's the convention the upstream test
+// uses, not for any deeper reason.
+#[derive(Clone, Debug)]
+struct DynamicLookupChip<F: Field> {
+    s_lookup: Selector,
+    s_ltable: Selector,
+    a: Column<Advice>,
+    b: Column<Advice>,
+    c: Column<Advice>,
+    d: Column<Advice>,
+    _ph: PhantomData<F>,
+}
+
+impl<F: PrimeField> LookupChip<F> {
+    // `a`/`b` are the querying side (one row per lookup, gated by
+    // `s_lookup`); `c`/`d` are the table side (one row per table entry,
+    // gated by `s_ltable`). Neither is populated here -- both are filled
+    // at runtime via `DynamicLookupChip::assign_input`/`assign_table`.
+    fn configure_dynamic(
+        meta: &mut ConstraintSystem<F>,
+        a: Column<Advice>,
+        b: Column<Advice>,
+        c: Column<Advice>,
+        d: Column<Advice>,
+    ) -> DynamicLookupChip<F> {
+        let s_lookup = meta.complex_selector();
+        let s_ltable = meta.complex_selector();
+
+        meta.lookup_any("dynamic lookup", |meta| {
+            let s_lookup = meta.query_selector(s_lookup);
+            let s_ltable = meta.query_selector(s_ltable);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            let d = meta.query_advice(d, Rotation::cur());
+
+            // (1, a, b) in (1, d, c): gating the leading `1` by each side's
+            // own selector lets an unselected row fall back to matching
+            // the all-zero tuple instead of spuriously matching a real one
+            vec![
+                (s_lookup.clone(), s_ltable.clone()),
+                (s_lookup.clone() * a, s_ltable.clone() * d),
+                (s_lookup * b, s_ltable * c),
+            ]
+        });
+
+        DynamicLookupChip {
+            s_lookup,
+            s_ltable,
+            a,
+            b,
+            c,
+            d,
+            _ph: PhantomData,
+        }
+    }
+
+    // A shuffle: proves the witnessed `a` column is a permutation of the
+    // witnessed `b` column. Uses `meta.shuffle` directly rather than
+    // hand-rolling the multiset-equality argument `ShuffleChip` in
+    // session-6.rs builds from a verifier challenge -- this halo2 fork
+    // exposes a native shuffle constraint, so there's no reason to
+    // re-derive one here.
+    fn configure_shuffle(
+        meta: &mut ConstraintSystem<F>,
+        a: Column<Advice>,
+        b: Column<Advice>,
+    ) -> ShuffleTableChip<F> {
+        let s_shuffle = meta.complex_selector();
+        let s_stable = meta.complex_selector();
+
+        meta.shuffle("dynamic shuffle", |meta| {
+            let s_shuffle = meta.query_selector(s_shuffle);
+            let s_stable = meta.query_selector(s_stable);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            vec![(s_shuffle * a, s_stable * b)]
+        });
+
+        ShuffleTableChip {
+            s_shuffle,
+            s_stable,
+            a,
+            b,
+            _ph: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField> DynamicLookupChip<F> {
+    // query side: claims `(key, value)` appears as a table row (see
+    // `assign_table`)
+    fn assign_input(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        key: Value<F>,
+        value: Value<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "dynamic lookup input",
+            |mut region| {
+                self.s_lookup.enable(&mut region, 0)?;
+                region.assign_advice(|| "key", self.a, 0, || key)?;
+                region.assign_advice(|| "value", self.b, 0, || value)?;
+                Ok(())
+            },
+        )
+    }
+
+    // table side: note the swap baked into `configure_dynamic`'s gate --
+    // `key` lands in `self.d`, `value` in `self.c`
+    fn assign_table(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        key: Value<F>,
+        value: Value<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "dynamic lookup table row",
+            |mut region| {
+                self.s_ltable.enable(&mut region, 0)?;
+                region.assign_advice(|| "key", self.d, 0, || key)?;
+                region.assign_advice(|| "value", self.c, 0, || value)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ShuffleTableChip<F: Field> {
+    s_shuffle: Selector,
+    s_stable: Selector,
+    a: Column<Advice>,
+    b: Column<Advice>,
+    _ph: PhantomData<F>,
+}
+
+impl<F: PrimeField> ShuffleTableChip<F> {
+    fn assign_input(&self, layouter: &mut impl Layouter<F>, value: Value<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "shuffle input",
+            |mut region| {
+                self.s_shuffle.enable(&mut region, 0)?;
+                region.assign_advice(|| "a", self.a, 0, || value)?;
+                Ok(())
+            },
+        )
+    }
+
+    fn assign_table(&self, layouter: &mut impl Layouter<F>, value: Value<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "shuffle table row",
+            |mut region| {
+                self.s_stable.enable(&mut region, 0)?;
+                region.assign_advice(|| "b", self.b, 0, || value)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+// Exercises `configure_dynamic`/`configure_shuffle` end-to-end: the lookup
+// checks that three `(key, value)` pairs each appear in a 3-entry table
+// only witnessed at proving time (never baked into the circuit), and the
+// shuffle checks that a runtime permutation of `[1, 2, 3]` really is one.
+#[derive(Clone, Debug)]
+struct DynamicDemoConfig<F: Field> {
+    lookup: DynamicLookupChip<F>,
+    shuffle: ShuffleTableChip<F>,
+}
+
+struct DynamicDemoCircuit<F: Field> {
+    _ph: PhantomData<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for DynamicDemoCircuit<F> {
+    type Config = DynamicDemoConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        DynamicDemoCircuit { _ph: PhantomData }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let c = meta.advice_column();
+        let d = meta.advice_column();
+        let lookup = LookupChip::configure_dynamic(meta, a, b, c, d);
+
+        let sa = meta.advice_column();
+        let sb = meta.advice_column();
+        let shuffle = LookupChip::configure_shuffle(meta, sa, sb);
+
+        DynamicDemoConfig { lookup, shuffle }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config, //
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        // the "table": a runtime-chosen map only known at proving time
+        let table: [(u64, u64); 3] = [(1, 10), (2, 20), (3, 30)];
+        for &(key, value) in &table {
+            config.lookup.assign_table(
+                &mut layouter,
+                Value::known(F::from(key)),
+                Value::known(F::from(value)),
+            )?;
+        }
+        // query every table entry back through the lookup side
+        for &(key, value) in &table {
+            config.lookup.assign_input(
+                &mut layouter,
+                Value::known(F::from(key)),
+                Value::known(F::from(value)),
+            )?;
+        }
+
+        // [1, 2, 3] is a permutation of [3, 1, 2]
+        for v in [1u64, 2, 3] {
+            config.shuffle.assign_input(&mut layouter, Value::known(F::from(v)))?;
+        }
+        for v in [3u64, 1, 2] {
+            config.shuffle.assign_table(&mut layouter, Value::known(F::from(v)))?;
+        }
+
+        Ok(())
+    }
+}
+// ANCHOR_END: dynamic_lookup
+
 const TYP_XOR: u64 = 2;
 const TYP_SBOX: u64 = 1;
 const TYP_MUL2: u64 = 3;
 const TYP_MUL3: u64 = 4;
+const TYP_XOR_NIBBLE: u64 = 5;
 
 const SBOX: [u8; 0x100] = [
     0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
@@ -434,6 +810,602 @@ const SBOX: [u8; 0x100] = [
 // for all a:
 //   (SBOX, a, 0, sbox(a)) <-- unary operation
 
+// the canonical (typ, in1, in2, out) relation: every byte XOR pair, plus
+// the unary MUL2/MUL3/SBOX tables with in2 fixed to zero. `LookupChip`
+// loads only its unary part (see `aes_unary_op_table`); `LogUpChip::finalize`
+// takes its filler rows from it. Kept in raw integer form (rather than
+// already folded into `F`), the same tuple type `LogUpChip::finalize` uses
+// as its hash-map key when counting multiplicities.
+fn aes_op_table() -> Vec<(u64, u8, u8, u8)> {
+    let mut entries = Vec::new();
+
+    // XOR
+    for i in 0..=0xffu8 {
+        for j in 0..=0xffu8 {
+            entries.push((TYP_XOR, i, j, i ^ j));
+        }
+    }
+
+    entries.extend(aes_unary_op_table());
+    entries
+}
+
+// the unary (MUL2/MUL3/SBOX) part of `aes_op_table`, with `in2` fixed to
+// zero. Shared with `LookupChip::initialize`, which otherwise replaces
+// the byte-level XOR table above with the much smaller `xor_nibble_table`.
+fn aes_unary_op_table() -> Vec<(u64, u8, u8, u8)> {
+    let mut entries = Vec::new();
+
+    // MUL2
+    for inp in 0..=0xffu8 {
+        entries.push((TYP_MUL2, inp, 0, op_mul2(inp)));
+    }
+
+    // MUL3
+    for inp in 0..=0xffu8 {
+        entries.push((TYP_MUL3, inp, 0, op_mul3(inp)));
+    }
+
+    // SBOX
+    for inp in 0..=0xffu8 {
+        entries.push((TYP_SBOX, inp, 0, SBOX[inp as usize]));
+    }
+
+    entries
+}
+
+// the 4-bit XOR table used by `LookupChip::xor`'s nibble decomposition:
+// 16x16 = 256 entries instead of the 256x256 byte-level table above
+fn xor_nibble_table() -> Vec<(u64, u8, u8, u8)> {
+    let mut entries = Vec::new();
+    for hi in 0..0x10u8 {
+        for lo in 0..0x10u8 {
+            entries.push((TYP_XOR_NIBBLE, hi, lo, hi ^ lo));
+        }
+    }
+    entries
+}
+
+// ANCHOR: nibble_xor_demo
+// A small end-to-end exercise of `LookupChip::xor`'s nibble decomposition,
+// independent of the (still in-progress) AES circuit below: xor two
+// secret bytes together and check the result via MockProver.
+#[derive(Clone, Debug)]
+struct NibbleXorConfig<F: Field + Clone> {
+    lookup: LookupChip<F>,
+    input1: Column<Advice>,
+}
+
+impl<F: PrimeField> NibbleXorConfig<F> {
+    fn free(&self, layouter: &mut impl Layouter<F>, value: Value<u8>) -> Result<Byte<F>, Error> {
+        layouter.assign_region(
+            || "free byte",
+            |mut region| {
+                let cell = region.assign_advice(
+                    || "byte",
+                    self.input1,
+                    0,
+                    || value.map(|v| F::from_u128(v as u128)),
+                )?;
+                Ok(Byte { value, cell })
+            },
+        )
+    }
+}
+
+struct NibbleXorCircuit<F: Field> {
+    a: Value<u8>,
+    b: Value<u8>,
+    _ph: PhantomData<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for NibbleXorCircuit<F> {
+    type Config = NibbleXorConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        NibbleXorCircuit {
+            a: Value::unknown(),
+            b: Value::unknown(),
+            _ph: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let fixed = meta.fixed_column();
+        let input1 = meta.advice_column();
+        let input2 = meta.advice_column();
+        let output = meta.advice_column();
+
+        meta.enable_equality(input1);
+        meta.enable_equality(input2);
+        meta.enable_equality(output);
+
+        let lookup = LookupChip::configure(meta, fixed, input1, input2, output);
+
+        NibbleXorConfig { lookup, input1 }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config, //
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.lookup.initialize(&mut layouter)?;
+
+        let a = config.free(&mut layouter, self.a)?;
+        let b = config.free(&mut layouter, self.b)?;
+
+        config.lookup.xor(&mut layouter, a, b)?;
+        Ok(())
+    }
+}
+// ANCHOR_END: nibble_xor_demo
+
+// extracts the concrete value out of a `Value<T>`, or `None` if it is
+// still unknown (e.g. during `without_witnesses()`'s keygen pass)
+fn known_value<T: Copy>(value: Value<T>) -> Option<T> {
+    let mut out = None;
+    value.map(|v| out = Some(v));
+    out
+}
+
+// ANCHOR: logup_chip
+// A drop-in for `LookupChip::configure`/`initialize` that proves the same
+// (typ, in1, in2, out) relation with a log-derivative (LogUp) argument
+// instead of a plookup. The witness side still costs one small region per
+// queried operation (see `op` below), exactly like `LookupChip::xor`, but
+// the table no longer has to be checked against every witness row through
+// `meta.lookup`: each table row instead carries a witnessed multiplicity
+// `m_j` counting how many witness rows hit it, and a single running sum
+// over `1/(beta + t_i)` (witness rows) minus `m_j/(beta + t_j)` (table
+// rows) must vanish.
+//
+// Unlike `LookupChip::initialize`, which must run *before* any operation
+// is queried, `LogUpChip::finalize` must run *after* every `xor`/`sbox`/
+// `mul2`/`mul3` call, since it needs to know the actual multiplicities --
+// and, because it only has to assign a table row per *distinct* operation
+// actually queried (rather than one for every entry of the canonical
+// table), that's also what keeps this cheaper than `LookupChip`'s plain
+// lookup.
+#[derive(Clone, Debug)]
+struct LogUpChip<F: Field> {
+    q_enable: Selector,
+    q_table: Selector,
+    q_acc: Selector,
+    q_last: Selector,
+
+    alpha: Challenge,
+    beta: Challenge,
+
+    entryt: Column<Fixed>,
+    input1: Column<Advice>,
+    input2: Column<Advice>,
+    output: Column<Advice>,
+    inv: Column<Advice>,
+
+    table_typ: Column<Fixed>,
+    table_in1: Column<Fixed>,
+    table_in2: Column<Fixed>,
+    table_out: Column<Fixed>,
+    mult: Column<Advice>,
+
+    term: Column<Advice>,
+    z: Column<Advice>,
+
+    // one entry per witness query: (typ, in1, in2, out, inv-cell), kept
+    // in raw integer form (so `finalize` can use it as a hash-map key
+    // when counting multiplicities) around so `finalize` can compute
+    // multiplicities and chain the accumulator through the
+    // already-assigned `inv` cells. Drained by `finalize`, since a real
+    // multi-phase prover calls `Circuit::synthesize` once per phase and
+    // would otherwise see the same queries pushed again on each call.
+    #[allow(clippy::type_complexity)]
+    queries: RefCell<Vec<(u64, Value<u8>, Value<u8>, Value<u8>, AssignedCell<F, F>)>>,
+    _ph: PhantomData<F>,
+}
+
+impl<F: PrimeField> LogUpChip<F> {
+    fn configure(
+        meta: &mut ConstraintSystem<F>,
+        fixed: Column<Fixed>,
+        input1: Column<Advice>,
+        input2: Column<Advice>,
+        output: Column<Advice>,
+    ) -> Self {
+        let q_enable = meta.selector();
+        let q_table = meta.selector();
+        let q_acc = meta.selector();
+        let q_last = meta.selector();
+
+        let alpha = meta.challenge_usable_after(FirstPhase);
+        let beta = meta.challenge_usable_after(FirstPhase);
+
+        let inv = meta.advice_column_in(SecondPhase);
+        let mult = meta.advice_column();
+        let term = meta.advice_column_in(SecondPhase);
+        let z = meta.advice_column_in(SecondPhase);
+
+        let table_typ = meta.fixed_column();
+        let table_in1 = meta.fixed_column();
+        let table_in2 = meta.fixed_column();
+        let table_out = meta.fixed_column();
+
+        meta.enable_equality(inv);
+        meta.enable_equality(term);
+        meta.enable_equality(z);
+
+        // per-witness-row inverse correctness: inv_i * (beta + t_i) = 1
+        meta.create_gate("logup witness inverse", |meta| {
+            let q_enable = meta.query_selector(q_enable);
+            let typ = meta.query_fixed(fixed, Rotation::cur());
+            let in1 = meta.query_advice(input1, Rotation::cur());
+            let in2 = meta.query_advice(input2, Rotation::cur());
+            let out = meta.query_advice(output, Rotation::cur());
+            let inv = meta.query_advice(inv, Rotation::cur());
+            let alpha = meta.query_challenge(alpha);
+            let beta = meta.query_challenge(beta);
+
+            let t = typ + alpha.clone() * in1 + alpha.clone() * alpha.clone() * in2
+                + alpha.clone() * alpha.clone() * alpha * out;
+            vec![q_enable * (inv * (beta + t) - Expression::Constant(F::ONE))]
+        });
+
+        // per-table-row multiplicity correctness: (-term_j) * (beta + t_j) = mult_j
+        meta.create_gate("logup table term", |meta| {
+            let q_table = meta.query_selector(q_table);
+            let typ = meta.query_fixed(table_typ, Rotation::cur());
+            let in1 = meta.query_fixed(table_in1, Rotation::cur());
+            let in2 = meta.query_fixed(table_in2, Rotation::cur());
+            let out = meta.query_fixed(table_out, Rotation::cur());
+            let mult = meta.query_advice(mult, Rotation::cur());
+            let term = meta.query_advice(term, Rotation::cur());
+            let alpha = meta.query_challenge(alpha);
+            let beta = meta.query_challenge(beta);
+
+            let t = typ + alpha.clone() * in1 + alpha.clone() * alpha.clone() * in2
+                + alpha.clone() * alpha.clone() * alpha * out;
+            vec![q_table * (-term * (beta + t) - mult)]
+        });
+
+        // shared running-sum: z_{i+1} = z_i + term_i. Witness rows copy
+        // their (already-checked) `inv` cell straight into `term`; table
+        // rows assign the negated `m_j/(beta + t_j)` into `term` instead,
+        // so the same gate chains both halves of the argument.
+        meta.create_gate("logup accumulator", |meta| {
+            let q_acc = meta.query_selector(q_acc);
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            let term = meta.query_advice(term, Rotation::cur());
+            vec![q_acc * (z_next - z_cur - term)]
+        });
+
+        // boundary: the final accumulator value must vanish
+        meta.create_gate("logup boundary", |meta| {
+            let q_last = meta.query_selector(q_last);
+            let z = meta.query_advice(z, Rotation::cur());
+            vec![q_last * z]
+        });
+
+        Self {
+            q_enable,
+            q_table,
+            q_acc,
+            q_last,
+            alpha,
+            beta,
+            entryt: fixed,
+            input1,
+            input2,
+            output,
+            inv,
+            table_typ,
+            table_in1,
+            table_in2,
+            table_out,
+            mult,
+            term,
+            z,
+            queries: RefCell::new(Vec::new()),
+            _ph: PhantomData,
+        }
+    }
+
+    // Query one operation, exactly like `LookupChip::xor`/`sbox`/etc, but
+    // record it so `finalize` can later derive the table multiplicities.
+    // `in2 = None` is used for unary ops (SBOX, MUL2, MUL3), which fix
+    // their second input to zero.
+    fn op(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        typ: u64,
+        in1: Byte<F>,
+        in2: Option<Byte<F>>,
+        compute: impl FnOnce(u8, u8) -> u8,
+    ) -> Result<Byte<F>, Error> {
+        let alpha = layouter.get_challenge(self.alpha);
+        let beta = layouter.get_challenge(self.beta);
+        let typ_f = Value::known(F::from_u128(typ as u128));
+
+        layouter.assign_region(
+            || "logup op",
+            |mut region| {
+                self.q_enable.enable(&mut region, 0)?;
+                region.assign_fixed(|| "typ", self.entryt, 0, || typ_f)?;
+                in1.cell.copy_advice(|| "in1", &mut region, self.input1, 0)?;
+
+                let in2_val = match &in2 {
+                    Some(b) => {
+                        b.cell.copy_advice(|| "in2", &mut region, self.input2, 0)?;
+                        b.value
+                    }
+                    None => {
+                        region.assign_advice(|| "in2", self.input2, 0, || Value::known(F::ZERO))?;
+                        Value::known(0u8)
+                    }
+                };
+
+                let value = in1.value.zip(in2_val).map(|(a, b)| compute(a, b));
+                let in1_f = in1.value.map(|v| F::from_u128(v as u128));
+                let in2_f = in2_val.map(|v| F::from_u128(v as u128));
+                let out_f = value.map(|v| F::from_u128(v as u128));
+                let out_cell = region.assign_advice(|| "out", self.output, 0, || out_f)?;
+
+                let t = typ_f
+                    .zip(alpha)
+                    .zip(in1_f)
+                    .zip(in2_f)
+                    .zip(out_f)
+                    .map(|((((typ, alpha), in1), in2), out)| {
+                        typ + alpha * in1 + alpha * alpha * in2 + alpha * alpha * alpha * out
+                    });
+                let inv = beta
+                    .zip(t)
+                    .map(|(beta, t)| (beta + t).invert().unwrap());
+                let inv_cell = region.assign_advice(|| "inv", self.inv, 0, || inv)?;
+
+                self.queries
+                    .borrow_mut()
+                    .push((typ, in1.value, in2_val, value, inv_cell));
+
+                Ok(Byte {
+                    value,
+                    cell: out_cell,
+                })
+            },
+        )
+    }
+
+    fn xor(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        in1: Byte<F>,
+        in2: Byte<F>,
+    ) -> Result<Byte<F>, Error> {
+        self.op(layouter, TYP_XOR, in1, Some(in2), |a, b| a ^ b)
+    }
+
+    fn sbox(&self, layouter: &mut impl Layouter<F>, in1: Byte<F>) -> Result<Byte<F>, Error> {
+        self.op(layouter, TYP_SBOX, in1, None, |a, _| SBOX[a as usize])
+    }
+
+    fn mul2(&self, layouter: &mut impl Layouter<F>, in1: Byte<F>) -> Result<Byte<F>, Error> {
+        self.op(layouter, TYP_MUL2, in1, None, |a, _| op_mul2(a))
+    }
+
+    fn mul3(&self, layouter: &mut impl Layouter<F>, in1: Byte<F>) -> Result<Byte<F>, Error> {
+        self.op(layouter, TYP_MUL3, in1, None, |a, _| op_mul3(a))
+    }
+
+    // Must be called once, after every `xor`/`sbox`/`mul2`/`mul3` query
+    // has been made: assigns only the operations that were actually
+    // queried -- not the full ~66k-row canonical table a plain lookup
+    // would need -- each tagged with its real multiplicity, and chains
+    // the running-sum accumulator through both the witness queries and
+    // these deduplicated table rows.
+    fn finalize(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        let alpha = layouter.get_challenge(self.alpha);
+        let beta = layouter.get_challenge(self.beta);
+
+        // take (rather than borrow) so a later phase's queries, if any,
+        // start from a clean slate instead of compounding onto this one
+        let queries = self.queries.take();
+
+        // `queries.len()` is fixed by how many times `xor`/`sbox`/`mul2`/
+        // `mul3` were called -- part of this circuit's static shape, not
+        // its witness -- so it's always a safe upper bound on the number
+        // of *distinct* entries a witness can produce: there can never be
+        // more than there were queries to begin with. Reserving exactly
+        // this many table-row slots keeps the region's shape fixed while
+        // still only paying for the operations this circuit actually
+        // performs, instead of the full canonical table.
+        let capacity = queries.len();
+
+        // build the multiplicity counts once, up front, instead of
+        // rescanning all of `queries` per table row. `None` (rather than
+        // a map with a missing key) signals that at least one query
+        // value is still unknown (e.g. during `keygen_vk`'s
+        // `without_witnesses()` pass), in which case every multiplicity
+        // is left unknown too and the table rows fall back to filler
+        // entries from the canonical table.
+        let mut counts = Some(std::collections::HashMap::<(u64, u8, u8, u8), u64>::new());
+        for (typ, in1, in2, out, _) in queries.iter() {
+            let known = known_value(*in1)
+                .zip(known_value(*in2))
+                .zip(known_value(*out));
+            match (counts.as_mut(), known) {
+                (Some(map), Some(((in1, in2), out))) => {
+                    *map.entry((*typ, in1, in2, out)).or_insert(0) += 1;
+                }
+                _ => counts = None,
+            }
+        }
+
+        // the distinct operations actually queried -- this is the whole
+        // entry list LogUp needs, versus every row of `aes_op_table()`
+        let entries: Vec<(u64, u8, u8, u8)> = match &counts {
+            Some(map) => map.keys().copied().collect(),
+            None => Vec::new(),
+        };
+        let fallback = aes_op_table();
+
+        layouter.assign_region(
+            || "logup accumulator",
+            |mut region| {
+                let mut z = region.assign_advice(|| "z0", self.z, 0, || Value::known(F::ZERO))?;
+                let mut row = 0;
+
+                // witness side: z_{i+1} = z_i + inv_i
+                for (_, _, _, _, inv_cell) in queries.iter() {
+                    self.q_acc.enable(&mut region, row)?;
+                    let term = inv_cell.copy_advice(|| "term", &mut region, self.term, row)?;
+                    let next = z
+                        .value()
+                        .copied()
+                        .zip(term.value().copied())
+                        .map(|(z, t)| z + t);
+                    z = region.assign_advice(|| "z", self.z, row + 1, || next)?;
+                    row += 1;
+                }
+
+                // table side: z_{i+1} = z_i - m_j/(beta + t_j), one row
+                // per distinct operation actually queried, padded with
+                // multiplicity-0 filler rows (borrowed from the canonical
+                // table, so they're still valid (typ,in1,in2,out) tuples)
+                // up to `capacity`, so the region's shape doesn't depend
+                // on how many distinct entries a given witness produces
+                for slot in 0..capacity {
+                    self.q_table.enable(&mut region, row)?;
+                    self.q_acc.enable(&mut region, row)?;
+
+                    let real_entry = entries.get(slot).copied();
+                    let (typ, in1, in2, out) =
+                        real_entry.unwrap_or_else(|| fallback[slot % fallback.len()]);
+
+                    let typ_f = F::from_u128(typ as u128);
+                    let in1_f = F::from_u128(in1 as u128);
+                    let in2_f = F::from_u128(in2 as u128);
+                    let out_f = F::from_u128(out as u128);
+
+                    region.assign_fixed(|| "typ", self.table_typ, row, || Value::known(typ_f))?;
+                    region.assign_fixed(|| "in1", self.table_in1, row, || Value::known(in1_f))?;
+                    region.assign_fixed(|| "in2", self.table_in2, row, || Value::known(in2_f))?;
+                    region.assign_fixed(|| "out", self.table_out, row, || Value::known(out_f))?;
+
+                    // multiplicity: how many witness queries hit this
+                    // entry. Padding rows past the end of `entries`
+                    // always get multiplicity 0 -- even if their filler
+                    // tuple happens to coincide with a real entry -- so
+                    // they never double-count in the running sum.
+                    let mult = match (&counts, real_entry) {
+                        (Some(map), Some(key)) => {
+                            Value::known(F::from_u128(map.get(&key).copied().unwrap_or(0) as u128))
+                        }
+                        (Some(_), None) => Value::known(F::ZERO),
+                        (None, _) => Value::unknown(),
+                    };
+                    region.assign_advice(|| "mult", self.mult, row, || mult)?;
+
+                    let t_j = alpha.map(|a| typ_f + a * in1_f + a * a * in2_f + a * a * a * out_f);
+                    let term_val = beta
+                        .zip(t_j)
+                        .zip(mult)
+                        .map(|((beta, t), m)| -(m * (beta + t).invert().unwrap()));
+                    let term = region.assign_advice(|| "term", self.term, row, || term_val)?;
+
+                    let next = z
+                        .value()
+                        .copied()
+                        .zip(term.value().copied())
+                        .map(|(z, t)| z + t);
+                    z = region.assign_advice(|| "z", self.z, row + 1, || next)?;
+                    row += 1;
+                }
+
+                self.q_last.enable(&mut region, row)?;
+                Ok(())
+            },
+        )
+    }
+}
+// ANCHOR_END: logup_chip
+
+// ANCHOR: logup_demo
+// A small end-to-end exercise of `LogUpChip`, independent of the (still
+// in-progress) AES circuit above: xor two secret bytes together, run the
+// result through the SBOX, and check it all via MockProver.
+#[derive(Clone, Debug)]
+struct LogUpConfig<F: Field + Clone> {
+    logup: LogUpChip<F>,
+    input1: Column<Advice>,
+}
+
+impl<F: PrimeField> LogUpConfig<F> {
+    fn free(&self, layouter: &mut impl Layouter<F>, value: Value<u8>) -> Result<Byte<F>, Error> {
+        layouter.assign_region(
+            || "free byte",
+            |mut region| {
+                let cell = region.assign_advice(
+                    || "byte",
+                    self.input1,
+                    0,
+                    || value.map(|v| F::from_u128(v as u128)),
+                )?;
+                Ok(Byte { value, cell })
+            },
+        )
+    }
+}
+
+struct LogUpCircuit<F: Field> {
+    a: Value<u8>,
+    b: Value<u8>,
+    _ph: PhantomData<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for LogUpCircuit<F> {
+    type Config = LogUpConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        LogUpCircuit {
+            a: Value::unknown(),
+            b: Value::unknown(),
+            _ph: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let fixed = meta.fixed_column();
+        let input1 = meta.advice_column();
+        let input2 = meta.advice_column();
+        let output = meta.advice_column();
+
+        meta.enable_equality(input1);
+        meta.enable_equality(input2);
+        meta.enable_equality(output);
+
+        let logup = LogUpChip::configure(meta, fixed, input1, input2, output);
+
+        LogUpConfig { logup, input1 }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config, //
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let a = config.free(&mut layouter, self.a)?;
+        let b = config.free(&mut layouter, self.b)?;
+
+        let xored = config.logup.xor(&mut layouter, a, b)?;
+        config.logup.sbox(&mut layouter, xored)?;
+
+        config.logup.finalize(&mut layouter)
+    }
+}
+// ANCHOR_END: logup_demo
+
 #[derive(Clone, Debug)]
 struct TestConfig<F: Field + Clone> {
     _ph: PhantomData<F>,
@@ -556,6 +1528,41 @@ fn main() {
             0xde, 0xad, 0xc0, 0xde, //
         ]),
     };
-    let prover = MockProver::run(17, &circuit, vec![]).unwrap();
+    // the nibble-decomposed XOR table only needs ~1k rows (down from the
+    // ~66k a byte-level XOR table would require), so k can drop well
+    // below 17 -- kept at 14 rather than the table's own minimum so the
+    // full (currently TODO'd) AES circuit still has room to fit once the
+    // exercise is completed, since each xor now costs a handful of rows
+    // instead of one
+    let prover = MockProver::run(14, &circuit, vec![]).unwrap();
+    prover.verify().unwrap();
+
+    // demonstrate the nibble-decomposed XOR on its own, so the new gates
+    // and range checks are actually exercised
+    let nibble_xor_circuit = NibbleXorCircuit::<Fr> {
+        a: Value::known(0x53),
+        b: Value::known(0xca),
+        _ph: PhantomData,
+    };
+    let prover = MockProver::run(11, &nibble_xor_circuit, vec![]).unwrap();
+    prover.verify().unwrap();
+
+    // demonstrate the LogUp-based variant of the same table construction.
+    // `LogUpChip::finalize` only assigns a table row per distinct
+    // operation this circuit actually queries (one xor, one sbox) rather
+    // than `aes_op_table()`'s ~66k rows, so -- unlike the plain-lookup
+    // `circuit` above -- k no longer has to cover the canonical table's
+    // own size.
+    let logup_circuit = LogUpCircuit::<Fr> {
+        a: Value::known(0x53),
+        b: Value::known(0xca),
+        _ph: PhantomData,
+    };
+    let prover = MockProver::run(6, &logup_circuit, vec![]).unwrap();
+    prover.verify().unwrap();
+
+    // demonstrate the dynamic (column-to-column) lookup and shuffle
+    let dynamic_demo_circuit = DynamicDemoCircuit::<Fr> { _ph: PhantomData };
+    let prover = MockProver::run(6, &dynamic_demo_circuit, vec![]).unwrap();
     prover.verify().unwrap();
 }
@@ -1,16 +1,104 @@
 use std::marker::PhantomData;
 
 use halo2_proofs::{
-    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
+    circuit::{AssignedCell, Cell, Chip, Layouter, Region, SimpleFloorPlanner, Value},
     dev::MockProver,
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
     plonk::{
-        self, Advice, Circuit, Column, ConstraintSystem, Expression, Fixed, Selector, VirtualCells,
+        self, create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Circuit, Column,
+        ConstraintSystem, Expression, Fixed, Instance, ProvingKey, Selector, TableColumn,
+        VerifyingKey, VirtualCells,
+    },
+    poly::{
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            multiopen::{ProverSHPLONK, VerifierSHPLONK},
+            strategy::SingleStrategy,
+        },
+        Rotation, VerificationStrategy,
+    },
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
     },
-    poly::Rotation,
 };
 
 use ff::{Field, PrimeField};
 
+// ANCHOR: utilities_instructions
+// A `Var` wraps an assigned cell's `Cell` and known `Value<F>` together,
+// mirroring the Orchard `UtilitiesInstructions` pattern: gadgets are
+// written against `Self::Var` instead of a raw `AssignedCell`, so a cell
+// produced by one chip (e.g. `RomChip::read`) can be fed directly into
+// another (e.g. `ArithmeticChip::add`) as long as both implement
+// `UtilitiesInstructions` with the same `Var`, with no re-implementation
+// of region assignment at the call site.
+#[derive(Clone, Debug)]
+struct Var<F: Field> {
+    cell: Cell,
+    value: Value<F>,
+}
+
+impl<F: Field> From<AssignedCell<F, F>> for Var<F> {
+    fn from(assigned: AssignedCell<F, F>) -> Self {
+        Var {
+            cell: assigned.cell(),
+            value: assigned.value().cloned(),
+        }
+    }
+}
+
+trait UtilitiesInstructions<F: Field>: Chip<F> {
+    type Var: Clone + std::fmt::Debug;
+
+    /// Load a private value into a fresh cell of `column`.
+    fn load_private(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        column: Column<Advice>,
+        value: Value<F>,
+    ) -> Result<Self::Var, plonk::Error>;
+
+    /// Copy `var` into a fresh cell of `column`.
+    fn copy(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        var: &Self::Var,
+        column: Column<Advice>,
+    ) -> Result<Self::Var, plonk::Error>;
+}
+// ANCHOR_END: utilities_instructions
+
+// ANCHOR: arithmetic_instructions
+trait ArithmeticInstructions<F: Field>: UtilitiesInstructions<F> {
+    fn mul(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        lhs: &Self::Var,
+        rhs: &Self::Var,
+    ) -> Result<Self::Var, plonk::Error>;
+
+    fn add(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        lhs: &Self::Var,
+        rhs: &Self::Var,
+    ) -> Result<Self::Var, plonk::Error>;
+}
+// ANCHOR_END: arithmetic_instructions
+
+// ANCHOR: memory_instructions
+trait MemoryInstructions<F: Field>: UtilitiesInstructions<F> {
+    /// Look up `rom[index]`, proving the result is consistent with the
+    /// fixed ROM table without re-checking every entry.
+    fn read(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        rom: &Value<Vec<F>>,
+        index: Index<F>,
+    ) -> Result<Self::Var, plonk::Error>;
+}
+// ANCHOR_END: memory_instructions
+
 // Gate:
 //
 // Prover can choose an array
@@ -21,6 +109,13 @@ use ff::{Field, PrimeField};
 
 const MAX_MEMORY: usize = 5;
 
+// Like `MAX_MEMORY`, the RAM trace length is a fixed circuit parameter,
+// not something derived from the witness: the row layout (which
+// selectors get enabled) must be identical whether `synthesize` is
+// running with `self.ram_trace` known (proving) or `Value::unknown()`
+// (`keygen_vk`/`keygen_pk` via `without_witnesses`).
+const MAX_RAM_ACCESSES: usize = 4;
+
 // (0, ?)
 // (1, ?)
 // (2, ?)
@@ -30,11 +125,14 @@ struct RomTable<F: Field> {
     idx: Column<Fixed>,
     arr: Column<Advice>,
     flag: Column<Fixed>,
+    // carried on the table instead of read off a global `const`, so
+    // multiple ROMs of different sizes can coexist in one circuit
+    max_memory: usize,
     _ph: PhantomData<F>,
 }
 
 impl<F: PrimeField> RomTable<F> {
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+    fn configure(meta: &mut ConstraintSystem<F>, max_memory: usize) -> Self {
         let idx = meta.fixed_column();
         let arr = meta.advice_column();
         let flag = meta.fixed_column();
@@ -43,6 +141,7 @@ impl<F: PrimeField> RomTable<F> {
             idx,
             arr,
             flag,
+            max_memory,
             _ph: PhantomData,
         }
     }
@@ -71,12 +170,12 @@ impl<F: PrimeField> RomTable<F> {
         layouter: &mut impl Layouter<F>,
         memory: Value<&Vec<F>>,
     ) -> Result<(), plonk::Error> {
-        memory.assert_if_known(|m| m.len() == MAX_MEMORY);
+        memory.assert_if_known(|m| m.len() == self.max_memory);
 
         layouter.assign_region(
             || "memory",
             |mut region| {
-                for i in 0..MAX_MEMORY {
+                for i in 0..self.max_memory {
                     println!("Assigning row {}", i);
                     println!("Memory: {:?}", memory.as_ref().map(|m| m[i]));
                     println!("index: {:?}", F::from_u128(i as u128));
@@ -91,7 +190,7 @@ impl<F: PrimeField> RomTable<F> {
 
                 self.assign_row(
                     &mut region,
-                    MAX_MEMORY,
+                    self.max_memory,
                     false,
                     Value::known(F::ZERO),
                     Value::known(F::ZERO),
@@ -160,49 +259,660 @@ impl<F: PrimeField> RomChip<F> {
             _ph: PhantomData,
         }
     }
+}
+
+impl<F: Field> Chip<F> for RomChip<F> {
+    type Config = Self;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        self
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: PrimeField> UtilitiesInstructions<F> for RomChip<F> {
+    type Var = Var<F>;
 
+    fn load_private(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        column: Column<Advice>,
+        value: Value<F>,
+    ) -> Result<Self::Var, plonk::Error> {
+        layouter
+            .assign_region(
+                || "load private",
+                |mut region| region.assign_advice(|| "private", column, 0, || value),
+            )
+            .map(Var::from)
+    }
+
+    fn copy(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        var: &Self::Var,
+        column: Column<Advice>,
+    ) -> Result<Self::Var, plonk::Error> {
+        layouter.assign_region(
+            || "copy",
+            |mut region| {
+                let new = region.assign_advice(|| "copy", column, 0, || var.value)?;
+                region.constrain_equal(new.cell(), var.cell)?;
+                Ok(Var::from(new))
+            },
+        )
+    }
+}
+
+impl<F: PrimeField> MemoryInstructions<F> for RomChip<F> {
     // y = arr[i]
     //
     // pick y
     // (y, i) in (arr, idx)
     // return y
-    fn get(
+    fn read(
         &self,
         layouter: &mut impl Layouter<F>,
         rom: &Value<Vec<F>>,
         input: Index<F>,
-    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+    ) -> Result<Self::Var, plonk::Error> {
+        self.get_many(layouter, rom, &[input])
+            .map(|mut outputs| outputs.remove(0))
+    }
+}
+
+impl<F: PrimeField> RomChip<F> {
+    /// Look up several indices in one region instead of one `assign_region`
+    /// call per read, so a run of lookups costs a single contiguous block
+    /// of rows rather than `indices.len()` separate regions.
+    ///
+    /// `input` is never trusted to be in range: indexing `rom` with
+    /// `Vec::get` instead of `[]` means an out-of-range index just
+    /// produces a witness the `ROM lookup` argument can't justify (since
+    /// only rows `0..max_memory` have `flag = 1`), rather than panicking
+    /// the prover.
+    fn get_many(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        rom: &Value<Vec<F>>,
+        indices: &[Index<F>],
+    ) -> Result<Vec<Var<F>>, plonk::Error> {
         layouter.assign_region(
-            || "get",
+            || "get_many",
             |mut region| {
-                self.rom_enable.enable(&mut region, 0)?;
+                indices
+                    .iter()
+                    .enumerate()
+                    .map(|(row, input)| {
+                        self.rom_enable.enable(&mut region, row)?;
 
-                println!("");
-                println!("input: {:?}", input);
+                        input
+                            .assigned
+                            .copy_advice(|| "input", &mut region, self.input, row)?;
 
-                input
-                    .assigned
-                    .copy_advice(|| "input", &mut region, self.input, 0)?; //
+                        let output = input.value.and_then(|i| {
+                            rom.as_ref().map(|m| m.get(i).copied().unwrap_or(F::ZERO))
+                        });
 
-                let output = input.value.and_then(|i| rom.as_ref().map(|m| m[i]));
+                        region
+                            .assign_advice(|| "output", self.output, row, || output)
+                            .map(Var::from)
+                    })
+                    .collect()
+            },
+        )
+    }
+}
+
+// ANCHOR: arithmetic_chip
+// The standard PLONK gate (`sa*w0 + sb*w1 + sc*w2 + sm*(w0*w1) = 0`), as
+// in `chips.rs`, exposed through `ArithmeticInstructions` so a `RomChip`
+// output `Var` can be fed into `add`/`mul` without any conversion.
+#[derive(Clone, Debug)]
+struct ArithmeticChip<F: Field> {
+    q_enable: Selector,
+    sa: Column<Fixed>,
+    sb: Column<Fixed>,
+    sc: Column<Fixed>,
+    sm: Column<Fixed>,
+    w0: Column<Advice>,
+    w1: Column<Advice>,
+    w2: Column<Advice>,
+    _ph: PhantomData<F>,
+}
+
+impl<F: Field> ArithmeticChip<F> {
+    fn configure(
+        meta: &mut ConstraintSystem<F>,
+        w0: Column<Advice>,
+        w1: Column<Advice>,
+        w2: Column<Advice>,
+    ) -> Self {
+        let q_enable = meta.complex_selector();
+        let sa = meta.fixed_column();
+        let sb = meta.fixed_column();
+        let sc = meta.fixed_column();
+        let sm = meta.fixed_column();
+
+        meta.create_gate("standard", |meta| {
+            let w0 = meta.query_advice(w0, Rotation::cur());
+            let w1 = meta.query_advice(w1, Rotation::cur());
+            let w2 = meta.query_advice(w2, Rotation::cur());
 
-                println!("output: {:?}", output);
+            let sa = meta.query_fixed(sa, Rotation::cur());
+            let sb = meta.query_fixed(sb, Rotation::cur());
+            let sc = meta.query_fixed(sc, Rotation::cur());
+            let sm = meta.query_fixed(sm, Rotation::cur());
 
-                let output = region.assign_advice(|| "output", self.output, 0, || output)?;
+            let q_enable = meta.query_selector(q_enable);
+            vec![q_enable * (sa * w0.clone() + sb * w1.clone() + sc * w2 + sm * (w0 * w1))]
+        });
 
-                println!("rom: {:?}", rom);
-                println!("output: {:?}", output);
-                println!("");
+        Self {
+            q_enable,
+            sa,
+            sb,
+            sc,
+            sm,
+            w0,
+            w1,
+            w2,
+            _ph: PhantomData,
+        }
+    }
+}
 
-                Ok(output)
+impl<F: Field> Chip<F> for ArithmeticChip<F> {
+    type Config = Self;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        self
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: PrimeField> UtilitiesInstructions<F> for ArithmeticChip<F> {
+    type Var = Var<F>;
+
+    fn load_private(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        column: Column<Advice>,
+        value: Value<F>,
+    ) -> Result<Self::Var, plonk::Error> {
+        layouter
+            .assign_region(
+                || "load private",
+                |mut region| region.assign_advice(|| "private", column, 0, || value),
+            )
+            .map(Var::from)
+    }
+
+    fn copy(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        var: &Self::Var,
+        column: Column<Advice>,
+    ) -> Result<Self::Var, plonk::Error> {
+        layouter.assign_region(
+            || "copy",
+            |mut region| {
+                let new = region.assign_advice(|| "copy", column, 0, || var.value)?;
+                region.constrain_equal(new.cell(), var.cell)?;
+                Ok(Var::from(new))
             },
         )
     }
 }
 
+impl<F: PrimeField> ArithmeticInstructions<F> for ArithmeticChip<F> {
+    fn mul(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        lhs: &Self::Var,
+        rhs: &Self::Var,
+    ) -> Result<Self::Var, plonk::Error> {
+        layouter
+            .assign_region(
+                || "mul",
+                |mut region| {
+                    self.q_enable.enable(&mut region, 0)?;
+
+                    let w0 = region.assign_advice(|| "w0", self.w0, 0, || lhs.value)?;
+                    let w1 = region.assign_advice(|| "w1", self.w1, 0, || rhs.value)?;
+                    let w2 = region.assign_advice(
+                        || "w2",
+                        self.w2,
+                        0,
+                        || {
+                            lhs.value
+                                .and_then(|a| rhs.value.and_then(|b| Value::known(a * b)))
+                        },
+                    )?;
+
+                    region.assign_fixed(|| "sa", self.sa, 0, || Value::known(F::ZERO))?;
+                    region.assign_fixed(|| "sb", self.sb, 0, || Value::known(F::ZERO))?;
+                    region.assign_fixed(|| "sc", self.sc, 0, || Value::known(-F::ONE))?;
+                    region.assign_fixed(|| "sm", self.sm, 0, || Value::known(F::ONE))?;
+
+                    region.constrain_equal(w0.cell(), lhs.cell)?;
+                    region.constrain_equal(w1.cell(), rhs.cell)?;
+
+                    Ok(w2)
+                },
+            )
+            .map(Var::from)
+    }
+
+    fn add(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        lhs: &Self::Var,
+        rhs: &Self::Var,
+    ) -> Result<Self::Var, plonk::Error> {
+        layouter
+            .assign_region(
+                || "add",
+                |mut region| {
+                    self.q_enable.enable(&mut region, 0)?;
+
+                    let w0 = region.assign_advice(|| "w0", self.w0, 0, || lhs.value)?;
+                    let w1 = region.assign_advice(|| "w1", self.w1, 0, || rhs.value)?;
+                    let w2 = region.assign_advice(
+                        || "w2",
+                        self.w2,
+                        0,
+                        || {
+                            lhs.value
+                                .and_then(|a| rhs.value.and_then(|b| Value::known(a + b)))
+                        },
+                    )?;
+
+                    region.assign_fixed(|| "sa", self.sa, 0, || Value::known(F::ONE))?;
+                    region.assign_fixed(|| "sb", self.sb, 0, || Value::known(F::ONE))?;
+                    region.assign_fixed(|| "sc", self.sc, 0, || Value::known(-F::ONE))?;
+                    region.assign_fixed(|| "sm", self.sm, 0, || Value::known(F::ZERO))?;
+
+                    region.constrain_equal(w0.cell(), lhs.cell)?;
+                    region.constrain_equal(w1.cell(), rhs.cell)?;
+
+                    Ok(w2)
+                },
+            )
+            .map(Var::from)
+    }
+}
+// ANCHOR_END: arithmetic_chip
+
+// ANCHOR: ram_chip
+// Generalizes `RomTable`'s read-only lookup into read/write RAM using the
+// classic "offline memory checking" trick: the prover commits to the
+// access trace in program order *and* the same accesses sorted by
+// `(addr, timestamp)`, proves the two are a permutation of each other
+// with a pair of `lookup_any` arguments (one in each direction), then
+// constrains the sorted order directly:
+//   - same address as the previous row: a read must repeat the previous
+//     row's value, and the timestamp must strictly increase from the
+//     previous row (proven by a range-checked lookup on `ts_cur - ts_prev
+//     - 1`, not just a witnessed "differs" inverse, so a prover can't
+//     claim a stale read by reordering timestamps);
+//   - address changed from the previous row: the first access to an
+//     address must be a write (no separate "declared initial value"
+//     table, to keep the toy example small).
+//
+// The original (unsorted) trace's own timestamps aren't a free witness
+// either: `orig_ts[i]` is gated to equal the fixed row index `i`, so
+// "issue order" means the literal row order the prover committed the
+// trace in, not whatever the prover chooses to label each access.
+#[derive(Clone, Copy, Debug)]
+struct Access<F: Field> {
+    addr: usize,
+    timestamp: usize,
+    value: F,
+    is_write: bool,
+}
+
+#[derive(Clone, Debug)]
+struct RamTable<F: Field> {
+    orig_addr: Column<Advice>,
+    orig_ts: Column<Advice>,
+    orig_val: Column<Advice>,
+    orig_write: Column<Advice>,
+    sorted_addr: Column<Advice>,
+    sorted_ts: Column<Advice>,
+    sorted_val: Column<Advice>,
+    sorted_write: Column<Advice>,
+    same_addr: Column<Advice>,
+    // fixed row index `i`, used to pin `orig_ts` to real issue order
+    row_index: Column<Fixed>,
+    // holds every value in `0..MAX_RAM_ACCESSES`, used to range-check
+    // `sorted_ts_cur - sorted_ts_prev - 1` is non-negative, i.e. that the
+    // timestamp genuinely strictly increases rather than merely differs
+    ts_delta_table: TableColumn,
+    q_row: Selector,
+    q_first: Selector,
+    q_transition: Selector,
+    _ph: PhantomData<F>,
+}
+
+#[derive(Clone, Debug)]
+struct RamChip<F: Field> {
+    table: RamTable<F>,
+}
+
+impl<F: PrimeField> RamChip<F> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let orig_addr = meta.advice_column();
+        let orig_ts = meta.advice_column();
+        let orig_val = meta.advice_column();
+        let orig_write = meta.advice_column();
+        let sorted_addr = meta.advice_column();
+        let sorted_ts = meta.advice_column();
+        let sorted_val = meta.advice_column();
+        let sorted_write = meta.advice_column();
+        let same_addr = meta.advice_column();
+        let row_index = meta.fixed_column();
+        let ts_delta_table = meta.lookup_table_column();
+
+        meta.enable_equality(orig_val);
+        meta.enable_equality(sorted_val);
+
+        let q_row = meta.complex_selector();
+        let q_first = meta.complex_selector();
+        let q_transition = meta.complex_selector();
+
+        // the original trace's timestamp is not a free witness: it must
+        // equal the row it was committed at, so "issue order" reflects the
+        // order the prover actually laid the trace out in
+        meta.create_gate("orig ts is real issue order", |meta| {
+            let q_row = meta.query_selector(q_row);
+            let ts = meta.query_advice(orig_ts, Rotation::cur());
+            let row_index = meta.query_fixed(row_index, Rotation::cur());
+            vec![q_row * (ts - row_index)]
+        });
+
+        // the original trace and the sorted trace are the same multiset
+        // of (addr, timestamp, value, is_write) tuples
+        meta.lookup_any("orig access is in sorted trace", |meta| {
+            let q_row = meta.query_selector(q_row);
+            let addr = meta.query_advice(orig_addr, Rotation::cur());
+            let ts = meta.query_advice(orig_ts, Rotation::cur());
+            let val = meta.query_advice(orig_val, Rotation::cur());
+            let write = meta.query_advice(orig_write, Rotation::cur());
+
+            let s_addr = meta.query_advice(sorted_addr, Rotation::cur());
+            let s_ts = meta.query_advice(sorted_ts, Rotation::cur());
+            let s_val = meta.query_advice(sorted_val, Rotation::cur());
+            let s_write = meta.query_advice(sorted_write, Rotation::cur());
+
+            vec![
+                (q_row.clone() * addr, s_addr),
+                (q_row.clone() * ts, s_ts),
+                (q_row.clone() * val, s_val),
+                (q_row * write, s_write),
+            ]
+        });
+
+        meta.lookup_any("sorted access is in orig trace", |meta| {
+            let q_row = meta.query_selector(q_row);
+            let addr = meta.query_advice(orig_addr, Rotation::cur());
+            let ts = meta.query_advice(orig_ts, Rotation::cur());
+            let val = meta.query_advice(orig_val, Rotation::cur());
+            let write = meta.query_advice(orig_write, Rotation::cur());
+
+            let s_addr = meta.query_advice(sorted_addr, Rotation::cur());
+            let s_ts = meta.query_advice(sorted_ts, Rotation::cur());
+            let s_val = meta.query_advice(sorted_val, Rotation::cur());
+            let s_write = meta.query_advice(sorted_write, Rotation::cur());
+
+            vec![
+                (q_row.clone() * s_addr, addr),
+                (q_row.clone() * s_ts, ts),
+                (q_row.clone() * s_val, val),
+                (q_row * s_write, write),
+            ]
+        });
+
+        meta.create_gate("sorted trace booleans", |meta| {
+            let q_row = meta.query_selector(q_row);
+            let write = meta.query_advice(sorted_write, Rotation::cur());
+            let same = meta.query_advice(same_addr, Rotation::cur());
+            vec![
+                q_row.clone() * write.clone() * (Expression::Constant(F::ONE) - write),
+                q_row * same.clone() * (Expression::Constant(F::ONE) - same),
+            ]
+        });
+
+        meta.create_gate("sorted trace first row is a write", |meta| {
+            let q_first = meta.query_selector(q_first);
+            let write = meta.query_advice(sorted_write, Rotation::cur());
+            vec![q_first * (Expression::Constant(F::ONE) - write)]
+        });
+
+        meta.create_gate("sorted trace transition", |meta| {
+            let q_transition = meta.query_selector(q_transition);
+
+            let addr_cur = meta.query_advice(sorted_addr, Rotation::cur());
+            let addr_prev = meta.query_advice(sorted_addr, Rotation::prev());
+            let val_cur = meta.query_advice(sorted_val, Rotation::cur());
+            let val_prev = meta.query_advice(sorted_val, Rotation::prev());
+            let write_cur = meta.query_advice(sorted_write, Rotation::cur());
+            let same = meta.query_advice(same_addr, Rotation::cur());
+
+            let one = Expression::Constant(F::ONE);
+
+            // `same` can only claim equality when the addresses truly match
+            let same_implies_equal_addr = same.clone() * (addr_cur - addr_prev);
+
+            // same address, read: value must repeat the previous row
+            let read_repeats_value =
+                same.clone() * (one.clone() - write_cur.clone()) * (val_cur - val_prev);
+
+            // address changed: the first access must be a write
+            let first_access_is_write = (one.clone() - same) * (one - write_cur);
+
+            vec![
+                q_transition.clone() * same_implies_equal_addr,
+                q_transition.clone() * read_repeats_value,
+                q_transition * first_access_is_write,
+            ]
+        });
+
+        // same address: the timestamp must *strictly* increase, not merely
+        // differ. `ts_cur - ts_prev - 1` is looked up against a table of
+        // `0..MAX_RAM_ACCESSES`, which only contains a valid entry when the
+        // difference is non-negative (and in range), ruling out both a
+        // repeated timestamp and a decreasing one.
+        meta.lookup("sorted ts strictly increases on repeat address", |meta| {
+            let q_transition = meta.query_selector(q_transition);
+            let same = meta.query_advice(same_addr, Rotation::cur());
+            let ts_cur = meta.query_advice(sorted_ts, Rotation::cur());
+            let ts_prev = meta.query_advice(sorted_ts, Rotation::prev());
+            let one = Expression::Constant(F::ONE);
+
+            vec![(
+                q_transition * same * (ts_cur - ts_prev - one),
+                ts_delta_table,
+            )]
+        });
+
+        Self {
+            table: RamTable {
+                orig_addr,
+                orig_ts,
+                orig_val,
+                orig_write,
+                sorted_addr,
+                sorted_ts,
+                sorted_val,
+                sorted_write,
+                same_addr,
+                row_index,
+                ts_delta_table,
+                q_row,
+                q_first,
+                q_transition,
+                _ph: PhantomData,
+            },
+        }
+    }
+
+    /// Assign the full access trace: `trace` in program order into the
+    /// original columns, and the same accesses sorted by `(addr,
+    /// timestamp)` into the sorted columns. The whole trace must be known
+    /// up front (like `RomTable::populate`), since the sorted order can't
+    /// be produced incrementally while accesses are still being recorded.
+    /// Returns the assigned value cell of each access in program order, so
+    /// `read`/`write` results can chain into the arithmetic chip.
+    fn populate(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        trace: Value<Vec<Access<F>>>,
+    ) -> Result<Vec<Var<F>>, plonk::Error> {
+        trace.assert_if_known(|t| t.len() == MAX_RAM_ACCESSES);
+
+        let sorted = trace.clone().map(|mut t| {
+            t.sort_by_key(|a| (a.addr, a.timestamp));
+            t
+        });
+
+        // populate the fixed table with every value in `0..MAX_RAM_ACCESSES`,
+        // so the "sorted ts strictly increases on repeat address" lookup
+        // above has something to check against
+        layouter.assign_table(
+            || "ts delta range-check table",
+            |mut table| {
+                for i in 0..MAX_RAM_ACCESSES {
+                    table.assign_cell(
+                        || "ts-delta-table-value",
+                        self.table.ts_delta_table,
+                        i,
+                        || Value::known(F::from_u128(i as u128)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+
+        layouter.assign_region(
+            || "ram trace",
+            |mut region| {
+                let mut orig_val_cells = Vec::with_capacity(MAX_RAM_ACCESSES);
+
+                for i in 0..MAX_RAM_ACCESSES {
+                    let orig = trace.as_ref().map(|t| t[i]);
+                    let sorted_i = sorted.as_ref().map(|t| t[i]);
+
+                    self.table.q_row.enable(&mut region, i)?;
+                    region.assign_fixed(
+                        || "row index",
+                        self.table.row_index,
+                        i,
+                        || Value::known(F::from_u128(i as u128)),
+                    )?;
+
+                    region.assign_advice(
+                        || "orig addr",
+                        self.table.orig_addr,
+                        i,
+                        || orig.map(|a| F::from_u128(a.addr as u128)),
+                    )?;
+                    region.assign_advice(
+                        || "orig ts",
+                        self.table.orig_ts,
+                        i,
+                        || orig.map(|a| F::from_u128(a.timestamp as u128)),
+                    )?;
+                    let orig_val = region.assign_advice(
+                        || "orig val",
+                        self.table.orig_val,
+                        i,
+                        || orig.map(|a| a.value),
+                    )?;
+                    orig_val_cells.push(Var::from(orig_val));
+                    region.assign_advice(
+                        || "orig write",
+                        self.table.orig_write,
+                        i,
+                        || orig.map(|a| if a.is_write { F::ONE } else { F::ZERO }),
+                    )?;
+
+                    region.assign_advice(
+                        || "sorted addr",
+                        self.table.sorted_addr,
+                        i,
+                        || sorted_i.map(|a| F::from_u128(a.addr as u128)),
+                    )?;
+                    region.assign_advice(
+                        || "sorted ts",
+                        self.table.sorted_ts,
+                        i,
+                        || sorted_i.map(|a| F::from_u128(a.timestamp as u128)),
+                    )?;
+                    region.assign_advice(
+                        || "sorted val",
+                        self.table.sorted_val,
+                        i,
+                        || sorted_i.map(|a| a.value),
+                    )?;
+                    region.assign_advice(
+                        || "sorted write",
+                        self.table.sorted_write,
+                        i,
+                        || sorted_i.map(|a| if a.is_write { F::ONE } else { F::ZERO }),
+                    )?;
+
+                    if i == 0 {
+                        region.assign_advice(
+                            || "same addr",
+                            self.table.same_addr,
+                            i,
+                            || Value::known(F::ZERO),
+                        )?;
+                        self.table.q_first.enable(&mut region, i)?;
+                    } else {
+                        let prev = sorted.as_ref().map(|t| t[i - 1]);
+                        let same = sorted_i.and_then(|cur| prev.map(|prev| cur.addr == prev.addr));
+                        region.assign_advice(
+                            || "same addr",
+                            self.table.same_addr,
+                            i,
+                            || same.map(|b| if b { F::ONE } else { F::ZERO }),
+                        )?;
+
+                        self.table.q_transition.enable(&mut region, i)?;
+                    }
+                }
+
+                Ok(orig_val_cells)
+            },
+        )
+    }
+
+    /// The value committed for the access at row `i` of the trace passed
+    /// to `populate`, whether that row was a read or a write — both are
+    /// just committed rows of the same trace once the whole trace is
+    /// known up front, and which operation it was is recorded (and
+    /// constrained) via that row's `is_write` flag, not by which accessor
+    /// the caller uses.
+    fn access(&self, accesses: &[Var<F>], i: usize) -> Var<F> {
+        accesses[i].clone()
+    }
+}
+// ANCHOR_END: ram_chip
+
 struct TestCircuit<F: Field> {
     _ph: PhantomData<F>,
     rom: Value<Vec<F>>,
+    ram_trace: Value<Vec<Access<F>>>,
 }
 
 #[derive(Clone, Debug)]
@@ -210,8 +920,12 @@ struct TestConfig<F: Field + Clone> {
     _ph: PhantomData<F>,
     rom: RomTable<F>,
     rom_chip: RomChip<F>,
+    arithmetic_chip: ArithmeticChip<F>,
+    ram_chip: RamChip<F>,
     adv1: Column<Advice>,
     adv2: Column<Advice>,
+    adv3: Column<Advice>,
+    instance: Column<Instance>,
 }
 
 impl<F: PrimeField> TestConfig<F> {
@@ -247,6 +961,7 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
         TestCircuit {
             _ph: PhantomData,
             rom: self.rom.clone(),
+            ram_trace: Value::unknown(),
         }
     }
 
@@ -254,18 +969,29 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
         let adv1 = meta.advice_column();
         let adv2 = meta.advice_column();
-        let rom = RomTable::configure(meta);
+        let adv3 = meta.advice_column();
+        let rom = RomTable::configure(meta, MAX_MEMORY);
         let rom_chip = RomChip::configure(meta, rom.clone(), adv1, adv2);
+        let arithmetic_chip = ArithmeticChip::configure(meta, adv1, adv2, adv3);
+        let ram_chip = RamChip::configure(meta);
 
         meta.enable_equality(adv1);
         meta.enable_equality(adv2);
+        meta.enable_equality(adv3);
+
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
 
         TestConfig {
             _ph: PhantomData {},
             rom,
             rom_chip,
+            arithmetic_chip,
+            ram_chip,
             adv1,
             adv2,
+            adv3,
+            instance,
         }
     }
 
@@ -279,23 +1005,143 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
         config.rom.populate(&mut layouter, self.rom.as_ref())?;
 
         let idx1 = config.free_index(&mut layouter, Value::known(1))?;
-        let idx2 = config.free_index(&mut layouter, Value::known(1))?;
+        let idx2 = config.free_index(&mut layouter, Value::known(2))?;
+
+        // a single `get_many` region instead of two separate `read` regions
+        let reads = config
+            .rom_chip
+            .get_many(&mut layouter, &self.rom, &[idx1, idx2])?;
+        let (arr1, arr2) = (reads[0].clone(), reads[1].clone());
+
+        // the ROM's output `Var`s feed straight into the arithmetic chip:
+        // no conversion or re-assignment needed, since both chips
+        // implement `UtilitiesInstructions` with the same `Var`
+        let sum = config.arithmetic_chip.add(&mut layouter, &arr1, &arr2)?;
 
-        let arr1 = config.rom_chip.get(&mut layouter, &self.rom, idx1)?;
-        /*
-        let arr2 = config.rom_chip.get(&mut layouter, &self.rom, idx2)?;
+        println!("arr1 + arr2 = {:?}", sum.value);
 
-        println!("arr1: {:?}", arr1);
-        println!("arr2: {:?}", arr2);
-        */
+        layouter.constrain_instance(sum.cell, config.instance, 0)?;
+
+        // populate the RAM trace (program order) and its sorted
+        // counterpart, then pull the first two accesses (a write to address
+        // 0 followed by a read of it) into the arithmetic chip
+        let accesses = config
+            .ram_chip
+            .populate(&mut layouter, self.ram_trace.clone())?;
+        let written = config.ram_chip.access(&accesses, 0);
+        let read_back = config.ram_chip.access(&accesses, 1);
+        let ram_sum = config
+            .arithmetic_chip
+            .add(&mut layouter, &written, &read_back)?;
+        println!(
+            "ram[0] + ram[0] (after write-then-read) = {:?}",
+            ram_sum.value
+        );
 
         Ok(())
     }
 }
 
-fn main() {
-    use halo2_proofs::halo2curves::bn256::Fr;
+// ANCHOR: prove_verify
+// Runs the full halo2 backend over bn256 instead of stopping at
+// `MockProver`: `keygen` produces the (pk, vk) pair, `prove` runs
+// `create_proof` into a `Blake2bWrite`/`Challenge255` transcript, and
+// `verify` runs the matching `verify_proof` against the serialized bytes.
+// `sum` (the two ROM reads added together) is exposed through
+// `config.instance`, so the verifier actually checks a value derived from
+// the ROM contents rather than an empty instance vector.
+mod prove_verify {
+    use super::*;
+
+    pub fn keygen(
+        params: &ParamsKZG<Bn256>,
+        circuit: &TestCircuit<Fr>,
+    ) -> (ProvingKey<G1Affine>, VerifyingKey<G1Affine>) {
+        let vk_circuit = circuit.without_witnesses();
+        let vk = keygen_vk(params, &vk_circuit).expect("keygen_vk failed");
+        let pk = keygen_pk(params, vk.clone(), &vk_circuit).expect("keygen_pk failed");
+        (pk, vk)
+    }
+
+    pub fn prove(
+        params: &ParamsKZG<Bn256>,
+        pk: &ProvingKey<G1Affine>,
+        circuit: &TestCircuit<Fr>,
+        instance: &[Fr],
+    ) -> Vec<u8> {
+        let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+            params,
+            pk,
+            &[circuit],
+            &[&[instance]],
+            rand::thread_rng(),
+            &mut transcript,
+        )
+        .expect("create_proof failed");
+        transcript.finalize()
+    }
+
+    pub fn verify(
+        params: &ParamsKZG<Bn256>,
+        vk: &VerifyingKey<G1Affine>,
+        proof: &[u8],
+        instance: &[Fr],
+    ) -> Result<(), plonk::Error> {
+        let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof);
+        let strategy = SingleStrategy::new(params);
+        verify_proof::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<_>, _, _, _>(
+            params,
+            vk,
+            strategy,
+            &[&[instance]],
+            &mut transcript,
+        )
+    }
+}
+// ANCHOR_END: prove_verify
+
+// ANCHOR: analysis
+// Reports how the circuit's `k` relates to its actual resource usage,
+// via the same `dev::cost::CircuitCost` tooling used in ex-suduko.rs,
+// plus `dev::CircuitGates` for the human-readable gate polynomials and a
+// `fits` check that turns `Error::NotEnoughRowsAvailable` into a concrete
+// answer instead of a failed `MockProver::run`.
+#[cfg(feature = "cost-estimator")]
+mod analysis {
+    use super::*;
+    use halo2_proofs::dev::{cost::CircuitCost, CircuitGates};
+    use halo2_proofs::halo2curves::bn256::G1;
+
+    /// Print max gate degree, advice column count, and estimated proof size for `k`.
+    pub fn report(k: u32, circuit: &TestCircuit<Fr>, num_instance: usize) {
+        let cost: CircuitCost<G1, TestCircuit<Fr>> = CircuitCost::measure(k, circuit);
+
+        println!("max gate degree: {:?}", cost.max_deg);
+        println!("advice columns: {:?}", cost.advice_columns);
+        println!("estimated proof size: {:?}", cost.proof_size(num_instance));
+    }
+
+    /// Print the human-readable polynomial for each gate in the circuit.
+    pub fn gates() {
+        let gates = CircuitGates::collect::<Fr, TestCircuit<Fr>>();
+        println!("{}", gates);
+    }
+
+    /// Whether `circuit` fits within `2^k` rows, by running the
+    /// `MockProver` and checking for `Error::NotEnoughRowsAvailable`
+    /// rather than any other failure.
+    pub fn fits(k: u32, circuit: &TestCircuit<Fr>, instance: Vec<Fr>) -> bool {
+        match MockProver::run(k, circuit, vec![instance]) {
+            Ok(_) => true,
+            Err(plonk::Error::NotEnoughRowsAvailable { .. }) => false,
+            Err(e) => panic!("unexpected error while checking fit: {e:?}"),
+        }
+    }
+}
+// ANCHOR_END: analysis
 
+fn main() {
     use std::iter;
 
     let rom = "Hello"; // World!";
@@ -306,12 +1152,68 @@ fn main() {
         .take(MAX_MEMORY)
         .collect::<Vec<_>>();
 
+    // arr1 = rom[1], arr2 = rom[2], exposed as the instance `arr1 + arr2`
+    let sum = rom[1] + rom[2];
+    let instance = vec![sum];
+
     let rom = Value::known(rom);
 
+    // write addr 0, read it back, write addr 1, read it back
+    let ram_trace = vec![
+        Access {
+            addr: 0,
+            timestamp: 0,
+            value: Fr::from(7u64),
+            is_write: true,
+        },
+        Access {
+            addr: 0,
+            timestamp: 1,
+            value: Fr::from(7u64),
+            is_write: false,
+        },
+        Access {
+            addr: 1,
+            timestamp: 2,
+            value: Fr::from(9u64),
+            is_write: true,
+        },
+        Access {
+            addr: 1,
+            timestamp: 3,
+            value: Fr::from(9u64),
+            is_write: false,
+        },
+    ];
+    let ram_trace = Value::known(ram_trace);
+
     let circuit = TestCircuit::<Fr> {
         _ph: PhantomData,
         rom,
+        ram_trace,
     };
-    let prover = MockProver::run(12, &circuit, vec![]).unwrap();
+    let prover = MockProver::run(12, &circuit, vec![instance.clone()]).unwrap();
     prover.verify().unwrap();
+
+    // now run the real prover/verifier over bn256
+    let params = ParamsKZG::<Bn256>::setup(12, rand::thread_rng());
+    let (pk, vk) = prove_verify::keygen(&params, &circuit);
+    let proof = prove_verify::prove(&params, &pk, &circuit, &instance);
+    prove_verify::verify(&params, &vk, &proof, &instance).expect("verify_proof failed");
+
+    // tampering with the transcript must make verification fail
+    let mut tampered_proof = proof.clone();
+    *tampered_proof.last_mut().unwrap() ^= 1;
+    assert!(prove_verify::verify(&params, &vk, &tampered_proof, &instance).is_err());
+
+    // tampering with the public instance must make verification fail
+    let wrong_instance = vec![sum + Fr::ONE];
+    assert!(prove_verify::verify(&params, &vk, &proof, &wrong_instance).is_err());
+
+    #[cfg(feature = "cost-estimator")]
+    {
+        analysis::report(12, &circuit, instance.len());
+        analysis::gates();
+        assert!(analysis::fits(12, &circuit, instance.clone()));
+    }
 }
@@ -59,4 +59,227 @@
 //
 // (f - g)(X) is at most degree 9.
 //
-fn main() {}
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Challenge, Circuit, Column, ConstraintSystem, Error, Expression, FirstPhase,
+        SecondPhase, Selector,
+    },
+    poly::Rotation,
+};
+
+use ff::{Field, PrimeField};
+
+// ANCHOR: shuffle_chip
+// Proves that two equal-length advice-column sequences `a` and `b` are a
+// permutation of one another, via the multiset-equality argument sketched
+// above: draw a verifier challenge `gamma`, and check
+//
+//   \prod_i (gamma + a_i) = \prod_i (gamma + b_i)
+//
+// witnessed as a running product `z` with `z_0 = 1`,
+// `z_{i+1} = z_i * (gamma + a_i) / (gamma + b_i)`, and a boundary
+// constraint `z_n = 1`. The division only ever happens off-circuit, while
+// assigning the witness; the gate itself is cross-multiplied so no
+// `invert` shows up in the constraint.
+#[derive(Clone, Debug)]
+struct ShuffleChip<F: Field> {
+    q_shuffle: Selector,
+    q_last: Selector,
+    gamma: Challenge,
+    a: Column<Advice>,
+    b: Column<Advice>,
+    z: Column<Advice>,
+    _ph: PhantomData<F>,
+}
+
+impl<F: PrimeField> ShuffleChip<F> {
+    fn configure(
+        meta: &mut ConstraintSystem<F>,
+        a: Column<Advice>,
+        b: Column<Advice>,
+    ) -> Self {
+        let q_shuffle = meta.selector();
+        let q_last = meta.selector();
+
+        let gamma = meta.challenge_usable_after(FirstPhase);
+        let z = meta.advice_column_in(SecondPhase);
+
+        meta.enable_equality(a);
+        meta.enable_equality(b);
+        meta.enable_equality(z);
+
+        // z_{i+1} * (gamma + b_i) = z_i * (gamma + a_i)
+        meta.create_gate("shuffle", |meta| {
+            let q_shuffle = meta.query_selector(q_shuffle);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            let gamma = meta.query_challenge(gamma);
+
+            vec![q_shuffle * (z_next * (gamma.clone() + b) - z_cur * (gamma + a))]
+        });
+
+        // boundary: z_0 = 1 and z_n = 1 (`q_last` is enabled on both rows)
+        meta.create_gate("shuffle boundary", |meta| {
+            let q_last = meta.query_selector(q_last);
+            let z = meta.query_advice(z, Rotation::cur());
+            vec![q_last * (z - Expression::Constant(F::ONE))]
+        });
+
+        Self {
+            q_shuffle,
+            q_last,
+            gamma,
+            a,
+            b,
+            z,
+            _ph: PhantomData,
+        }
+    }
+
+    // copies `a`/`b` into the tracked columns and proves they're a
+    // permutation of one another
+    fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        a: &[AssignedCell<F, F>],
+        b: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        assert_eq!(a.len(), b.len(), "shuffle requires equal-length sequences");
+        let gamma = layouter.get_challenge(self.gamma);
+
+        layouter.assign_region(
+            || "shuffle",
+            |mut region| {
+                let mut z = region.assign_advice(|| "z0", self.z, 0, || Value::known(F::ONE))?;
+                // pin z_0 = 1 with the same boundary gate used for z_n
+                self.q_last.enable(&mut region, 0)?;
+
+                for (i, (a_i, b_i)) in a.iter().zip(b.iter()).enumerate() {
+                    self.q_shuffle.enable(&mut region, i)?;
+                    a_i.copy_advice(|| "a", &mut region, self.a, i)?;
+                    b_i.copy_advice(|| "b", &mut region, self.b, i)?;
+
+                    let next = z
+                        .value()
+                        .copied()
+                        .zip(gamma)
+                        .zip(a_i.value().copied())
+                        .zip(b_i.value().copied())
+                        .map(|(((z, gamma), a), b)| {
+                            z * (gamma + a) * (gamma + b).invert().unwrap()
+                        });
+                    z = region.assign_advice(|| "z", self.z, i + 1, || next)?;
+                }
+
+                self.q_last.enable(&mut region, a.len())?;
+                Ok(())
+            },
+        )
+    }
+}
+// ANCHOR_END: shuffle_chip
+
+#[derive(Clone, Debug)]
+struct TestConfig<F: Field + Clone> {
+    shuffle: ShuffleChip<F>,
+    a: Column<Advice>,
+    b: Column<Advice>,
+}
+
+struct TestCircuit<F: Field> {
+    a: Value<Vec<F>>,
+    b: Value<Vec<F>>,
+    n: usize,
+    _ph: PhantomData<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
+    type Config = TestConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        TestCircuit {
+            a: Value::unknown(),
+            b: Value::unknown(),
+            n: self.n,
+            _ph: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+
+        meta.enable_equality(a);
+        meta.enable_equality(b);
+
+        let shuffle = ShuffleChip::configure(meta, a, b);
+
+        TestConfig { shuffle, a, b }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config, //
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let a_cells = layouter.assign_region(
+            || "load a",
+            |mut region| {
+                (0..self.n)
+                    .map(|i| {
+                        region.assign_advice(
+                            || "a",
+                            config.a,
+                            i,
+                            || self.a.as_ref().map(|a| a[i]),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+
+        let b_cells = layouter.assign_region(
+            || "load b",
+            |mut region| {
+                (0..self.n)
+                    .map(|i| {
+                        region.assign_advice(
+                            || "b",
+                            config.b,
+                            i,
+                            || self.b.as_ref().map(|b| b[i]),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+
+        config.shuffle.assign(&mut layouter, &a_cells, &b_cells)
+    }
+}
+
+fn main() {
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    // B is a permutation of A
+    let a = vec![3, 2, 1, 4, 6, 5, 7, 8, 9];
+    let b = vec![9, 1, 2, 3, 4, 5, 6, 7, 8];
+
+    let circuit = TestCircuit::<Fr> {
+        a: Value::known(a.iter().map(|&v| Fr::from(v as u64)).collect()),
+        b: Value::known(b.iter().map(|&v| Fr::from(v as u64)).collect()),
+        n: a.len(),
+        _ph: PhantomData,
+    };
+
+    let prover = MockProver::run(8, &circuit, vec![]).unwrap();
+    prover.verify().unwrap();
+}
@@ -20,23 +20,39 @@ use halo2_proofs::{
 use ff::Field;
 use rand::rngs::ThreadRng;
 
+// shared by `RLCChip` and `ShuffleChip`, both of which compress a row into a
+// single scalar via a verifier challenge the same way
+fn compute_rlc<F: Field>(challenge: F, row: &[F]) -> F {
+    let mut rlc = F::ZERO;
+    let mut c = F::ONE;
+    for v in row.iter() {
+        rlc += *v * c;
+        c *= challenge;
+    }
+    rlc
+}
+
 struct TestCircuit<F: Field> {
     _ph: PhantomData<F>,
+    arity: usize,
     eq_rows: Vec<(usize, usize)>,
-    assignment: Value<Vec<[F; 3]>>,
+    assignment: Value<Vec<Vec<F>>>,
 }
 
+// `N` used to be a const generic here; the arity is now a runtime
+// `Circuit::Params` on `TestCircuit` below (see `configure_with_params`),
+// so `advice` is sized at configuration time instead of compile time.
 #[derive(Clone, Debug)]
-struct RLCChip<F: Field, const N: usize> {
+struct RLCChip<F: Field> {
     q_enable: Selector,
-    advice: [Column<Advice>; N],
+    advice: Vec<Column<Advice>>,
     challenge: Challenge,
     rlc: Column<Advice>, // rlc = (adv[0] + c * adv[1] + c^2 * adv[2] + ... + c^(N-1) * adv[N-1])
     _ph: PhantomData<F>,
 }
 
-impl<F: Field, const N: usize> RLCChip<F, N> {
-    fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; N]) -> Self {
+impl<F: Field> RLCChip<F> {
+    fn configure(meta: &mut ConstraintSystem<F>, advice: Vec<Column<Advice>>) -> Self {
         let rlc = meta.advice_column_in(SecondPhase); // <- enforce equality on this
         let q_enable = meta.selector();
         let challenge = meta.challenge_usable_after(FirstPhase);
@@ -59,7 +75,7 @@ impl<F: Field, const N: usize> RLCChip<F, N> {
             //
 
             for adv in advice.iter() {
-                y = y + meta.query_advice(adv.clone(), Rotation::cur()) * x.clone();
+                y = y + meta.query_advice(*adv, Rotation::cur()) * x.clone();
                 x = x.clone() * challenge.clone();
             }
             vec![sel * (rlc - y)]
@@ -74,33 +90,33 @@ impl<F: Field, const N: usize> RLCChip<F, N> {
         }
     }
 
-    fn compute_rlc(&self, challenge: F, advs: [F; N]) -> F {
-        let mut rlc = F::ZERO;
-        let mut c = F::ONE;
-        for i in 0..N {
-            rlc += advs[i] * c;
-            c *= challenge;
-        }
-        rlc
+    fn arity(&self) -> usize {
+        self.advice.len()
+    }
+
+    fn compute_rlc(&self, challenge: F, advs: &[F]) -> F {
+        compute_rlc(challenge, advs)
     }
 
     fn alloc_row(
         &self,
         layouter: &mut impl Layouter<F>,
-        value: Value<[F; N]>,
-    ) -> Result<([AssignedCell<F, F>; N], AssignedCell<F, F>), Error> {
+        value: Value<Vec<F>>,
+    ) -> Result<(Vec<AssignedCell<F, F>>, AssignedCell<F, F>), Error> {
+        value.as_ref().assert_if_known(|v| v.len() == self.arity());
+
         let challenge = layouter.get_challenge(self.challenge);
 
         layouter.assign_region(
             || "fingerprint-row",
             |mut region| {
                 let mut result = vec![];
-                for i in 0..N {
+                for i in 0..self.arity() {
                     result.push(region.assign_advice(
                         || format!("adv{}", i),
                         self.advice[i],
                         0,
-                        || value.map(|v| v[i]),
+                        || value.as_ref().map(|v| v[i]),
                     )?);
                 }
 
@@ -112,14 +128,324 @@ impl<F: Field, const N: usize> RLCChip<F, N> {
                     0,
                     || {
                         challenge
-                            .and_then(|c| value.and_then(|v| Value::known(self.compute_rlc(c, v))))
+                            .zip(value.as_ref())
+                            .map(|(c, v)| self.compute_rlc(c, v))
                     },
                 )?;
-                Ok((result.try_into().unwrap(), rlc))
+                Ok((result, rlc))
+            },
+        )
+    }
+
+    // Batched sibling of `alloc_row`: assigns every row inside a single
+    // region instead of opening one region per row. `num_rows` is a
+    // circuit-structural parameter (not derived from `values`, which may
+    // be unknown at keygen time) -- `values` must carry exactly that many
+    // rows whenever it *is* known. Takes `values` by reference (like
+    // `precompute_rows`'s sequences in instances.rs) so callers with a
+    // large witness don't pay for an extra full-table clone just to hand
+    // it to this chip.
+    fn alloc_rows(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        num_rows: usize,
+        values: &Value<Vec<Vec<F>>>,
+    ) -> Result<(Vec<Vec<AssignedCell<F, F>>>, Vec<AssignedCell<F, F>>), Error> {
+        values
+            .as_ref()
+            .assert_if_known(|v| v.len() == num_rows && v.iter().all(|row| row.len() == self.arity()));
+
+        let challenge = layouter.get_challenge(self.challenge);
+
+        #[cfg(feature = "multicore")]
+        let (rows, rlcs) = precompute_rlc_rows(values, challenge, num_rows);
+        #[cfg(not(feature = "multicore"))]
+        let (rows, rlcs): (Vec<Value<Vec<F>>>, Vec<Value<F>>) = (0..num_rows)
+            .map(|i| {
+                let row = values.as_ref().map(|v| v[i].clone());
+                let rlc = challenge.zip(row.as_ref()).map(|(c, r)| compute_rlc(c, r));
+                (row, rlc)
+            })
+            .unzip();
+
+        layouter.assign_region(
+            || "fingerprint-rows",
+            |mut region| {
+                let mut out_rows = Vec::with_capacity(num_rows);
+                let mut out_rlcs = Vec::with_capacity(num_rows);
+                for i in 0..num_rows {
+                    self.q_enable.enable(&mut region, i)?;
+
+                    let mut cells = vec![];
+                    for j in 0..self.arity() {
+                        cells.push(region.assign_advice(
+                            || format!("adv{j}"),
+                            self.advice[j],
+                            i,
+                            || rows[i].as_ref().map(|r| r[j]),
+                        )?);
+                    }
+
+                    let rlc = region.assign_advice(|| "rlc", self.rlc, i, || rlcs[i])?;
+
+                    out_rows.push(cells);
+                    out_rlcs.push(rlc);
+                }
+                Ok((out_rows, out_rlcs))
+            },
+        )
+    }
+}
+
+// Number of rows handed to each worker thread when `multicore` is enabled.
+#[cfg(feature = "multicore")]
+const RLC_CHUNK_SIZE: usize = 1 << 10;
+
+// `Region` is not `Send`, so `assign_advice` itself cannot be called from
+// worker threads. What we *can* parallelize is deriving each row's advice
+// values and RLC fingerprint from the witness and the challenge -- both
+// are pure functions of the two. Split the row range into contiguous
+// chunks, fill each chunk's slice of the output vectors from a crossbeam
+// scoped thread, then hand the fully-materialized vectors back to the
+// (single) layouter thread, which assigns them purely by offset. Mirrors
+// `precompute_rows` in instances.rs.
+#[cfg(feature = "multicore")]
+fn precompute_rlc_rows<F: Field>(
+    values: &Value<Vec<Vec<F>>>,
+    challenge: Value<F>,
+    num_rows: usize,
+) -> (Vec<Value<Vec<F>>>, Vec<Value<F>>) {
+    let mut rows = vec![Value::unknown(); num_rows];
+    let mut rlcs = vec![Value::unknown(); num_rows];
+
+    crossbeam::scope(|scope| {
+        let chunks = rows
+            .chunks_mut(RLC_CHUNK_SIZE)
+            .zip(rlcs.chunks_mut(RLC_CHUNK_SIZE))
+            .enumerate();
+
+        for (chunk_idx, (row_chunk, rlc_chunk)) in chunks {
+            let base = chunk_idx * RLC_CHUNK_SIZE;
+            scope.spawn(move |_| {
+                for offset in 0..row_chunk.len() {
+                    let i = base + offset;
+                    let row = values.as_ref().map(|v| v[i].clone());
+                    rlc_chunk[offset] = challenge.zip(row.as_ref()).map(|(c, r)| compute_rlc(c, r));
+                    row_chunk[offset] = row;
+                }
+            });
+        }
+    })
+    .expect("a `precompute_rlc_rows` worker thread panicked");
+
+    (rows, rlcs)
+}
+
+// ANCHOR: shuffle_chip
+// A sibling to `RLCChip` above: proves that a block of `W` FirstPhase
+// advice columns (`orig`) is a multiset permutation of another block
+// (`shuf`), without any explicit index mapping, via a grand-product
+// argument -- useful for the memory-consistency/sorting arguments
+// elsewhere in the course. `theta` compresses each row into a single
+// scalar using the same `compute_rlc` as `RLCChip` (`a_i` for `orig`,
+// `b_i` for `shuf`); a second challenge `gamma` and the running-product
+// column `z` (height `H+1`, `z[0] = 1`, `z[i+1]*(b_i+gamma) =
+// z[i]*(a_i+gamma)`) then close the argument in multiplicative form, so
+// no in-circuit division is needed. `z` ending back at 1 is exactly
+// `\prod_i (a_i+gamma) = \prod_i (b_i+gamma)`, which holds with
+// overwhelming probability over `gamma` iff the two multisets are equal.
+//
+// Critical invariant: `theta`/`gamma` and `z` must live strictly after
+// `orig`/`shuf` are committed (both challenges are
+// `challenge_usable_after(FirstPhase)`, `z` is `SecondPhase`) -- drawing
+// them before the rows are fixed would let a prover choose `shuf` to
+// match the challenge after the fact, which breaks soundness.
+#[derive(Clone, Debug)]
+struct ShuffleChip<F: Field, const W: usize> {
+    q_enable: Selector, // product recurrence, rows 0..H-1
+    q_first: Selector,  // z[0] = 1
+    q_last: Selector,   // z[H] = 1
+    orig: [Column<Advice>; W],
+    shuf: [Column<Advice>; W],
+    theta: Challenge,
+    gamma: Challenge,
+    z: Column<Advice>,
+    _ph: PhantomData<F>,
+}
+
+impl<F: Field, const W: usize> ShuffleChip<F, W> {
+    fn configure(
+        meta: &mut ConstraintSystem<F>,
+        orig: [Column<Advice>; W],
+        shuf: [Column<Advice>; W],
+    ) -> Self {
+        let theta = meta.challenge_usable_after(FirstPhase);
+        let gamma = meta.challenge_usable_after(FirstPhase);
+        let z = meta.advice_column_in(SecondPhase);
+
+        meta.enable_equality(z);
+
+        let q_enable = meta.selector();
+        let q_first = meta.selector();
+        let q_last = meta.selector();
+
+        meta.create_gate("shuffle product recurrence", |meta| {
+            let q_enable = meta.query_selector(q_enable);
+            let theta = meta.query_challenge(theta);
+            let gamma = meta.query_challenge(gamma);
+
+            let mut pow = Expression::Constant(F::ONE);
+            let mut a = Expression::Constant(F::ZERO);
+            for col in orig.iter() {
+                a = a + meta.query_advice(*col, Rotation::cur()) * pow.clone();
+                pow = pow * theta.clone();
+            }
+
+            let mut pow = Expression::Constant(F::ONE);
+            let mut b = Expression::Constant(F::ZERO);
+            for col in shuf.iter() {
+                b = b + meta.query_advice(*col, Rotation::cur()) * pow.clone();
+                pow = pow * theta.clone();
+            }
+
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+
+            vec![q_enable * (z_next * (b + gamma.clone()) - z_cur * (a + gamma))]
+        });
+
+        meta.create_gate("shuffle boundary first", |meta| {
+            let q_first = meta.query_selector(q_first);
+            let z = meta.query_advice(z, Rotation::cur());
+            vec![q_first * (z - Expression::Constant(F::ONE))]
+        });
+
+        meta.create_gate("shuffle boundary last", |meta| {
+            let q_last = meta.query_selector(q_last);
+            let z = meta.query_advice(z, Rotation::cur());
+            vec![q_last * (z - Expression::Constant(F::ONE))]
+        });
+
+        Self {
+            q_enable,
+            q_first,
+            q_last,
+            orig,
+            shuf,
+            theta,
+            gamma,
+            z,
+            _ph: PhantomData,
+        }
+    }
+
+    // Assigns the whole `orig`/`shuf` trace plus the running-product
+    // column `z` in one region. `height` is a circuit-structural
+    // parameter (not derived from witness data, which may be unknown at
+    // keygen time): both `orig_rows` and `shuf_rows` must have exactly
+    // `height` rows whenever they *are* known.
+    fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        height: usize,
+        orig_rows: Value<Vec<[F; W]>>,
+        shuf_rows: Value<Vec<[F; W]>>,
+    ) -> Result<(), Error> {
+        orig_rows.as_ref().assert_if_known(|v| v.len() == height);
+        shuf_rows.as_ref().assert_if_known(|v| v.len() == height);
+
+        let theta = layouter.get_challenge(self.theta);
+        let gamma = layouter.get_challenge(self.gamma);
+
+        layouter.assign_region(
+            || "shuffle",
+            |mut region| {
+                self.q_first.enable(&mut region, 0)?;
+                let mut z = region.assign_advice(|| "z0", self.z, 0, || Value::known(F::ONE))?;
+
+                for i in 0..height {
+                    self.q_enable.enable(&mut region, i)?;
+
+                    let orig_i = orig_rows.as_ref().map(|v| v[i]);
+                    let shuf_i = shuf_rows.as_ref().map(|v| v[i]);
+
+                    for j in 0..W {
+                        region.assign_advice(
+                            || format!("orig{j}"),
+                            self.orig[j],
+                            i,
+                            || orig_i.map(|r| r[j]),
+                        )?;
+                        region.assign_advice(
+                            || format!("shuf{j}"),
+                            self.shuf[j],
+                            i,
+                            || shuf_i.map(|r| r[j]),
+                        )?;
+                    }
+
+                    let a = theta.zip(orig_i).map(|(t, r)| compute_rlc(t, &r));
+                    let b = theta.zip(shuf_i).map(|(t, r)| compute_rlc(t, &r));
+                    let next = z
+                        .value()
+                        .copied()
+                        .zip(a)
+                        .zip(b)
+                        .zip(gamma)
+                        .map(|(((z, a), b), gamma)| z * (a + gamma) * (b + gamma).invert().unwrap());
+                    z = region.assign_advice(|| "z", self.z, i + 1, || next)?;
+                }
+
+                self.q_last.enable(&mut region, height)?;
+                Ok(())
             },
         )
     }
 }
+// ANCHOR_END: shuffle_chip
+
+struct ShuffleTestCircuit<F: Field, const W: usize> {
+    _ph: PhantomData<F>,
+    height: usize,
+    orig: Value<Vec<[F; W]>>,
+    shuf: Value<Vec<[F; W]>>,
+}
+
+#[derive(Clone, Debug)]
+struct ShuffleTestConfig<F: Field + Clone, const W: usize> {
+    chip: ShuffleChip<F, W>,
+}
+
+impl<F: Field, const W: usize> Circuit<F> for ShuffleTestCircuit<F, W> {
+    type Config = ShuffleTestConfig<F, W>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        ShuffleTestCircuit {
+            _ph: PhantomData,
+            height: self.height,
+            orig: Value::unknown(),
+            shuf: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let orig = [(); W].map(|_| meta.advice_column());
+        let shuf = [(); W].map(|_| meta.advice_column());
+        let chip = ShuffleChip::configure(meta, orig, shuf);
+        ShuffleTestConfig { chip }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), plonk::Error> {
+        config
+            .chip
+            .assign(&mut layouter, self.height, self.orig.clone(), self.shuf.clone())
+    }
+}
 
 const ROW_EQUALITY: [(usize, usize); 3] = [
     (0, 3), //
@@ -130,31 +456,39 @@ const ROW_EQUALITY: [(usize, usize); 3] = [
 #[derive(Clone, Debug)]
 struct TestConfig<F: Field + Clone> {
     _ph: PhantomData<F>,
-    rlc_chip: RLCChip<F, 3>,
-    advs: [Column<Advice>; 3],
+    rlc_chip: RLCChip<F>,
+    advs: Vec<Column<Advice>>,
 }
 
 impl<F: Field> Circuit<F> for TestCircuit<F> {
     type Config = TestConfig<F>;
     type FloorPlanner = SimpleFloorPlanner;
+    type Params = usize;
 
     fn without_witnesses(&self) -> Self {
         TestCircuit {
             _ph: PhantomData,
-            eq_rows: ROW_EQUALITY.to_vec(),
+            arity: self.arity,
+            eq_rows: self.eq_rows.clone(),
             assignment: Value::unknown(),
         }
     }
 
-    #[allow(unused_variables)]
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        let advs = [
-            meta.advice_column(),
-            meta.advice_column(),
-            meta.advice_column(),
-        ];
+    // the RLC arity is a runtime parameter: it sizes `advs` and the
+    // number of RLC powers summed in `RLCChip::configure`'s gate, rather
+    // than being baked in as a const generic
+    fn params(&self) -> Self::Params {
+        self.arity
+    }
+
+    fn configure(_meta: &mut ConstraintSystem<F>) -> Self::Config {
+        unreachable!("TestCircuit::Params != (); configure_with_params is called instead")
+    }
 
-        let rlc_chip = RLCChip::configure(meta, advs);
+    fn configure_with_params(meta: &mut ConstraintSystem<F>, arity: Self::Params) -> Self::Config {
+        let advs: Vec<Column<Advice>> = (0..arity).map(|_| meta.advice_column()).collect();
+
+        let rlc_chip = RLCChip::configure(meta, advs.clone());
 
         TestConfig {
             _ph: PhantomData,
@@ -181,15 +515,10 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
             + 1;
 
         // allocate the rows
-        let mut rows = vec![];
-        let mut rlcs = vec![];
-        for i in 0..num_rows {
-            let (row, rlc) = config
+        let (rows, rlcs) =
+            config
                 .rlc_chip
-                .alloc_row(&mut layouter, self.assignment.as_ref().map(|v| v[i]))?;
-            rows.push(row);
-            rlcs.push(rlc);
-        }
+                .alloc_rows(&mut layouter, num_rows, &self.assignment)?;
 
         //
         layouter.assign_region(
@@ -209,24 +538,25 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
 fn main() {
     use ff::PrimeField;
     use halo2_proofs::halo2curves::bn256::Fr;
-    let assignment = [
-        [Fr::from_u128(1), Fr::from_u128(2), Fr::from_u128(3)], // row 0
-        [Fr::from_u128(4), Fr::from_u128(5), Fr::from_u128(6)], // row 1
-        [Fr::from_u128(4), Fr::from_u128(5), Fr::from_u128(6)], // row 2
-        [Fr::from_u128(1), Fr::from_u128(2), Fr::from_u128(3)], // row 3
-        [
+    let assignment: Vec<Vec<Fr>> = vec![
+        vec![Fr::from_u128(1), Fr::from_u128(2), Fr::from_u128(3)], // row 0
+        vec![Fr::from_u128(4), Fr::from_u128(5), Fr::from_u128(6)], // row 1
+        vec![Fr::from_u128(4), Fr::from_u128(5), Fr::from_u128(6)], // row 2
+        vec![Fr::from_u128(1), Fr::from_u128(2), Fr::from_u128(3)], // row 3
+        vec![
             Fr::from_u128(0xbeef),
             Fr::from_u128(0xcafe),
             Fr::from_u128(0xf00d),
         ], // row 4
-        [Fr::from_u128(1), Fr::from_u128(2), Fr::from_u128(3)], // row 5
+        vec![Fr::from_u128(1), Fr::from_u128(2), Fr::from_u128(3)], // row 5
     ];
 
-    println!("check witness");
+    println!("check witness (arity 3)");
     let circuit = TestCircuit::<Fr> {
         _ph: PhantomData,
+        arity: 3,
         eq_rows: ROW_EQUALITY.to_vec(),
-        assignment: Value::known(assignment.to_vec()),
+        assignment: Value::known(assignment),
     };
     let prover = MockProver::run(8, &circuit, vec![]).unwrap();
     prover.verify().unwrap();
@@ -253,4 +583,71 @@ fn main() {
         TestCircuit<Fr>,
     >(&srs, &pk, &[circuit], &[&[]], rng, &mut transcript)
     .unwrap();
+
+    // same `TestCircuit` code path, a different `Params` (arity 5 instead
+    // of 3): `configure_with_params` sizes `advs` and the RLC gate purely
+    // from the runtime parameter, no recompilation needed
+    println!("check witness (arity 5)");
+    let wide_assignment: Vec<Vec<Fr>> = vec![
+        vec![
+            Fr::from_u128(1),
+            Fr::from_u128(2),
+            Fr::from_u128(3),
+            Fr::from_u128(4),
+            Fr::from_u128(5),
+        ], // row 0
+        vec![
+            Fr::from_u128(1),
+            Fr::from_u128(2),
+            Fr::from_u128(3),
+            Fr::from_u128(4),
+            Fr::from_u128(5),
+        ], // row 1
+        vec![
+            Fr::from_u128(6),
+            Fr::from_u128(7),
+            Fr::from_u128(8),
+            Fr::from_u128(9),
+            Fr::from_u128(10),
+        ], // row 2
+    ];
+    let wide_circuit = TestCircuit::<Fr> {
+        _ph: PhantomData,
+        arity: 5,
+        eq_rows: vec![(0, 1), (2, 2)],
+        assignment: Value::known(wide_assignment),
+    };
+    let prover = MockProver::run(8, &wide_circuit, vec![]).unwrap();
+    prover.verify().unwrap();
+
+    println!("check shuffle");
+    let orig: Vec<[Fr; 2]> = vec![
+        [Fr::from_u128(1), Fr::from_u128(2)],
+        [Fr::from_u128(3), Fr::from_u128(4)],
+        [Fr::from_u128(5), Fr::from_u128(6)],
+    ];
+    let mut shuf = orig.clone();
+    shuf.swap(0, 2);
+
+    let shuffle_circuit = ShuffleTestCircuit::<Fr, 2> {
+        _ph: PhantomData,
+        height: orig.len(),
+        orig: Value::known(orig.clone()),
+        shuf: Value::known(shuf.clone()),
+    };
+    let prover = MockProver::run(8, &shuffle_circuit, vec![]).unwrap();
+    prover.verify().unwrap();
+
+    // corrupting one row of the shuffled side breaks the multiset equality,
+    // so the grand product can no longer return to 1
+    let mut bad_shuf = shuf;
+    bad_shuf[0] = [Fr::from_u128(999), Fr::from_u128(999)];
+    let bad_shuffle_circuit = ShuffleTestCircuit::<Fr, 2> {
+        _ph: PhantomData,
+        height: orig.len(),
+        orig: Value::known(orig),
+        shuf: Value::known(bad_shuf),
+    };
+    let prover = MockProver::run(8, &bad_shuffle_circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
 }
@@ -1,15 +1,451 @@
 use std::marker::PhantomData;
 
 use halo2_proofs::{
-    circuit::{layouter, AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
+    circuit::{layouter, AssignedCell, Cell, Layouter, Region, SimpleFloorPlanner, Value},
     dev::MockProver,
     plonk::{
-        self, Advice, Circuit, Column, ConstraintSystem, Error, Expression, Selector, TableColumn,
+        self, Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance,
+        Selector, TableColumn, VirtualCells,
     },
     poly::Rotation,
 };
 
 use ff::{BitViewSized, Field, PrimeFieldBits};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+// A small reusable layer every chip in this file builds on, matching the
+// ecosystem's utilities-chip pattern (a `Var` handle plus a
+// `UtilitiesInstructions` trait) instead of each chip hand-rolling its own
+// "witness a private value"/"copy a cell" region boilerplate.
+mod utilities {
+    use super::*;
+
+    // The minimal handle a chip needs to read back a witnessed value.
+    // `AssignedCell<F, F>` is the only implementor here, but routing chip
+    // signatures through `Var` rather than the concrete type is what lets
+    // `UtilitiesInstructions` stay agnostic to it.
+    pub trait Var<F: Field>: Clone + std::fmt::Debug {
+        fn new(cell: AssignedCell<F, F>) -> Self;
+        fn cell(&self) -> Cell;
+        fn value(&self) -> Value<F>;
+    }
+
+    impl<F: Field> Var<F> for AssignedCell<F, F> {
+        fn new(cell: AssignedCell<F, F>) -> Self {
+            cell
+        }
+        fn cell(&self) -> Cell {
+            AssignedCell::cell(self)
+        }
+        fn value(&self) -> Value<F> {
+            self.value().copied()
+        }
+    }
+
+    // One consistent way to witness and copy cells, for every chip in this
+    // file that implements it.
+    pub trait UtilitiesInstructions<F: Field> {
+        type Var: Var<F>;
+
+        // witnesses `value` into `column` as a brand-new, unconstrained cell
+        fn load_private(
+            &self,
+            layouter: &mut impl Layouter<F>,
+            column: Column<Advice>,
+            value: Value<F>,
+        ) -> Result<Self::Var, Error> {
+            layouter.assign_region(
+                || "load private",
+                |mut region| {
+                    region
+                        .assign_advice(|| "private value", column, 0, || value)
+                        .map(Self::Var::new)
+                },
+            )
+        }
+
+        // copies `var` into `column` at `offset`, constraining the two cells equal
+        fn copy(
+            &self,
+            region: &mut Region<'_, F>,
+            column: Column<Advice>,
+            offset: usize,
+            var: &Self::Var,
+        ) -> Result<Self::Var, Error> {
+            let cell = region.assign_advice(|| "copy", column, offset, || var.value())?;
+            region.constrain_equal(cell.cell(), var.cell())?;
+            Ok(Self::Var::new(cell))
+        }
+    }
+}
+use utilities::{UtilitiesInstructions, Var};
+
+// A Pow5-style Poseidon sponge chip: a width-3 permutation witnessed
+// directly as the hashing circuit's own rows (full and partial rounds each
+// get their own gate), rather than `conditional-poseidon.rs`'s approach of
+// pre-populating a permutation trace and proving membership in it via
+// `lookup_any`. Round constants and the MDS matrix are, like that file's,
+// generated from a seeded RNG rather than derived from a real Grain LFSR --
+// this toy instance makes no security claim, same as the course's other
+// Poseidon example.
+mod poseidon {
+    use super::*;
+
+    pub const WIDTH: usize = 3;
+    pub const RATE: usize = WIDTH - 1;
+
+    // Split half before, half after the partial rounds, as in real
+    // Poseidon; both counts are toy-sized (not a security target).
+    const FULL_ROUNDS: usize = 8;
+    const PARTIAL_ROUNDS: usize = 16;
+    const TOTAL_ROUNDS: usize = FULL_ROUNDS + PARTIAL_ROUNDS;
+
+    fn is_full_round(r: usize) -> bool {
+        r < FULL_ROUNDS / 2 || r >= FULL_ROUNDS / 2 + PARTIAL_ROUNDS
+    }
+
+    fn sbox<F: Field>(x: F) -> F {
+        x * x * x * x * x
+    }
+
+    fn sbox_expr<F: Field>(x: Expression<F>) -> Expression<F> {
+        x.clone() * x.clone() * x.clone() * x.clone() * x
+    }
+
+    // Cauchy matrix, the same construction `conditional-poseidon.rs` uses
+    // for its own toy MDS matrix.
+    fn mds_matrix<F: Field>() -> [[F; WIDTH]; WIDTH] {
+        let mut matrix = [[F::ZERO; WIDTH]; WIDTH];
+        let mut rng = ChaCha8Rng::seed_from_u64(0x9e3779b97f4a7c15);
+        let xi: [F; WIDTH] = [(); WIDTH].map(|_| F::random(&mut rng));
+        let yi: [F; WIDTH] = [(); WIDTH].map(|_| F::random(&mut rng));
+        for i in 0..WIDTH {
+            for j in 0..WIDTH {
+                matrix[i][j] = (xi[i] + yi[j]).invert().unwrap();
+            }
+        }
+        matrix
+    }
+
+    fn round_constants<F: Field>() -> [[F; WIDTH]; TOTAL_ROUNDS] {
+        let mut rc = [[F::ZERO; WIDTH]; TOTAL_ROUNDS];
+        let mut rng = ChaCha8Rng::seed_from_u64(0x517cc1b727220a95);
+        for round in rc.iter_mut() {
+            for v in round.iter_mut() {
+                *v = F::random(&mut rng);
+            }
+        }
+        rc
+    }
+
+    // Computed once (at `configure` time in-circuit, or once per call on
+    // the host side) and reused, rather than regenerated per round.
+    pub fn params<F: Field>() -> ([[F; WIDTH]; WIDTH], [[F; WIDTH]; TOTAL_ROUNDS]) {
+        (mds_matrix(), round_constants())
+    }
+
+    // One full or partial round, applied out of circuit (used both to
+    // derive in-circuit witnesses and by `absorb_host` below).
+    fn apply_round<F: Field>(
+        matrix: &[[F; WIDTH]; WIDTH],
+        rc: &[F; WIDTH],
+        st: [F; WIDTH],
+        full: bool,
+    ) -> [F; WIDTH] {
+        let added = [st[0] + rc[0], st[1] + rc[1], st[2] + rc[2]];
+        let boxed = if full {
+            [sbox(added[0]), sbox(added[1]), sbox(added[2])]
+        } else {
+            [sbox(added[0]), added[1], added[2]]
+        };
+        [
+            matrix[0][0] * boxed[0] + matrix[0][1] * boxed[1] + matrix[0][2] * boxed[2],
+            matrix[1][0] * boxed[0] + matrix[1][1] * boxed[1] + matrix[1][2] * boxed[2],
+            matrix[2][0] * boxed[0] + matrix[2][1] * boxed[1] + matrix[2][2] * boxed[2],
+        ]
+    }
+
+    // `ConstantLength` domain separation: fold the absorbed length into the
+    // capacity lane once, up front, so hashing `[a]` can never collide with
+    // hashing `[a, 0]` just because the sponge zero-pads a short input.
+    pub fn domain_tag<F: Field>(len: usize) -> F {
+        F::from(len as u64)
+    }
+
+    // Host-side mirror of `PoseidonConfig::absorb`'s one-permutation step,
+    // for callers (like `main`) that need the expected output without
+    // building a circuit.
+    pub fn absorb_host<F: Field>(
+        matrix: &[[F; WIDTH]; WIDTH],
+        rc: &[[F; WIDTH]; TOTAL_ROUNDS],
+        capacity: F,
+        rate: [F; RATE],
+    ) -> F {
+        let mut state = [capacity, rate[0], rate[1]];
+        for (r, round_rc) in rc.iter().enumerate() {
+            state = apply_round(matrix, round_rc, state, is_full_round(r));
+        }
+        state[0]
+    }
+
+    // The capacity lane fed into one absorption: either a fresh domain tag
+    // (starting a new `ConstantLength` hash) or the previous absorption's
+    // output, carried forward across a multi-block sponge.
+    pub enum Capacity<'a, F: Field> {
+        Fresh(F),
+        Carried(&'a AssignedCell<F, F>),
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct PoseidonConfig<F: Field> {
+        matrix: [[F; WIDTH]; WIDTH],
+        rc: [[F; WIDTH]; TOTAL_ROUNDS],
+        state: [Column<Advice>; WIDTH],
+        rc_col: [Column<Fixed>; WIDTH],
+        q_full: Selector,
+        q_partial: Selector,
+    }
+
+    impl<F: Field> UtilitiesInstructions<F> for PoseidonConfig<F> {
+        type Var = AssignedCell<F, F>;
+    }
+
+    impl<F: Field> PoseidonConfig<F> {
+        pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+            let (matrix, rc) = params();
+
+            let state: [Column<Advice>; WIDTH] = [(); WIDTH].map(|_| meta.advice_column());
+            for col in state {
+                meta.enable_equality(col);
+            }
+            let rc_col: [Column<Fixed>; WIDTH] = [(); WIDTH].map(|_| meta.fixed_column());
+
+            let q_full = meta.selector();
+            let q_partial = meta.selector();
+
+            meta.create_gate("poseidon_full_round", |meta| {
+                let q = meta.query_selector(q_full);
+                Self::round_constraints(meta, &matrix, &state, &rc_col, q, true)
+            });
+
+            meta.create_gate("poseidon_partial_round", |meta| {
+                let q = meta.query_selector(q_partial);
+                Self::round_constraints(meta, &matrix, &state, &rc_col, q, false)
+            });
+
+            Self {
+                matrix,
+                rc,
+                state,
+                rc_col,
+                q_full,
+                q_partial,
+            }
+        }
+
+        // Shared full/partial round gate: `full` toggles whether the
+        // S-box is applied to every lane (a full round) or only the first
+        // (a partial round) before the fixed MDS mix.
+        fn round_constraints(
+            meta: &mut VirtualCells<'_, F>,
+            matrix: &[[F; WIDTH]; WIDTH],
+            state: &[Column<Advice>; WIDTH],
+            rc_col: &[Column<Fixed>; WIDTH],
+            q: Expression<F>,
+            full: bool,
+        ) -> Vec<Expression<F>> {
+            let cur = [
+                meta.query_advice(state[0], Rotation::cur()),
+                meta.query_advice(state[1], Rotation::cur()),
+                meta.query_advice(state[2], Rotation::cur()),
+            ];
+            let rcq = [
+                meta.query_fixed(rc_col[0], Rotation::cur()),
+                meta.query_fixed(rc_col[1], Rotation::cur()),
+                meta.query_fixed(rc_col[2], Rotation::cur()),
+            ];
+            let nxt = [
+                meta.query_advice(state[0], Rotation::next()),
+                meta.query_advice(state[1], Rotation::next()),
+                meta.query_advice(state[2], Rotation::next()),
+            ];
+
+            let added = [
+                cur[0].clone() + rcq[0].clone(),
+                cur[1].clone() + rcq[1].clone(),
+                cur[2].clone() + rcq[2].clone(),
+            ];
+
+            let boxed = if full {
+                [
+                    sbox_expr(added[0].clone()),
+                    sbox_expr(added[1].clone()),
+                    sbox_expr(added[2].clone()),
+                ]
+            } else {
+                [
+                    sbox_expr(added[0].clone()),
+                    added[1].clone(),
+                    added[2].clone(),
+                ]
+            };
+
+            let mixed = [
+                Expression::Constant(matrix[0][0]) * boxed[0].clone()
+                    + Expression::Constant(matrix[0][1]) * boxed[1].clone()
+                    + Expression::Constant(matrix[0][2]) * boxed[2].clone(),
+                Expression::Constant(matrix[1][0]) * boxed[0].clone()
+                    + Expression::Constant(matrix[1][1]) * boxed[1].clone()
+                    + Expression::Constant(matrix[1][2]) * boxed[2].clone(),
+                Expression::Constant(matrix[2][0]) * boxed[0].clone()
+                    + Expression::Constant(matrix[2][1]) * boxed[1].clone()
+                    + Expression::Constant(matrix[2][2]) * boxed[2].clone(),
+            ];
+
+            vec![
+                q.clone() * (mixed[0].clone() - nxt[0].clone()),
+                q.clone() * (mixed[1].clone() - nxt[1].clone()),
+                q * (mixed[2].clone() - nxt[2].clone()),
+            ]
+        }
+
+        // Witnesses a fresh domain tag into the capacity lane, to start a
+        // new `ConstantLength` absorption (as opposed to `Capacity::Carried`,
+        // which continues one already in progress).
+        pub fn seed(
+            &self,
+            layouter: &mut impl Layouter<F>,
+            len: usize,
+        ) -> Result<AssignedCell<F, F>, Error> {
+            self.load_private(layouter, self.state[0], Value::known(domain_tag(len)))
+        }
+
+        // Runs one Poseidon permutation over `capacity` and up to `RATE`
+        // new elements (zero-padded if fewer), returning the new capacity
+        // -- the digest, if this is the last absorption, or the carry into
+        // the next one.
+        pub fn absorb<const N: usize>(
+            &self,
+            layouter: &mut impl Layouter<F>,
+            capacity: Capacity<'_, F>,
+            chunk: [AssignedCell<F, F>; N],
+        ) -> Result<AssignedCell<F, F>, Error> {
+            assert!(N <= RATE, "chunk does not fit in one absorption");
+
+            layouter.assign_region(
+                || "poseidon_permute",
+                |mut region| {
+                    let cap = match capacity {
+                        Capacity::Fresh(tag) => region.assign_advice(
+                            || "capacity",
+                            self.state[0],
+                            0,
+                            || Value::known(tag),
+                        )?,
+                        Capacity::Carried(cell) => {
+                            self.copy(&mut region, self.state[0], 0, cell)?
+                        }
+                    };
+
+                    let mut rate_cells = Vec::with_capacity(RATE);
+                    for i in 0..RATE {
+                        if i < N {
+                            rate_cells.push(self.copy(
+                                &mut region,
+                                self.state[1 + i],
+                                0,
+                                &chunk[i],
+                            )?);
+                        } else {
+                            rate_cells.push(region.assign_advice(
+                                || "pad",
+                                self.state[1 + i],
+                                0,
+                                || Value::known(F::ZERO),
+                            )?);
+                        }
+                    }
+
+                    let mut cur = [
+                        cap.value().copied(),
+                        rate_cells[0].value().copied(),
+                        rate_cells[1].value().copied(),
+                    ];
+                    let mut digest = cap;
+
+                    let mut row = 0;
+                    for (r, round_rc) in self.rc.iter().enumerate() {
+                        let full = is_full_round(r);
+                        if full {
+                            self.q_full.enable(&mut region, row)?;
+                        } else {
+                            self.q_partial.enable(&mut region, row)?;
+                        }
+                        for i in 0..WIDTH {
+                            region.assign_fixed(
+                                || "rc",
+                                self.rc_col[i],
+                                row,
+                                || Value::known(round_rc[i]),
+                            )?;
+                        }
+
+                        let next = cur[0].zip(cur[1]).zip(cur[2]).map(|((s0, s1), s2)| {
+                            apply_round(&self.matrix, round_rc, [s0, s1, s2], full)
+                        });
+
+                        row += 1;
+                        let n0 = region.assign_advice(
+                            || "state0",
+                            self.state[0],
+                            row,
+                            || next.map(|v| v[0]),
+                        )?;
+                        let n1 = region.assign_advice(
+                            || "state1",
+                            self.state[1],
+                            row,
+                            || next.map(|v| v[1]),
+                        )?;
+                        let n2 = region.assign_advice(
+                            || "state2",
+                            self.state[2],
+                            row,
+                            || next.map(|v| v[2]),
+                        )?;
+
+                        cur = [
+                            n0.value().copied(),
+                            n1.value().copied(),
+                            n2.value().copied(),
+                        ];
+                        digest = n0;
+                    }
+
+                    Ok(digest)
+                },
+            )
+        }
+
+        // A single-block `ConstantLength` hash of up to `RATE` elements.
+        // Longer inputs (like `StateConfig`'s 5-field rows) chain several
+        // `absorb` calls by hand instead, carrying the capacity forward
+        // between them.
+        pub fn hash<const L: usize>(
+            &self,
+            layouter: &mut impl Layouter<F>,
+            inputs: [AssignedCell<F, F>; L],
+        ) -> Result<AssignedCell<F, F>, Error> {
+            assert!(
+                L <= RATE,
+                "ConstantLength: input does not fit in one absorption"
+            );
+            self.absorb(layouter, Capacity::Fresh(domain_tag(L)), inputs)
+        }
+    }
+}
+use poseidon::{Capacity, PoseidonConfig};
 
 #[derive(Clone, Debug)]
 struct RangeTable<F: PrimeFieldBits, const BITS: usize> {
@@ -53,6 +489,23 @@ struct RangeConfig<F: PrimeFieldBits, const BITS: usize, const LIMBS: usize> {
     limbs: [Column<Advice>; LIMBS],
     table: RangeTable<F, BITS>,
     q_enable: Selector,
+
+    // `check_short`: a verifier-known 0/1 mask per limb, so limbs above a
+    // runtime cutoff can be forced to zero, plus a scaled-lookup pair for
+    // the cutoff limb itself when it needs fewer than `BITS` bits
+    limb_active: [Column<Fixed>; LIMBS],
+    q_mask: Selector,
+    remainder: Column<Advice>,
+    scaled: Column<Advice>,
+    shift: Column<Fixed>,
+    q_short: Selector,
+
+    // `check_bounded`: ties a witnessed `diff` to `value` via a
+    // verifier-known `bound`, so both can be run through `check_short`
+    diff: Column<Advice>,
+    bound: Column<Fixed>,
+    q_bounded: Selector,
+
     _ph: PhantomData<F>,
 }
 
@@ -61,6 +514,12 @@ struct RangeConfig<F: PrimeFieldBits, const BITS: usize, const LIMBS: usize> {
 // v     < 2^(BITS * LIMBS)
 // B - v < 2^(BITS * LIMBS)
 //
+impl<F: PrimeFieldBits, const BITS: usize, const LIMBS: usize> UtilitiesInstructions<F>
+    for RangeConfig<F, BITS, LIMBS>
+{
+    type Var = AssignedCell<F, F>;
+}
+
 // Chip can check: 0 <= v < 2^(BITS * LIMBS)
 impl<F: PrimeFieldBits, const BITS: usize, const LIMBS: usize> RangeConfig<F, BITS, LIMBS> {
     fn configure(
@@ -98,11 +557,79 @@ impl<F: PrimeFieldBits, const BITS: usize, const LIMBS: usize> RangeConfig<F, BI
             });
         }
 
+        for limb in limbs.iter().cloned() {
+            meta.enable_equality(limb);
+        }
+
+        // `check_short`: force every limb at or above a runtime cutoff to
+        // zero, via a fixed 0/1 mask per limb (1 = active, so the
+        // existing per-limb lookup above is the only constraint on it; 0
+        // = must be zero)
+        let limb_active: [Column<Fixed>; LIMBS] = [(); LIMBS].map(|_| meta.fixed_column());
+        let q_mask = meta.selector();
+        for (limb, mask) in limbs.iter().cloned().zip(limb_active.iter().cloned()) {
+            meta.create_gate("limb forced to zero when inactive", |meta| {
+                let limb = meta.query_advice(limb, Rotation::cur());
+                let mask = meta.query_fixed(mask, Rotation::cur());
+                let q_mask = meta.query_selector(q_mask);
+                vec![q_mask * (Expression::Constant(F::ONE) - mask) * limb]
+            });
+        }
+
+        // `check_short`'s cutoff limb, when it needs fewer than `BITS`
+        // bits: `scaled = remainder * shift` (`shift = 2^(BITS -
+        // num_bits_in_remainder)`, verifier-known), and `scaled` is
+        // looked up against the same table, so the table also certifies
+        // `remainder < 2^(BITS - log2(shift))`
+        let remainder = meta.advice_column();
+        let scaled = meta.advice_column();
+        let shift = meta.fixed_column();
+        let q_short = meta.complex_selector();
+        meta.enable_equality(remainder);
+
+        meta.create_gate("short remainder scaling", |meta| {
+            let remainder = meta.query_advice(remainder, Rotation::cur());
+            let scaled = meta.query_advice(scaled, Rotation::cur());
+            let shift = meta.query_fixed(shift, Rotation::cur());
+            let q_short = meta.query_selector(q_short);
+            vec![q_short * (remainder * shift - scaled)]
+        });
+        meta.lookup("lookup_scaled_remainder", |meta| {
+            let scaled = meta.query_advice(scaled, Rotation::cur());
+            let q_short = meta.query_selector(q_short);
+            vec![(q_short * scaled, table.range)]
+        });
+
+        // `check_bounded`: `value + diff == bound`, with `bound`
+        // verifier-known (fixed) so it can be an arbitrary compile-time
+        // or runtime constant rather than baked into the gate
+        let diff = meta.advice_column();
+        let bound = meta.fixed_column();
+        let q_bounded = meta.selector();
+        meta.enable_equality(diff);
+
+        meta.create_gate("bounded", |meta| {
+            let value = meta.query_advice(value, Rotation::cur());
+            let diff = meta.query_advice(diff, Rotation::cur());
+            let bound = meta.query_fixed(bound, Rotation::cur());
+            let q_bounded = meta.query_selector(q_bounded);
+            vec![q_bounded * (value + diff - bound)]
+        });
+
         RangeConfig {
             value,
             table,
             q_enable,
             limbs,
+            limb_active,
+            q_mask,
+            remainder,
+            scaled,
+            shift,
+            q_short,
+            diff,
+            bound,
+            q_bounded,
             _ph: PhantomData,
         }
     }
@@ -115,12 +642,31 @@ impl<F: PrimeFieldBits, const BITS: usize, const LIMBS: usize> RangeConfig<F, BI
         assert!(BITS * LIMBS <= F::CAPACITY as usize);
 
         // decompose value into limbs
-        let limbs: Value<[F; LIMBS]> = value.value().map(|v| {
-            let le_bits = v.clone().to_le_bits();
+        let limbs = Self::decompose_limbs(value.value().copied());
+
+        // assign all the decomposed limbs
+        layouter.assign_region(
+            || "check_range",
+            |mut region| {
+                self.q_enable.enable(&mut region, 0)?;
+                self.copy(&mut region, self.value, 0, value)?;
+                for (i, limb) in self.limbs.iter().cloned().enumerate() {
+                    region.assign_advice(|| "limb", limb, 0, || limbs.map(|l| l[i]))?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    // Little-endian `BITS`-wide chunk decomposition shared by `check` and
+    // `check_short`, truncated to the bottom `LIMBS * BITS` bits of `v`.
+    fn decompose_limbs(v: Value<F>) -> Value<[F; LIMBS]> {
+        v.map(|v| {
+            let le_bits = v.to_le_bits();
             let le_bits: Vec<_> = le_bits.iter().take(LIMBS * BITS).collect();
             let mut limbs = Vec::with_capacity(LIMBS);
             for limb in le_bits.chunks_exact(BITS) {
-                let mut v = 0;
+                let mut v = 0u128;
                 for (i, bit) in limb.into_iter().enumerate() {
                     if **bit {
                         v += 1 << i;
@@ -131,16 +677,241 @@ impl<F: PrimeFieldBits, const BITS: usize, const LIMBS: usize> RangeConfig<F, BI
 
             assert_eq!(limbs.len(), LIMBS);
             limbs.try_into().unwrap()
-        });
+        })
+    }
 
-        // assign all the decomposed limbs
-        layouter.assign_region(
-            || "check_range",
+    // Number of bits needed to represent `v` (0 for `v == 0`).
+    fn bit_length(v: F) -> usize {
+        let bits = v.to_le_bits();
+        let mut len = 0;
+        for (i, bit) in bits.iter().enumerate() {
+            if *bit {
+                len = i + 1;
+            }
+        }
+        len
+    }
+
+    // Proves `0 <= v < 2^num_bits` for `num_bits <= BITS * LIMBS`, where
+    // `num_bits` need not be a multiple of `BITS`. Decomposes `v` into
+    // `LIMBS` limbs exactly like `check`, but every limb above the
+    // `ceil(num_bits / BITS)` cutoff is forced to zero (so a cheating
+    // prover can't hide weight above the claimed bound), and -- when the
+    // cutoff limb needs fewer than `BITS` bits -- that limb is also
+    // checked via the scaled lookup above instead of just the ordinary
+    // (too-loose) per-limb one.
+    fn check_short(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<(), Error> {
+        assert!(BITS * LIMBS <= F::CAPACITY as usize);
+        assert!(num_bits >= 1 && num_bits <= BITS * LIMBS);
+        let cutoff = (num_bits + BITS - 1) / BITS; // number of active limbs
+        let rem_bits = num_bits - (cutoff - 1) * BITS; // bits used by the top active limb, in (0, BITS]
+
+        let limbs = Self::decompose_limbs(value.value().copied());
+
+        let top_limb = layouter.assign_region(
+            || "check_short",
             |mut region| {
                 self.q_enable.enable(&mut region, 0)?;
-                value.copy_advice(|| "", &mut region, self.value, 0)?; //
+                self.q_mask.enable(&mut region, 0)?;
+                self.copy(&mut region, self.value, 0, value)?;
+
+                let mut top_limb_cell = None;
                 for (i, limb) in self.limbs.iter().cloned().enumerate() {
-                    region.assign_advice(|| "limb", limb, 0, || limbs.map(|l| l[i]))?;
+                    let active = i < cutoff;
+                    region.assign_fixed(
+                        || "limb_active",
+                        self.limb_active[i],
+                        0,
+                        || Value::known(if active { F::ONE } else { F::ZERO }),
+                    )?;
+                    let cell = region.assign_advice(
+                        || "limb",
+                        limb,
+                        0,
+                        || limbs.map(|l| if active { l[i] } else { F::ZERO }),
+                    )?;
+                    if i == cutoff - 1 {
+                        top_limb_cell = Some(cell);
+                    }
+                }
+                Ok(top_limb_cell.unwrap())
+            },
+        )?;
+
+        if rem_bits < BITS {
+            let shift = F::from_u128(1 << (BITS - rem_bits) as u128);
+            layouter.assign_region(
+                || "check_short_remainder",
+                |mut region| {
+                    self.q_short.enable(&mut region, 0)?;
+                    self.copy(&mut region, self.remainder, 0, &top_limb)?;
+                    region.assign_fixed(|| "shift", self.shift, 0, || Value::known(shift))?;
+                    region.assign_advice(
+                        || "scaled",
+                        self.scaled,
+                        0,
+                        || top_limb.value().map(|v| *v * shift),
+                    )?;
+                    Ok(())
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // Proves `0 <= v <= bound` for an arbitrary compile-time or runtime
+    // bound `B`, as promised by this chip's doc comment above:
+    // decomposes and range-checks both `v` and `B - v` via `check_short`,
+    // each against `B`'s own bit length rather than the full
+    // `BITS * LIMBS` capacity `check` proves.
+    fn check_bounded(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        bound: F,
+    ) -> Result<(), Error> {
+        let num_bits = Self::bit_length(bound).max(1);
+        assert!(
+            num_bits <= BITS * LIMBS,
+            "bound does not fit in BITS*LIMBS bits"
+        );
+
+        let diff = layouter.assign_region(
+            || "check_bounded",
+            |mut region| {
+                self.q_bounded.enable(&mut region, 0)?;
+                self.copy(&mut region, self.value, 0, value)?;
+                region.assign_fixed(|| "bound", self.bound, 0, || Value::known(bound))?;
+                region.assign_advice(
+                    || "bound_minus_value",
+                    self.diff,
+                    0,
+                    || value.value().map(|v| bound - *v),
+                )
+            },
+        )?;
+
+        self.check_short(layouter, value, num_bits)?;
+        self.check_short(layouter, &diff, num_bits)?;
+        Ok(())
+    }
+}
+
+// Alternative to `RangeConfig`: instead of `LIMBS` parallel advice
+// columns decomposed in a single row, lay the limbs out as a running sum
+// over `LIMBS+1` rows of one advice column. Given `v` to prove in
+// `[0, 2^(BITS*LIMBS))`, set `z_0 = v` and `z_{i+1} = (z_i - k_i) /
+// 2^BITS`, where `k_i` is the i-th little-endian `BITS`-wide chunk of
+// `v`; a gate derives `k_i = z_i - 2^BITS * z_{i+1}` from
+// `Rotation::cur()`/`Rotation::next()` and looks it up against the same
+// `RangeTable`, and `z_LIMBS` is constrained to zero. One column + one
+// lookup regardless of `LIMBS`, at the cost of `LIMBS+1` rows instead of
+// one. This is the standard Orchard-style running-sum decomposition.
+#[derive(Clone, Debug)]
+struct RunningSumRangeConfig<F: PrimeFieldBits, const BITS: usize, const LIMBS: usize> {
+    z: Column<Advice>,
+    table: RangeTable<F, BITS>,
+    q_enable: Selector,
+    q_last: Selector,
+    _ph: PhantomData<F>,
+}
+
+impl<F: PrimeFieldBits, const BITS: usize, const LIMBS: usize> UtilitiesInstructions<F>
+    for RunningSumRangeConfig<F, BITS, LIMBS>
+{
+    type Var = AssignedCell<F, F>;
+}
+
+impl<F: PrimeFieldBits, const BITS: usize, const LIMBS: usize>
+    RunningSumRangeConfig<F, BITS, LIMBS>
+{
+    fn configure(
+        meta: &mut ConstraintSystem<F>,
+        z: Column<Advice>,
+        table: RangeTable<F, BITS>,
+    ) -> Self {
+        let q_enable = meta.complex_selector();
+        let q_last = meta.selector();
+        meta.enable_equality(z);
+
+        // k_i = z_i - 2^BITS * z_{i+1}, looked up against the range table
+        meta.lookup("lookup_running_sum_chunk", |meta| {
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_nxt = meta.query_advice(z, Rotation::next());
+            let q_enable = meta.query_selector(q_enable);
+            let k = z_cur - Expression::Constant(F::from_u128(1 << BITS as u128)) * z_nxt;
+            vec![(q_enable * k, table.range)]
+        });
+
+        // z_LIMBS == 0
+        meta.create_gate("running_sum_ends_at_zero", |meta| {
+            let z_last = meta.query_advice(z, Rotation::cur());
+            let q_last = meta.query_selector(q_last);
+            vec![q_last * z_last]
+        });
+
+        Self {
+            z,
+            table,
+            q_enable,
+            q_last,
+            _ph: PhantomData,
+        }
+    }
+
+    fn check(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        assert!(BITS * LIMBS <= F::CAPACITY as usize);
+
+        // z_0 = v, z_{i+1} = (z_i - k_i) / 2^BITS
+        let zs: Value<Vec<F>> = value.value().map(|v| {
+            let le_bits = v.clone().to_le_bits();
+            let le_bits: Vec<_> = le_bits.iter().take(LIMBS * BITS).collect();
+            let shift_inv = F::from_u128(1 << BITS as u128).invert().unwrap();
+
+            let mut zs = Vec::with_capacity(LIMBS + 1);
+            let mut acc = *v;
+            zs.push(acc);
+            for limb in le_bits.chunks_exact(BITS) {
+                let mut k = 0u128;
+                for (i, bit) in limb.iter().enumerate() {
+                    if **bit {
+                        k += 1 << i;
+                    }
+                }
+                acc = (acc - F::from_u128(k)) * shift_inv;
+                zs.push(acc);
+            }
+
+            assert_eq!(zs.len(), LIMBS + 1);
+            zs
+        });
+
+        layouter.assign_region(
+            || "check_range_running_sum",
+            |mut region| {
+                self.copy(&mut region, self.z, 0, value)?;
+                for i in 0..LIMBS {
+                    self.q_enable.enable(&mut region, i)?;
+                }
+                self.q_last.enable(&mut region, LIMBS)?;
+
+                for i in 1..=LIMBS {
+                    region.assign_advice(
+                        || format!("z_{}", i),
+                        self.z,
+                        i,
+                        || zs.as_ref().map(|z| z[i]),
+                    )?;
                 }
                 Ok(())
             },
@@ -153,6 +924,7 @@ impl<F: PrimeFieldBits, const BITS: usize, const LIMBS: usize> RangeConfig<F, BI
 #[derive(Clone, Debug)]
 struct RwTable<F: PrimeFieldBits, const ROWS: usize> {
     q_enable: Selector,         // is the RwTable defined for this row?
+    q_row: Selector,            // is this row a valid Rw row? (every row, incl. the last)
     addr: Column<Advice>,       // address of the cell
     rw_counter: Column<Advice>, // counter of the row
     val_old: Column<Advice>,    // prev. value of the cell
@@ -176,9 +948,45 @@ impl<F: PrimeFieldBits> RwRow<F> {
     }
 }
 
+// Host-side mirror of `StateConfig::commit`, so `main` can hand the
+// circuit's computed digest back to it as the public instance.
+fn expected_commitment<F: PrimeFieldBits>(rows: &[RwRow<F>]) -> F {
+    let mut sorted = rows.to_vec();
+    sorted.sort_by_key(|row| row.key());
+
+    let (matrix, rc) = poseidon::params::<F>();
+    let mut digest = poseidon::domain_tag::<F>(sorted.len());
+    for row in &sorted {
+        let addr: F = (row.addr as u64).into();
+        let rwc: F = (row.rw_counter as u64).into();
+        let is_write = if row.is_write { F::ONE } else { F::ZERO };
+
+        let seed = poseidon::domain_tag::<F>(5);
+        let d1 = poseidon::absorb_host(&matrix, &rc, seed, [addr, rwc]);
+        let d2 = poseidon::absorb_host(&matrix, &rc, d1, [row.val_old, row.val_new]);
+        let leaf = poseidon::absorb_host(&matrix, &rc, d2, [is_write, F::ZERO]);
+
+        digest = poseidon::absorb_host(&matrix, &rc, digest, [leaf, F::ZERO]);
+    }
+    digest
+}
+
+// The cells `RwTable::assign_with_region` produces for one row, so callers
+// (like `StateConfig::commit`) can fold the already-assigned values into
+// further gadgets instead of re-witnessing them.
+#[derive(Clone, Debug)]
+struct RwRowCells<F: PrimeFieldBits> {
+    addr: AssignedCell<F, F>,
+    rw_counter: AssignedCell<F, F>,
+    val_old: AssignedCell<F, F>,
+    val_new: AssignedCell<F, F>,
+    is_write: AssignedCell<F, F>,
+}
+
 impl<F: PrimeFieldBits, const ROWS: usize> RwTable<F, ROWS> {
     fn configure(meta: &mut ConstraintSystem<F>) -> Self {
         let q_enable = meta.selector();
+        let q_row = meta.selector();
 
         let addr = meta.advice_column();
         let val_old = meta.advice_column();
@@ -186,6 +994,22 @@ impl<F: PrimeFieldBits, const ROWS: usize> RwTable<F, ROWS> {
         let rw_counter = meta.advice_column();
         let is_write = meta.advice_column();
 
+        // `is_write` is a boolean flag.
+        meta.create_gate("is_write_bool", |meta| {
+            let q_row = meta.query_selector(q_row);
+            let is_write = meta.query_advice(is_write, Rotation::cur());
+            vec![q_row * is_write.clone() * (Expression::Constant(F::ONE) - is_write)]
+        });
+
+        // On a read (`is_write == 0`), the value must not change.
+        meta.create_gate("read_preserves_value", |meta| {
+            let q_row = meta.query_selector(q_row);
+            let is_write = meta.query_advice(is_write, Rotation::cur());
+            let val_old = meta.query_advice(val_old, Rotation::cur());
+            let val_new = meta.query_advice(val_new, Rotation::cur());
+            vec![q_row * (Expression::Constant(F::ONE) - is_write) * (val_old - val_new)]
+        });
+
         Self {
             addr,
             val_old,
@@ -193,6 +1017,7 @@ impl<F: PrimeFieldBits, const ROWS: usize> RwTable<F, ROWS> {
             rw_counter,
             is_write,
             q_enable,
+            q_row,
             _ph: PhantomData,
         }
     }
@@ -201,15 +1026,17 @@ impl<F: PrimeFieldBits, const ROWS: usize> RwTable<F, ROWS> {
         &self,
         rows: Value<Vec<RwRow<F>>>,
         region: &mut Region<'_, F>,
-    ) -> Result<(), Error> {
+    ) -> Result<Vec<RwRowCells<F>>, Error> {
+        let mut cells = Vec::with_capacity(ROWS);
         for i in 0..ROWS {
             // turn on the row
             if i != ROWS - 1 {
                 self.q_enable.enable(region, i)?;
             }
+            self.q_row.enable(region, i)?;
 
             // assign combined key
-            region.assign_advice(
+            let addr = region.assign_advice(
                 || format!("addr[{}]", i),
                 self.addr,
                 i,
@@ -220,7 +1047,7 @@ impl<F: PrimeFieldBits, const ROWS: usize> RwTable<F, ROWS> {
                     })
                 },
             )?;
-            region.assign_advice(
+            let rw_counter = region.assign_advice(
                 || format!("rw_counter[{}]", i),
                 self.rw_counter,
                 i,
@@ -231,19 +1058,19 @@ impl<F: PrimeFieldBits, const ROWS: usize> RwTable<F, ROWS> {
                     })
                 },
             )?;
-            region.assign_advice(
+            let val_old = region.assign_advice(
                 || format!("value_old[{}]", i),
                 self.val_old,
                 i,
                 || rows.as_ref().map(|m| m[i].val_old),
             )?;
-            region.assign_advice(
+            let val_new = region.assign_advice(
                 || format!("value_new[{}]", i),
                 self.val_new,
                 i,
                 || rows.as_ref().map(|m| m[i].val_new),
             )?;
-            region.assign_advice(
+            let is_write = region.assign_advice(
                 || format!("is_write[{}]", i),
                 self.is_write,
                 i,
@@ -252,28 +1079,97 @@ impl<F: PrimeFieldBits, const ROWS: usize> RwTable<F, ROWS> {
                         .map(|m| if m[i].is_write { F::ONE } else { F::ZERO })
                 },
             )?;
+
+            cells.push(RwRowCells {
+                addr,
+                rw_counter,
+                val_old,
+                val_new,
+                is_write,
+            });
         }
-        Ok(())
+        Ok(cells)
     }
 }
 
 #[derive(Clone, Debug)]
 struct StateConfig<F: PrimeFieldBits, const ROWS: usize> {
-    rw_table: RwTable<F, ROWS>,
+    rw_table: RwTable<F, ROWS>, // rows, proven sorted by (addr, rw_counter)
+    unsorted: RwTable<F, ROWS>, // the same rows, in original issue order
+    s_shuffle: Selector,        // gates the unsorted side of the shuffle
+    s_stable: Selector,         // gates the sorted side of the shuffle
     range64: RangeConfig<F, 8, 8>,
     delta: Column<Advice>,
+    addr_inv: Column<Advice>, // inverse hint for the addr_nxt == addr_cur selector
+    // fixed per-row issue-order index, pinned equal to `unsorted.rw_counter`
+    // so a prover can't permute which operation claims which counter --
+    // the shuffle above only proves multiset equality, not that `unsorted`
+    // itself reflects real issue order
+    row_index: Column<Fixed>,
+    q_first: Selector,           // enabled only on the very first row
+    poseidon: PoseidonConfig<F>, // folds the sorted trace into `commit`'s digest
+    instance: Column<Instance>,  // row 0 holds the digest the verifier checks against
+}
+
+impl<F: PrimeFieldBits, const ROWS: usize> UtilitiesInstructions<F> for StateConfig<F, ROWS> {
+    type Var = AssignedCell<F, F>;
 }
 
 impl<F: PrimeFieldBits, const ROWS: usize> StateConfig<F, ROWS> {
     fn configure(
         meta: &mut ConstraintSystem<F>,
         rw_table: RwTable<F, ROWS>,
+        unsorted: RwTable<F, ROWS>,
         range64: RangeConfig<F, 8, 8>,
     ) -> Self {
         let delta = meta.advice_column();
+        let addr_inv = meta.advice_column();
+        let row_index = meta.fixed_column();
+        let q_first = meta.selector();
+        let s_shuffle = meta.complex_selector();
+        let s_stable = meta.complex_selector();
 
         meta.enable_equality(delta);
 
+        // `unsorted.rw_counter` must equal the row's real position in
+        // issue order, not an arbitrary witness: `assign` pins
+        // `row_index` to that position for every row it assigns
+        meta.create_gate("unsorted rw_counter is real issue order", |meta| {
+            let s_shuffle = meta.query_selector(s_shuffle);
+            let rwc = meta.query_advice(unsorted.rw_counter, Rotation::cur());
+            let row_index = meta.query_fixed(row_index, Rotation::cur());
+            vec![s_shuffle * (rwc - row_index)]
+        });
+
+        // `unsorted` (original issue order) and `rw_table` (claimed sorted
+        // order) are the same multiset of (addr, rw_counter, val_old,
+        // val_new, is_write) tuples -- this is what lets `assign` sort the
+        // rows itself instead of trusting a pre-sorted caller.
+        meta.shuffle("rw trace shuffle", |meta| {
+            let s_shuffle = meta.query_selector(s_shuffle);
+            let s_stable = meta.query_selector(s_stable);
+
+            let addr_u = meta.query_advice(unsorted.addr, Rotation::cur());
+            let rwc_u = meta.query_advice(unsorted.rw_counter, Rotation::cur());
+            let old_u = meta.query_advice(unsorted.val_old, Rotation::cur());
+            let new_u = meta.query_advice(unsorted.val_new, Rotation::cur());
+            let isw_u = meta.query_advice(unsorted.is_write, Rotation::cur());
+
+            let addr_s = meta.query_advice(rw_table.addr, Rotation::cur());
+            let rwc_s = meta.query_advice(rw_table.rw_counter, Rotation::cur());
+            let old_s = meta.query_advice(rw_table.val_old, Rotation::cur());
+            let new_s = meta.query_advice(rw_table.val_new, Rotation::cur());
+            let isw_s = meta.query_advice(rw_table.is_write, Rotation::cur());
+
+            vec![
+                (s_shuffle.clone() * addr_u, s_stable.clone() * addr_s),
+                (s_shuffle.clone() * rwc_u, s_stable.clone() * rwc_s),
+                (s_shuffle.clone() * old_u, s_stable.clone() * old_s),
+                (s_shuffle.clone() * new_u, s_stable.clone() * new_s),
+                (s_shuffle * isw_u, s_stable * isw_s),
+            ]
+        });
+
         meta.create_gate("delta_gate", |meta| {
             let delta = meta.query_advice(delta, Rotation::cur());
             let q_enable = meta.query_selector(rw_table.q_enable);
@@ -290,27 +1186,127 @@ impl<F: PrimeFieldBits, const ROWS: usize> StateConfig<F, ROWS> {
             vec![q_enable * (delta - (key_nxt - key_cur))]
         });
 
+        // Memory-consistency gates, gated on the same (addr, rw_counter)-sorted
+        // adjacency as `delta_gate` above.
+        meta.create_gate("memory_consistency", |meta| {
+            let q_enable = meta.query_selector(rw_table.q_enable);
+
+            let addr_cur = meta.query_advice(rw_table.addr, Rotation::cur());
+            let addr_nxt = meta.query_advice(rw_table.addr, Rotation::next());
+            let addr_inv = meta.query_advice(addr_inv, Rotation::cur());
+
+            let val_new_cur = meta.query_advice(rw_table.val_new, Rotation::cur());
+            let val_old_nxt = meta.query_advice(rw_table.val_old, Rotation::next());
+
+            let diff = addr_nxt - addr_cur;
+            // is_equal == 1 when addr_nxt == addr_cur, 0 otherwise; sound only
+            // because the "diff * is_equal == 0" constraint below forces the
+            // prover to supply `addr_inv == diff^{-1}` whenever diff != 0.
+            let is_equal = Expression::Constant(F::ONE) - diff.clone() * addr_inv;
+
+            vec![
+                q_enable.clone() * diff * is_equal.clone(),
+                // same address: carry the value over
+                q_enable.clone() * is_equal.clone() * (val_old_nxt.clone() - val_new_cur),
+                // new address: first access starts from zero
+                q_enable * (Expression::Constant(F::ONE) - is_equal) * val_old_nxt,
+            ]
+        });
+
+        // the very first row of the table is always a first access
+        meta.create_gate("first_access_fresh", |meta| {
+            let q_first = meta.query_selector(q_first);
+            let val_old_cur = meta.query_advice(rw_table.val_old, Rotation::cur());
+            vec![q_first * val_old_cur]
+        });
+
+        let poseidon = PoseidonConfig::configure(meta);
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
         Self {
             rw_table,
+            unsorted,
+            s_shuffle,
+            s_stable,
             delta,
             range64,
+            addr_inv,
+            row_index,
+            q_first,
+            poseidon,
+            instance,
         }
     }
 
+    // `rows` may be supplied in any order (e.g. original issue order) --
+    // this sorts them by `(addr, rw_counter)` itself and uses the shuffle
+    // argument above to prove the sorted table it actually constrains is a
+    // faithful permutation of the caller's input.
+    //
+    // The per-row cells below are witnessed directly via `region.assign_advice`
+    // rather than `load_private`, since they all share one multi-row region;
+    // `load_private`/`copy` fit a single cell in its own region, not a batch.
+    // The `delta` cells this produces are copied into `range64` through the
+    // same shared `UtilitiesInstructions::copy` convention, inside `check`.
     fn assign(
         &self,
         rows: Value<Vec<RwRow<F>>>,
         layouter: &mut impl Layouter<F>,
     ) -> Result<(), Error> {
-        let range_64 = layouter.assign_region(
+        let sorted_rows: Value<Vec<RwRow<F>>> = rows.as_ref().map(|rows| {
+            let mut sorted = rows.clone();
+            sorted.sort_by_key(|row| row.key());
+            sorted
+        });
+
+        let (sorted_cells, range_64) = layouter.assign_region(
             || "state",
             |mut region| {
-                // assigns the RwTable
-                self.rw_table
+                // assigns the unsorted (original issue order) side of the shuffle
+                self.unsorted
                     .assign_with_region(rows.clone(), &mut region)?;
+                for i in 0..ROWS {
+                    self.s_shuffle.enable(&mut region, i)?;
+                    region.assign_fixed(
+                        || format!("row_index[{}]", i),
+                        self.row_index,
+                        i,
+                        || Value::known(F::from(i as u64)),
+                    )?;
+                }
+
+                // assigns the sorted RwTable
+                let sorted_cells = self
+                    .rw_table
+                    .assign_with_region(sorted_rows.clone(), &mut region)?;
+                for i in 0..ROWS {
+                    self.s_stable.enable(&mut region, i)?;
+                }
+
+                // the first row of the table is always a first access
+                self.q_first.enable(&mut region, 0)?;
+
+                // assign the addr_nxt == addr_cur inverse hints
+                let addr_invs: Value<Vec<F>> = sorted_rows.as_ref().map(|rows| {
+                    rows.windows(2)
+                        .map(|win| {
+                            let diff: F = F::from(win[1].addr as u64) - F::from(win[0].addr as u64);
+                            diff.invert().unwrap_or(F::ZERO)
+                        })
+                        .collect()
+                });
+                for i in 0..ROWS - 1 {
+                    region.assign_advice(
+                        || format!("addr_inv[{}]", i),
+                        self.addr_inv,
+                        i,
+                        || addr_invs.as_ref().map(|m| m[i]),
+                    )?;
+                }
 
                 //
-                let deltas: Value<Vec<u64>> = rows.as_ref().map(|rows| {
+                let deltas: Value<Vec<u64>> = sorted_rows.as_ref().map(|rows| {
                     rows.windows(2)
                         .map(|win| {
                             let cur = &win[0];
@@ -336,7 +1332,7 @@ impl<F: PrimeFieldBits, const ROWS: usize> StateConfig<F, ROWS> {
                     )?);
                 }
 
-                Ok(range_64)
+                Ok((sorted_cells, range_64))
             },
         )?;
 
@@ -345,7 +1341,35 @@ impl<F: PrimeFieldBits, const ROWS: usize> StateConfig<F, ROWS> {
             self.range64.check(layouter, cell)?;
         }
 
-        Ok(())
+        self.commit(layouter, &sorted_cells)
+    }
+
+    // Folds each sorted row into a running Poseidon hash -- (addr, rw_counter),
+    // then (val_old, val_new), then is_write, each absorption carrying the
+    // previous one's capacity forward -- and binds the final digest to
+    // `instance` row 0. `expected_commitment` is the host-side mirror the
+    // caller uses to compute that public input.
+    fn commit(&self, layouter: &mut impl Layouter<F>, rows: &[RwRowCells<F>]) -> Result<(), Error> {
+        let mut digest = self.poseidon.seed(layouter, rows.len())?;
+        for row in rows {
+            let d1 = self.poseidon.absorb(
+                layouter,
+                Capacity::Fresh(poseidon::domain_tag(5)),
+                [row.addr.clone(), row.rw_counter.clone()],
+            )?;
+            let d2 = self.poseidon.absorb(
+                layouter,
+                Capacity::Carried(&d1),
+                [row.val_old.clone(), row.val_new.clone()],
+            )?;
+            let leaf =
+                self.poseidon
+                    .absorb(layouter, Capacity::Carried(&d2), [row.is_write.clone()])?;
+            digest = self
+                .poseidon
+                .absorb(layouter, Capacity::Carried(&digest), [leaf])?;
+        }
+        layouter.constrain_instance(digest.cell(), self.instance, 0)
     }
 }
 
@@ -354,11 +1378,17 @@ struct TestCircuit<F: PrimeFieldBits> {
     rw_table: Value<Vec<RwRow<F>>>,
 }
 
+impl<F: PrimeFieldBits> UtilitiesInstructions<F> for TestCircuit<F> {
+    type Var = AssignedCell<F, F>;
+}
+
 #[derive(Clone, Debug)]
 struct TestConfig<F: PrimeFieldBits + Clone> {
     value: Column<Advice>,
     tabl_range: RangeTable<F, 8>,
     chip_range: RangeConfig<F, 8, 8>,
+    z: Column<Advice>,
+    chip_range_rs: RunningSumRangeConfig<F, 8, 8>,
     rw_table: RwTable<F, 4>,
     state: StateConfig<F, 4>,
     _ph: PhantomData<F>,
@@ -382,14 +1412,25 @@ impl<F: PrimeFieldBits> Circuit<F> for TestCircuit<F> {
         let tabl_range = RangeTable::<F, 8>::configure(meta);
         let chip_range = RangeConfig::configure(meta, value, tabl_range.clone(), limbs);
 
+        let z = meta.advice_column();
+        let chip_range_rs = RunningSumRangeConfig::configure(meta, z, tabl_range.clone());
+
         let rw_table = RwTable::<F, 4>::configure(meta);
-        let state = StateConfig::<F, 4>::configure(meta, rw_table.clone(), chip_range.clone());
+        let unsorted_rw_table = RwTable::<F, 4>::configure(meta);
+        let state = StateConfig::<F, 4>::configure(
+            meta,
+            rw_table.clone(),
+            unsorted_rw_table,
+            chip_range.clone(),
+        );
 
         TestConfig {
             _ph: PhantomData,
             value,
             tabl_range,
             chip_range,
+            z,
+            chip_range_rs,
             rw_table,
             state,
         }
@@ -403,23 +1444,24 @@ impl<F: PrimeFieldBits> Circuit<F> for TestCircuit<F> {
     ) -> Result<(), plonk::Error> {
         config.tabl_range.load(&mut layouter)?;
 
-        let free = layouter.assign_region(
-            || "test",
-            |mut region| {
-                region.assign_advice(
-                    || "test",
-                    config.value,
-                    0,
-                    || {
-                        let v: F = 10_000u64.into();
-                        Value::known(v)
-                    },
-                )
-            },
-        )?;
+        let v: F = 10_000u64.into();
+        let free = self.load_private(&mut layouter, config.value, Value::known(v))?;
 
         config.chip_range.check(&mut layouter, &free)?;
 
+        // same value, checked via the running-sum decomposition instead
+        // of the parallel-limb-columns one above
+        let free_rs = self.load_private(&mut layouter, config.z, Value::known(v))?;
+        config.chip_range_rs.check(&mut layouter, &free_rs)?;
+
+        // same value, checked against the exact upper bound `10_001`
+        // instead of the full `2^(BITS*LIMBS)` capacity
+        let free_bounded = self.load_private(&mut layouter, config.value, Value::known(v))?;
+        let bound: F = 10_001u64.into();
+        config
+            .chip_range
+            .check_bounded(&mut layouter, &free_bounded, bound)?;
+
         config.state.assign(self.rw_table.clone(), &mut layouter)?;
 
         /*
@@ -440,6 +1482,10 @@ impl<F: PrimeFieldBits> Circuit<F> for TestCircuit<F> {
 fn main() {
     use halo2_proofs::halo2curves::bn256::Fr;
 
+    // rows are given in original issue (rw_counter) order, not sorted by
+    // (addr, rw_counter) -- `StateConfig::assign` sorts them itself and
+    // the shuffle argument proves the sorted table it constrains is a
+    // faithful permutation of this trace.
     let rw_rows = vec![
         RwRow {
             addr: 0,
@@ -448,13 +1494,6 @@ fn main() {
             rw_counter: 0,
             is_write: true,
         },
-        RwRow {
-            addr: 0,
-            val_old: Fr::from(1u64),
-            val_new: Fr::from(1u64),
-            rw_counter: 2,
-            is_write: false,
-        },
         RwRow {
             addr: 1,
             val_old: Fr::from(0u64),
@@ -462,6 +1501,13 @@ fn main() {
             rw_counter: 1,
             is_write: true,
         },
+        RwRow {
+            addr: 0,
+            val_old: Fr::from(1u64),
+            val_new: Fr::from(1u64),
+            rw_counter: 2,
+            is_write: false,
+        },
         RwRow {
             addr: 2,
             val_old: Fr::from(0u64),
@@ -471,10 +1517,15 @@ fn main() {
         },
     ];
 
+    // binds the proof to this exact trace: a verifier re-derives this same
+    // digest from the public trace it expects and checks it against the
+    // instance column instead of trusting the prover's claimed rows.
+    let instance = vec![expected_commitment(&rw_rows)];
+
     let circuit = TestCircuit::<Fr> {
         _ph: PhantomData,
         rw_table: Value::known(rw_rows),
     };
-    let prover = MockProver::run(16, &circuit, vec![]).unwrap();
+    let prover = MockProver::run(16, &circuit, vec![instance]).unwrap();
     prover.verify().unwrap();
 }
@@ -0,0 +1,993 @@
+// Alongside the AES example (aes.rs, session-4.rs's LookupChip), this file
+// demonstrates the other classic lookup-heavy trick for bit-twiddly crypto:
+// the "spread table".
+//
+// A `b`-bit dense value `x = x_{b-1} ... x_1 x_0` has a *spread* form
+// `spread(x)` where each bit `x_k` is placed at position `2k`, i.e. every
+// bit is interleaved with a zero:
+//
+//   dense:  x_2 x_1 x_0
+//   spread: 0 x_2 0 x_1 0 x_0
+//
+// The trick: if you add together the spread forms of N one-bit-at-a-time
+// aligned values, no lane can overflow into its neighbour as long as N < 4
+// (each lane holds a number 0..=N, which for N in {2, 3} fits in the 2 bits
+// available before the next lane starts). So, reading back the sum's bit at
+// position `2k` gives the XOR of the N inputs' bit `k`, and the bit at
+// position `2k + 1` gives their carry - which for N = 2 is AND(x, y), and
+// for N = 3 is Maj(x, y, z). That turns XOR/Maj/Ch into a handful of table
+// lookups and additions instead of one boolean constraint per bit, which is
+// exactly how halo2's own `table16` SHA-256 gadget is built.
+//
+// This file works the same way, simplified for a course example: words are
+// split into 16-bit halves, each half is looked up against a single
+// (tag, dense, spread) table shared by every halving in the circuit (the
+// `tag` column is what lets the same three columns also host an unrelated
+// small range-check, rather than needing a second table). Rotations and
+// shifts fall outside the spread trick (they need to be realised as a plain
+// bit permutation, not a bit-algebra identity), so those are done via an
+// explicit 32-bit boolean decomposition instead. A "real" table16-style
+// implementation picks its chunk boundaries so that a rotated word's spread
+// can be read off the *same* lookups used for the unrotated word; we pay an
+// extra round of lookups per rotation instead, in exchange for a much
+// smaller gadget.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Selector,
+        TableColumn,
+    },
+    poly::Rotation,
+};
+
+use ff::{Field, PrimeField};
+
+// a 32-bit word, tracked both as a witness value (for computing further
+// witnesses) and as the cell that carries it through the circuit
+#[derive(Clone, Debug)]
+struct Word32<F: Field> {
+    value: Value<u32>,
+    cell: AssignedCell<F, F>,
+}
+
+fn spread16(dense: u16) -> u32 {
+    let mut spread = 0u32;
+    for k in 0..16 {
+        if (dense >> k) & 1 == 1 {
+            spread |= 1 << (2 * k);
+        }
+    }
+    spread
+}
+
+// distinguishes the two uses the (tag, dense, spread) columns are put to:
+// real 16-bit spread entries, and a small range-checked carry table reused
+// by `add_mod32` to bound how far a multi-word sum can overflow 2^32.
+const TAG_SPREAD16: u64 = 0;
+const TAG_CARRY: u64 = 1;
+const CARRY_RANGE: u64 = 8; // generous: T1 sums at most 5 words, so carry < 5
+
+// ANCHOR: spread_table_chip
+#[derive(Clone, Debug)]
+struct SpreadTableChip<F: Field> {
+    tag: TableColumn,
+    dense: TableColumn,
+    spread: TableColumn,
+    _ph: PhantomData<F>,
+}
+
+impl<F: PrimeField> SpreadTableChip<F> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        SpreadTableChip {
+            tag: meta.lookup_table_column(),
+            dense: meta.lookup_table_column(),
+            spread: meta.lookup_table_column(),
+            _ph: PhantomData,
+        }
+    }
+
+    fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "spread table",
+            |mut table| {
+                let mut row = 0;
+                for dense in 0..=0xffffu32 {
+                    table.assign_cell(
+                        || "tag",
+                        self.tag,
+                        row,
+                        || Value::known(F::from(TAG_SPREAD16)),
+                    )?;
+                    table.assign_cell(
+                        || "dense",
+                        self.dense,
+                        row,
+                        || Value::known(F::from(dense as u64)),
+                    )?;
+                    table.assign_cell(
+                        || "spread",
+                        self.spread,
+                        row,
+                        || Value::known(F::from(spread16(dense as u16) as u64)),
+                    )?;
+                    row += 1;
+                }
+                for carry in 0..CARRY_RANGE {
+                    table.assign_cell(
+                        || "tag",
+                        self.tag,
+                        row,
+                        || Value::known(F::from(TAG_CARRY)),
+                    )?;
+                    table.assign_cell(|| "dense", self.dense, row, || Value::known(F::from(carry)))?;
+                    table.assign_cell(|| "spread", self.spread, row, || Value::known(F::ZERO))?;
+                    row += 1;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+// ANCHOR_END: spread_table_chip
+
+fn pow2<F: PrimeField>(i: u32) -> F {
+    F::from(1u64 << i)
+}
+
+#[derive(Clone, Debug)]
+struct Sha256Chip<F: Field> {
+    table: SpreadTableChip<F>,
+
+    // (tag, dense, spread) lookup, shared by every spread conversion and
+    // every carry range check in the circuit
+    q_lookup: Selector,
+    tag: Column<Fixed>,
+    dense: Column<Advice>,
+    spread: Column<Advice>,
+
+    // word = hi * scale + lo, with `scale` fixed to 2^16: splits a 32-bit
+    // word into 16-bit halves (`halves`) and recomposes one back
+    // (`compose`). A per-row fixed column rather than a hardcoded constant
+    // only because that's the column shape the rest of the chip uses.
+    q_scale: Selector,
+    word: Column<Advice>,
+    hi: Column<Advice>,
+    lo: Column<Advice>,
+    scale: Column<Fixed>,
+
+    // t0 + t1 + t2 = even + 2 * odd: the "unspread" identity that recovers
+    // an N-way XOR (even) and its carry (odd, = AND for N=2 or Maj for N=3)
+    // from the sum of N spread values. Unused terms are assigned zero.
+    q_unspread: Selector,
+    t0: Column<Advice>,
+    t1: Column<Advice>,
+    t2: Column<Advice>,
+    even: Column<Advice>,
+    odd: Column<Advice>,
+
+    // bit-by-bit decomposition used for rotations/shifts: `bit` ranges over
+    // a word's 32 bits, `acc` reaccumulates the original word (so the
+    // decomposition is checked faithful), `acc_out` accumulates the
+    // permuted value using per-row fixed weights
+    q_bits: Selector,
+    bit: Column<Advice>,
+    acc: Column<Advice>,
+    acc_out: Column<Advice>,
+    w_orig: Column<Fixed>,
+    w_out: Column<Fixed>,
+
+    // in0 + in1 + in2 + in3 + in4 = out + dense * 2^32, mod-2^32 addition of
+    // up to 5 words at once; the carry is range-checked via `dense`/`tag`
+    q_add: Selector,
+    add_in: [Column<Advice>; 5],
+    add_out: Column<Advice>,
+
+    // word = const: pins a circuit-level literal (a round constant, the
+    // IV) to its actual value, rather than leaving it a free witness a
+    // prover could swap out for anything
+    q_const: Selector,
+    const_col: Column<Fixed>,
+}
+
+impl<F: PrimeField> Sha256Chip<F> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let table = SpreadTableChip::configure(meta);
+
+        let q_lookup = meta.complex_selector();
+        let tag = meta.fixed_column();
+        let dense = meta.advice_column();
+        let spread = meta.advice_column();
+        meta.enable_equality(dense);
+        meta.enable_equality(spread);
+
+        meta.lookup("spread/carry", |meta| {
+            let q = meta.query_selector(q_lookup);
+            let tag = meta.query_fixed(tag, Rotation::cur());
+            let dense = meta.query_advice(dense, Rotation::cur());
+            let spread = meta.query_advice(spread, Rotation::cur());
+            vec![
+                (q.clone() * tag, table.tag),
+                (q.clone() * dense, table.dense),
+                (q * spread, table.spread),
+            ]
+        });
+
+        let q_scale = meta.selector();
+        let word = meta.advice_column();
+        let hi = meta.advice_column();
+        let lo = meta.advice_column();
+        let scale = meta.fixed_column();
+        meta.enable_equality(word);
+        meta.enable_equality(hi);
+        meta.enable_equality(lo);
+
+        meta.create_gate("scale compose", |meta| {
+            let q = meta.query_selector(q_scale);
+            let word = meta.query_advice(word, Rotation::cur());
+            let hi = meta.query_advice(hi, Rotation::cur());
+            let lo = meta.query_advice(lo, Rotation::cur());
+            let scale = meta.query_fixed(scale, Rotation::cur());
+            vec![q * (word - (hi * scale + lo))]
+        });
+
+        let q_unspread = meta.selector();
+        let t0 = meta.advice_column();
+        let t1 = meta.advice_column();
+        let t2 = meta.advice_column();
+        let even = meta.advice_column();
+        let odd = meta.advice_column();
+        for c in [t0, t1, t2, even, odd] {
+            meta.enable_equality(c);
+        }
+
+        meta.create_gate("unspread", |meta| {
+            let q = meta.query_selector(q_unspread);
+            let t0 = meta.query_advice(t0, Rotation::cur());
+            let t1 = meta.query_advice(t1, Rotation::cur());
+            let t2 = meta.query_advice(t2, Rotation::cur());
+            let even = meta.query_advice(even, Rotation::cur());
+            let odd = meta.query_advice(odd, Rotation::cur());
+            vec![q * (t0 + t1 + t2 - even - odd * Expression::Constant(F::from(2)))]
+        });
+
+        let q_bits = meta.selector();
+        let bit = meta.advice_column();
+        let acc = meta.advice_column();
+        let acc_out = meta.advice_column();
+        let w_orig = meta.fixed_column();
+        let w_out = meta.fixed_column();
+        meta.enable_equality(acc);
+        meta.enable_equality(acc_out);
+
+        meta.create_gate("bit decompose", |meta| {
+            let q = meta.query_selector(q_bits);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            let acc_out_cur = meta.query_advice(acc_out, Rotation::cur());
+            let acc_out_next = meta.query_advice(acc_out, Rotation::next());
+            let w_orig = meta.query_fixed(w_orig, Rotation::cur());
+            let w_out = meta.query_fixed(w_out, Rotation::cur());
+
+            vec![
+                q.clone() * bit.clone() * (Expression::Constant(F::ONE) - bit.clone()),
+                q.clone() * (acc_next - acc_cur - bit.clone() * w_orig),
+                q * (acc_out_next - acc_out_cur - bit * w_out),
+            ]
+        });
+
+        let q_add = meta.selector();
+        let add_in = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+        let add_out = meta.advice_column();
+        for c in add_in {
+            meta.enable_equality(c);
+        }
+        meta.enable_equality(add_out);
+
+        meta.create_gate("add mod 2^32", |meta| {
+            let q = meta.query_selector(q_add);
+            let sum = add_in
+                .iter()
+                .map(|c| meta.query_advice(*c, Rotation::cur()))
+                .fold(Expression::Constant(F::ZERO), |acc, x| acc + x);
+            let out = meta.query_advice(add_out, Rotation::cur());
+            let carry = meta.query_advice(dense, Rotation::cur());
+            vec![q * (sum - out - carry * Expression::Constant(F::from(1u64 << 32)))]
+        });
+
+        let q_const = meta.selector();
+        let const_col = meta.fixed_column();
+        meta.create_gate("constant", |meta| {
+            let q = meta.query_selector(q_const);
+            let word = meta.query_advice(word, Rotation::cur());
+            let constant = meta.query_fixed(const_col, Rotation::cur());
+            vec![q * (word - constant)]
+        });
+
+        Sha256Chip {
+            table,
+            q_lookup,
+            tag,
+            dense,
+            spread,
+            q_scale,
+            word,
+            hi,
+            lo,
+            scale,
+            q_unspread,
+            t0,
+            t1,
+            t2,
+            even,
+            odd,
+            q_bits,
+            bit,
+            acc,
+            acc_out,
+            w_orig,
+            w_out,
+            q_add,
+            add_in,
+            add_out,
+            q_const,
+            const_col,
+        }
+    }
+
+    fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.table.load(layouter)
+    }
+
+    // loads a witness-supplied word and range-checks it into [0, 2^32) -
+    // for circuit *inputs* (the message block),
+    // not for literals the circuit itself relies on being correct (use
+    // `constant` for those).
+    fn free(&self, layouter: &mut impl Layouter<F>, value: Value<u32>) -> Result<Word32<F>, Error> {
+        let out = layouter.assign_region(
+            || "free word",
+            |mut region| {
+                let cell = region.assign_advice(
+                    || "word",
+                    self.word,
+                    0,
+                    || value.map(|v| F::from(v as u64)),
+                )?;
+                Ok(Word32 { value, cell })
+            },
+        )?;
+
+        self.range_check(layouter, &out)?;
+        Ok(out)
+    }
+
+    // loads a literal pinned to its actual value via a fixed column, for
+    // constants the circuit itself depends on (the round constants and the
+    // IV here) -
+    // unlike `free`, a prover can't substitute a different word here
+    fn constant(&self, layouter: &mut impl Layouter<F>, value: u32) -> Result<Word32<F>, Error> {
+        layouter.assign_region(
+            || "constant word",
+            |mut region| {
+                self.q_const.enable(&mut region, 0)?;
+                region.assign_fixed(
+                    || "const",
+                    self.const_col,
+                    0,
+                    || Value::known(F::from(value as u64)),
+                )?;
+                let cell = region.assign_advice(
+                    || "word",
+                    self.word,
+                    0,
+                    || Value::known(F::from(value as u64)),
+                )?;
+                Ok(Word32 { value: Value::known(value), cell })
+            },
+        )
+    }
+
+    // word = hi * 2^16 + lo; `hi`/`lo` come back as plain dense 16-bit
+    // values usable as `dense` inputs to `spread_lookup`
+    fn halves(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        word: &Word32<F>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        layouter.assign_region(
+            || "halves",
+            |mut region| {
+                self.q_scale.enable(&mut region, 0)?;
+                region.assign_fixed(|| "scale", self.scale, 0, || Value::known(F::from(1u64 << 16)))?;
+                word.cell.copy_advice(|| "word", &mut region, self.word, 0)?;
+
+                let hi_val = word.value.map(|v| v >> 16);
+                let lo_val = word.value.map(|v| v & 0xffff);
+                let hi_cell = region.assign_advice(
+                    || "hi",
+                    self.hi,
+                    0,
+                    || hi_val.map(|v| F::from(v as u64)),
+                )?;
+                let lo_cell = region.assign_advice(
+                    || "lo",
+                    self.lo,
+                    0,
+                    || lo_val.map(|v| F::from(v as u64)),
+                )?;
+                Ok((hi_cell, lo_cell))
+            },
+        )
+    }
+
+    // inverse of `halves`: combine a 16-bit hi/lo pair (already proven
+    // in-range by wherever they came from) back into a 32-bit word
+    fn compose(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        hi: &AssignedCell<F, F>,
+        lo: &AssignedCell<F, F>,
+        hi_val: Value<u32>,
+        lo_val: Value<u32>,
+    ) -> Result<Word32<F>, Error> {
+        layouter.assign_region(
+            || "compose",
+            |mut region| {
+                self.q_scale.enable(&mut region, 0)?;
+                region.assign_fixed(|| "scale", self.scale, 0, || Value::known(F::from(1u64 << 16)))?;
+                hi.copy_advice(|| "hi", &mut region, self.hi, 0)?;
+                lo.copy_advice(|| "lo", &mut region, self.lo, 0)?;
+
+                let value = hi_val.zip(lo_val).map(|(hi, lo)| (hi << 16) | lo);
+                let cell = region.assign_advice(
+                    || "word",
+                    self.word,
+                    0,
+                    || value.map(|v| F::from(v as u64)),
+                )?;
+                Ok(Word32 { value, cell })
+            },
+        )
+    }
+
+    // forces `word` into [0, 2^32) by splitting it into 16-bit halves (
+    // `halves` alone doesn't range-check anything) and running each half
+    // through the spread lookup, whose table only has rows for dense
+    // values 0..2^16
+    fn range_check(&self, layouter: &mut impl Layouter<F>, word: &Word32<F>) -> Result<(), Error> {
+        let (hi, lo) = self.halves(layouter, word)?;
+        let hi_val = word.value.map(|v| v >> 16);
+        let lo_val = word.value.map(|v| v & 0xffff);
+        self.spread_lookup(layouter, &hi, hi_val)?;
+        self.spread_lookup(layouter, &lo, lo_val)?;
+        Ok(())
+    }
+
+    // looks up the spread of a 16-bit dense value already sitting in a cell
+    // (ranges the value to 0..2^16 as a side effect of the lookup)
+    fn spread_lookup(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        dense_cell: &AssignedCell<F, F>,
+        dense_val: Value<u32>,
+    ) -> Result<(AssignedCell<F, F>, Value<u32>), Error> {
+        layouter.assign_region(
+            || "spread lookup",
+            |mut region| {
+                self.q_lookup.enable(&mut region, 0)?;
+                region.assign_fixed(|| "tag", self.tag, 0, || Value::known(F::from(TAG_SPREAD16)))?;
+                dense_cell.copy_advice(|| "dense", &mut region, self.dense, 0)?;
+
+                let spread_val = dense_val.map(|v| spread16(v as u16));
+                let cell = region.assign_advice(
+                    || "spread",
+                    self.spread,
+                    0,
+                    || spread_val.map(|v| F::from(v as u64)),
+                )?;
+                Ok((cell, spread_val))
+            },
+        )
+    }
+
+    // one 16-bit lane of the unspread identity: given up to 3 spread
+    // operands (unused ones passed as `None`), returns the XOR and the
+    // carry (AND for 2 operands, Maj for 3) of the corresponding dense
+    // 16-bit values
+    fn unspread_lane(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        terms: [Option<&AssignedCell<F, F>>; 3],
+        xor_val: Value<u16>,
+        carry_val: Value<u16>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let xor_dense = layouter.assign_region(
+            || "unspread dense (xor)",
+            |mut region| {
+                region.assign_advice(|| "xor", self.word, 0, || xor_val.map(|v| F::from(v as u64)))
+            },
+        )?;
+        let carry_dense = layouter.assign_region(
+            || "unspread dense (carry)",
+            |mut region| {
+                region.assign_advice(|| "carry", self.word, 0, || carry_val.map(|v| F::from(v as u64)))
+            },
+        )?;
+
+        let (s_xor, _) = self.spread_lookup(layouter, &xor_dense, xor_val.map(|v| v as u32))?;
+        let (s_carry, _) = self.spread_lookup(layouter, &carry_dense, carry_val.map(|v| v as u32))?;
+
+        let cols = [self.t0, self.t1, self.t2];
+        layouter.assign_region(
+            || "unspread",
+            |mut region| {
+                self.q_unspread.enable(&mut region, 0)?;
+                for (col, term) in cols.iter().zip(terms.iter()) {
+                    match term {
+                        Some(cell) => {
+                            cell.copy_advice(|| "term", &mut region, *col, 0)?;
+                        }
+                        None => {
+                            region.assign_advice(|| "term", *col, 0, || Value::known(F::ZERO))?;
+                        }
+                    }
+                }
+                s_xor.copy_advice(|| "even", &mut region, self.even, 0)?;
+                s_carry.copy_advice(|| "odd", &mut region, self.odd, 0)?;
+                Ok(())
+            },
+        )?;
+
+        Ok((xor_dense, carry_dense))
+    }
+
+    // general N-of-{2,3} word combiner: splits each word into halves,
+    // proves the per-lane XOR/carry identity on each half, and recomposes
+    // the two 16-bit results back into 32-bit words
+    fn combine(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        words: &[&Word32<F>],
+        xor_fn: impl Fn(&[u32]) -> u32,
+        carry_fn: impl Fn(&[u32]) -> u32,
+    ) -> Result<(Word32<F>, Word32<F>), Error> {
+        assert!(words.len() == 2 || words.len() == 3, "combine takes 2 or 3 words");
+
+        let halves: Vec<_> = words
+            .iter()
+            .map(|w| {
+                let (hi, lo) = self.halves(layouter, w)?;
+                let hi_val = w.value.map(|v| v >> 16);
+                let lo_val = w.value.map(|v| v & 0xffff);
+                Ok::<_, Error>((hi, hi_val, lo, lo_val))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let mut do_lane = |which_hi: bool| -> Result<(AssignedCell<F, F>, AssignedCell<F, F>, Value<u32>, Value<u32>), Error> {
+            let mut spread_cells = Vec::new();
+            let mut lane_vals = Vec::new();
+            for (hi, hi_val, lo, lo_val) in &halves {
+                let (cell, _) = if which_hi {
+                    self.spread_lookup(layouter, hi, *hi_val)?
+                } else {
+                    self.spread_lookup(layouter, lo, *lo_val)?
+                };
+                spread_cells.push(cell);
+                lane_vals.push(if which_hi { *hi_val } else { *lo_val });
+            }
+
+            let lane_vals_known: Value<Vec<u32>> = lane_vals.into_iter().collect();
+            let xor_val = lane_vals_known.clone().map(|vs| xor_fn(&vs) as u16);
+            let carry_val = lane_vals_known.map(|vs| carry_fn(&vs) as u16);
+
+            let mut terms: [Option<&AssignedCell<F, F>>; 3] = [None, None, None];
+            for (slot, cell) in terms.iter_mut().zip(spread_cells.iter()) {
+                *slot = Some(cell);
+            }
+
+            let (xor_dense, carry_dense) = self.unspread_lane(layouter, terms, xor_val, carry_val)?;
+            Ok((xor_dense, carry_dense, xor_val.map(|v| v as u32), carry_val.map(|v| v as u32)))
+        };
+
+        let (xor_lo, carry_lo, xor_lo_val, carry_lo_val) = do_lane(false)?;
+        let (xor_hi, carry_hi, xor_hi_val, carry_hi_val) = do_lane(true)?;
+
+        let xor_word = self.compose(layouter, &xor_hi, &xor_lo, xor_hi_val, xor_lo_val)?;
+        let carry_word = self.compose(layouter, &carry_hi, &carry_lo, carry_hi_val, carry_lo_val)?;
+
+        Ok((xor_word, carry_word))
+    }
+
+    fn xor2(&self, layouter: &mut impl Layouter<F>, a: &Word32<F>, b: &Word32<F>) -> Result<Word32<F>, Error> {
+        let (xor, _and) = self.combine(
+            layouter,
+            &[a, b],
+            |vs| vs[0] ^ vs[1],
+            |vs| vs[0] & vs[1],
+        )?;
+        Ok(xor)
+    }
+
+    fn and2(&self, layouter: &mut impl Layouter<F>, a: &Word32<F>, b: &Word32<F>) -> Result<Word32<F>, Error> {
+        let (_xor, and) = self.combine(
+            layouter,
+            &[a, b],
+            |vs| vs[0] ^ vs[1],
+            |vs| vs[0] & vs[1],
+        )?;
+        Ok(and)
+    }
+
+    fn maj(&self, layouter: &mut impl Layouter<F>, a: &Word32<F>, b: &Word32<F>, c: &Word32<F>) -> Result<Word32<F>, Error> {
+        let (_xor, maj) = self.combine(
+            layouter,
+            &[a, b, c],
+            |vs| vs[0] ^ vs[1] ^ vs[2],
+            |vs| (vs[0] & vs[1]) | (vs[1] & vs[2]) | (vs[0] & vs[2]),
+        )?;
+        Ok(maj)
+    }
+
+    // Ch(e, f, g) = g ^ (e & (f ^ g)), built entirely from `xor2`/`and2` so
+    // it stays on the spread-table path despite not being symmetric like
+    // Maj
+    fn ch(&self, layouter: &mut impl Layouter<F>, e: &Word32<F>, f: &Word32<F>, g: &Word32<F>) -> Result<Word32<F>, Error> {
+        let f_xor_g = self.xor2(layouter, f, g)?;
+        let e_and_h = self.and2(layouter, e, &f_xor_g)?;
+        self.xor2(layouter, g, &e_and_h)
+    }
+
+    // bit-by-bit decompose `word` and recombine its bits under `weight`,
+    // which maps each original bit index to the bit index it lands on in
+    // the output (or `None` to drop it, for shifts)
+    fn recombine(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        word: &Word32<F>,
+        weight: impl Fn(u32) -> Option<u32> + Copy,
+    ) -> Result<Word32<F>, Error> {
+        // both running sums start from this pinned zero, not a free witness
+        let zero = self.constant(layouter, 0)?;
+
+        let new_value = word.value.map(|v| {
+            let mut out = 0u32;
+            for i in 0..32u32 {
+                if (v >> i) & 1 == 1 {
+                    if let Some(j) = weight(i) {
+                        out |= 1 << j;
+                    }
+                }
+            }
+            out
+        });
+
+        layouter.assign_region(
+            || "bit recombine",
+            |mut region: Region<'_, F>| {
+                let mut acc = region.assign_advice(|| "acc0", self.acc, 0, || Value::known(F::ZERO))?;
+                let mut acc_out =
+                    region.assign_advice(|| "acc_out0", self.acc_out, 0, || Value::known(F::ZERO))?;
+                region.constrain_equal(acc.cell(), zero.cell.cell())?;
+                region.constrain_equal(acc_out.cell(), zero.cell.cell())?;
+
+                for i in 0..32u32 {
+                    self.q_bits.enable(&mut region, i as usize)?;
+
+                    let bit_val = word.value.map(|v| F::from(((v >> i) & 1) as u64));
+                    region.assign_advice(|| "bit", self.bit, i as usize, || bit_val)?;
+
+                    let w_orig = pow2::<F>(i);
+                    let w_out = weight(i).map(pow2::<F>).unwrap_or(F::ZERO);
+                    region.assign_fixed(|| "w_orig", self.w_orig, i as usize, || Value::known(w_orig))?;
+                    region.assign_fixed(|| "w_out", self.w_out, i as usize, || Value::known(w_out))?;
+
+                    let next_acc = acc.value().copied().zip(bit_val).map(|(a, b)| a + b * w_orig);
+                    let next_acc_out =
+                        acc_out.value().copied().zip(bit_val).map(|(a, b)| a + b * w_out);
+
+                    acc = region.assign_advice(|| "acc", self.acc, i as usize + 1, || next_acc)?;
+                    acc_out =
+                        region.assign_advice(|| "acc_out", self.acc_out, i as usize + 1, || next_acc_out)?;
+                }
+
+                region.constrain_equal(acc.cell(), word.cell.cell())?;
+
+                Ok(Word32 {
+                    value: new_value,
+                    cell: acc_out,
+                })
+            },
+        )
+    }
+
+    fn rotr(&self, layouter: &mut impl Layouter<F>, word: &Word32<F>, amt: u32) -> Result<Word32<F>, Error> {
+        let amt = amt % 32;
+        self.recombine(layouter, word, move |i| Some((i + 32 - amt) % 32))
+    }
+
+    fn shr(&self, layouter: &mut impl Layouter<F>, word: &Word32<F>, amt: u32) -> Result<Word32<F>, Error> {
+        self.recombine(layouter, word, move |i| if i >= amt { Some(i - amt) } else { None })
+    }
+
+    // sums up to 5 words mod 2^32
+    fn add_mod32(&self, layouter: &mut impl Layouter<F>, words: &[&Word32<F>]) -> Result<Word32<F>, Error> {
+        assert!(!words.is_empty() && words.len() <= 5, "add_mod32 takes 1..=5 words");
+
+        let out = layouter.assign_region(
+            || "add mod 2^32",
+            |mut region| {
+                self.q_add.enable(&mut region, 0)?;
+
+                for (col, w) in self.add_in.iter().zip(words.iter()) {
+                    w.cell.copy_advice(|| "addend", &mut region, *col, 0)?;
+                }
+                for col in self.add_in.iter().skip(words.len()) {
+                    region.assign_advice(|| "zero", *col, 0, || Value::known(F::ZERO))?;
+                }
+
+                let sum: Value<u64> = words
+                    .iter()
+                    .map(|w| w.value)
+                    .fold(Value::known(0u64), |acc, v| acc.zip(v).map(|(acc, v)| acc + v as u64));
+                let carry = sum.map(|s| s >> 32);
+                let rem = sum.map(|s| (s & 0xffff_ffff) as u32);
+
+                self.q_lookup.enable(&mut region, 0)?;
+                region.assign_fixed(|| "tag", self.tag, 0, || Value::known(F::from(TAG_CARRY)))?;
+                region.assign_advice(|| "carry", self.dense, 0, || carry.map(F::from))?;
+                region.assign_advice(|| "carry spread (unused)", self.spread, 0, || Value::known(F::ZERO))?;
+
+                let cell = region.assign_advice(|| "sum", self.add_out, 0, || rem.map(|v| F::from(v as u64)))?;
+                Ok(Word32 { value: rem, cell })
+            },
+        )?;
+
+        self.range_check(layouter, &out)?;
+        Ok(out)
+    }
+
+    fn big_sigma0(&self, layouter: &mut impl Layouter<F>, a: &Word32<F>) -> Result<Word32<F>, Error> {
+        let r2 = self.rotr(layouter, a, 2)?;
+        let r13 = self.rotr(layouter, a, 13)?;
+        let r22 = self.rotr(layouter, a, 22)?;
+        let t = self.xor2(layouter, &r2, &r13)?;
+        self.xor2(layouter, &t, &r22)
+    }
+
+    fn big_sigma1(&self, layouter: &mut impl Layouter<F>, e: &Word32<F>) -> Result<Word32<F>, Error> {
+        let r6 = self.rotr(layouter, e, 6)?;
+        let r11 = self.rotr(layouter, e, 11)?;
+        let r25 = self.rotr(layouter, e, 25)?;
+        let t = self.xor2(layouter, &r6, &r11)?;
+        self.xor2(layouter, &t, &r25)
+    }
+
+    fn small_sigma0(&self, layouter: &mut impl Layouter<F>, x: &Word32<F>) -> Result<Word32<F>, Error> {
+        let r7 = self.rotr(layouter, x, 7)?;
+        let r18 = self.rotr(layouter, x, 18)?;
+        let s3 = self.shr(layouter, x, 3)?;
+        let t = self.xor2(layouter, &r7, &r18)?;
+        self.xor2(layouter, &t, &s3)
+    }
+
+    fn small_sigma1(&self, layouter: &mut impl Layouter<F>, x: &Word32<F>) -> Result<Word32<F>, Error> {
+        let r17 = self.rotr(layouter, x, 17)?;
+        let r19 = self.rotr(layouter, x, 19)?;
+        let s10 = self.shr(layouter, x, 10)?;
+        let t = self.xor2(layouter, &r17, &r19)?;
+        self.xor2(layouter, &t, &s10)
+    }
+}
+
+const SHA256_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+// ANCHOR: sha256_chip
+// Builds the message schedule and runs the 64-round compression function on
+// a single 512-bit block, entirely in terms of the gadgets above.
+struct Sha256BlockChip<F: Field> {
+    inner: Sha256Chip<F>,
+}
+
+impl<F: PrimeField> Sha256BlockChip<F> {
+    fn message_schedule(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        block: [Word32<F>; 16],
+    ) -> Result<[Word32<F>; 64], Error> {
+        let mut w: Vec<Word32<F>> = block.into_iter().collect();
+        for i in 16..64 {
+            let s0 = self.inner.small_sigma0(layouter, &w[i - 15])?;
+            let s1 = self.inner.small_sigma1(layouter, &w[i - 2])?;
+            let next = self
+                .inner
+                .add_mod32(layouter, &[&s1, &w[i - 7], &s0, &w[i - 16]])?;
+            w.push(next);
+        }
+        w.try_into().map_err(|_| Error::Synthesis)
+    }
+
+    fn round(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        state: [Word32<F>; 8],
+        k: u32,
+        w: &Word32<F>,
+    ) -> Result<[Word32<F>; 8], Error> {
+        let [a, b, c, d, e, f, g, h] = state;
+
+        let big_s1 = self.inner.big_sigma1(layouter, &e)?;
+        let ch = self.inner.ch(layouter, &e, &f, &g)?;
+        let k_word = self.inner.constant(layouter, k)?;
+        let t1 = self
+            .inner
+            .add_mod32(layouter, &[&h, &big_s1, &ch, &k_word, w])?;
+
+        let big_s0 = self.inner.big_sigma0(layouter, &a)?;
+        let maj = self.inner.maj(layouter, &a, &b, &c)?;
+        let t2 = self.inner.add_mod32(layouter, &[&big_s0, &maj])?;
+
+        let new_e = self.inner.add_mod32(layouter, &[&d, &t1])?;
+        let new_a = self.inner.add_mod32(layouter, &[&t1, &t2])?;
+
+        Ok([new_a, a, b, c, new_e, e, f, g])
+    }
+
+    fn compress(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        h_in: [Word32<F>; 8],
+        block: [Word32<F>; 16],
+    ) -> Result<[Word32<F>; 8], Error> {
+        let w = self.message_schedule(layouter, block)?;
+
+        let mut state: [Word32<F>; 8] = h_in.clone();
+        for i in 0..64 {
+            state = self.round(layouter, state, SHA256_K[i], &w[i])?;
+        }
+
+        let mut out = Vec::with_capacity(8);
+        for (h, s) in h_in.into_iter().zip(state.into_iter()) {
+            out.push(self.inner.add_mod32(layouter, &[&h, &s])?);
+        }
+        out.try_into().map_err(|_| Error::Synthesis)
+    }
+}
+// ANCHOR_END: sha256_chip
+
+#[derive(Clone, Debug)]
+struct TestConfig<F: Field + Clone> {
+    sha: Sha256Chip<F>,
+    expected: Column<Fixed>,
+    q_expect: Selector,
+}
+
+struct TestCircuit<F: Field> {
+    // the single 512-bit block for "abc", padded per the SHA-256 spec
+    block: [u32; 16],
+    // the 8 expected output words, as a fixed public test vector
+    digest: [u32; 8],
+    _ph: PhantomData<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
+    type Config = TestConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        TestCircuit {
+            block: self.block,
+            digest: self.digest,
+            _ph: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let sha = Sha256Chip::configure(meta);
+
+        let q_expect = meta.selector();
+        let expected = meta.fixed_column();
+        meta.create_gate("equals expected digest word", |meta| {
+            let q = meta.query_selector(q_expect);
+            let word = meta.query_advice(sha.word, Rotation::cur());
+            let expected = meta.query_fixed(expected, Rotation::cur());
+            vec![q * (word - expected)]
+        });
+
+        TestConfig { sha, expected, q_expect }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        config.sha.load_table(&mut layouter)?;
+
+        let block: [Word32<F>; 16] = {
+            let mut words = Vec::with_capacity(16);
+            for &w in &self.block {
+                words.push(config.sha.free(&mut layouter, Value::known(w))?);
+            }
+            words.try_into().map_err(|_| Error::Synthesis)?
+        };
+
+        let iv: [Word32<F>; 8] = {
+            let mut words = Vec::with_capacity(8);
+            for &h in &SHA256_IV {
+                words.push(config.sha.constant(&mut layouter, h)?);
+            }
+            words.try_into().map_err(|_| Error::Synthesis)?
+        };
+
+        let block_chip = Sha256BlockChip { inner: config.sha.clone() };
+        let digest = block_chip.compress(&mut layouter, iv, block)?;
+
+        for (word, &expected) in digest.into_iter().zip(self.digest.iter()) {
+            layouter.assign_region(
+                || "check digest word",
+                |mut region| {
+                    config.q_expect.enable(&mut region, 0)?;
+                    word.cell.copy_advice(|| "word", &mut region, config.sha.word, 0)?;
+                    region.assign_fixed(
+                        || "expected",
+                        config.expected,
+                        0,
+                        || Value::known(F::from(expected as u64)),
+                    )?;
+                    Ok(())
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn main() {
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    // SHA-256("abc"), padded to a single 512-bit block: the message "abc",
+    // a 0x80 terminator bit, zero padding, and the 64-bit big-endian bit
+    // length (24) in the last word.
+    let block: [u32; 16] = [
+        0x61626380, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+        0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+        0x00000000, 0x00000018,
+    ];
+
+    // the well-known SHA-256("abc") digest
+    let digest: [u32; 8] = [
+        0xba7816bf, 0x8f01cfea, 0x414140de, 0x5dae2223, 0xb00361a3, 0x96177a9c, 0xb410ff61,
+        0xf20015ad,
+    ];
+
+    let circuit = TestCircuit::<Fr> {
+        block,
+        digest,
+        _ph: PhantomData,
+    };
+
+    // the spread table alone needs 2^16 + CARRY_RANGE rows, so k = 17 is
+    // the floor regardless of how much of the rest of the circuit is used
+    let prover = MockProver::run(17, &circuit, vec![]).unwrap();
+    prover.verify().unwrap();
+}
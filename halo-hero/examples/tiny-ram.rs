@@ -1,7 +1,7 @@
-use std::marker::PhantomData;
+use std::{cell::RefCell, collections::HashMap, marker::PhantomData};
 
 use halo2_proofs::{
-    circuit::{Layouter, SimpleFloorPlanner, Value},
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
     dev::MockProver,
     plonk::{
         Advice,
@@ -9,6 +9,7 @@ use halo2_proofs::{
         Column, //
         ConstraintSystem,
         Error,
+        Expression,
         Fixed,
         Selector,
         TableColumn,
@@ -20,47 +21,642 @@ use ff::{Field, PrimeField};
 
 // ANCHOR: rwtable
 struct RwTable {
-    addr: Column<Advice>,    // address
-    value: Column<Advice>,   // value
-    counter: Column<Advice>, // counter
+    addr: Column<Advice>,     // address
+    value: Column<Advice>,    // value
+    counter: Column<Advice>,  // global access counter, unique and monotone in issue order
+    is_write: Column<Advice>, // 1 for a write, 0 for a read
 }
 // ANCHOR_END: rwtable
 
-// ANCHOR: regex
-const ST_A: usize = 1;
-const ST_B: usize = 2;
-const ST_C: usize = 3;
+// ANCHOR: memory_chip
+// Turns the unused `RwTable` into a full read/write memory using the
+// classic "offline memory checking" trick, following session-5.rs's
+// `RamChip` but with a native shuffle (the `s_shuffle`/`s_stable`
+// fixed-column-selector pattern `DynamicLookupChip::configure_shuffle`
+// builds in session-4.rs) standing in for its pair of `lookup_any`s: the
+// prover commits to the access trace in issue order (`unsorted`) *and*
+// the same accesses sorted by `(addr, counter)` (`sorted`), the shuffle
+// proves `sorted` is a permutation of `unsorted`, and a pair of gates
+// walks `sorted` row by row to enforce memory consistency:
+//   - within a run of equal `addr`, `counter` must *strictly increase*
+//     from the previous row, proven (the same way session-5.rs's
+//     `RamChip` proves its timestamp column strictly increases) by a
+//     range-checked lookup of `counter_cur - counter_prev - 1` against a
+//     `0..MAX_COUNTER_DELTA` table, rather than merely a witnessed
+//     inverse proving the two differ;
+//   - same address, a read (`is_write = 0`): the row must repeat the
+//     previous row's `value`;
+//   - address changed: the first access to it must be a write, or a read
+//     of zero (there's no separate "declared initial value" table, so
+//     reading never-written memory is only sound when it reads as zero).
+//
+// This all relies on `counter` being unique and strictly increasing in
+// *issue* order: `MemoryChip` hands out `0, 1, 2, ...` from `next_counter`
+// and never reuses one, so `(addr, counter)` is a total order on
+// accesses and the "sorted by `(addr, counter)`" trace the shuffle
+// checks against is uniquely determined. That's not just host-side
+// bookkeeping: each `op` call's region pins `unsorted.counter` against a
+// fixed `counter_index` column holding the call's position in issue
+// order, so a prover who doesn't go through this API can't claim a
+// different (and possibly non-monotonic) counter for that row -- the
+// fixed column is baked into the verifying key, so it can't be changed
+// between keygen and proving.
 
-// start and done states
-const ST_START: usize = ST_A;
-const ST_DONE: usize = 4;
+// Upper bound on how far apart two same-address counters in the sorted
+// trace may be; `MockProver::run`'s `k` for `MemoryDemoCircuit` must give
+// the table at least this many rows.
+const MAX_COUNTER_DELTA: u64 = 64;
 
-// end of file marker:
-// "dummy padding character"
+#[derive(Clone, Debug)]
+struct MemoryChip<F: Field> {
+    unsorted: RwTable,
+    sorted: RwTable,
+    same_addr: Column<Advice>,
+    // holds every value in `0..MAX_COUNTER_DELTA`, used to range-check
+    // `sorted.counter_cur - sorted.counter_prev - 1`, i.e. that the
+    // counter genuinely strictly increases rather than merely differs
+    counter_delta_table: TableColumn,
+    // fixed per-row issue-order index, pinned equal to `unsorted.counter`
+    // so the counter can't be an arbitrary prover-chosen witness
+    counter_index: Column<Fixed>,
+    s_shuffle: Selector,
+    s_stable: Selector,
+    q_first: Selector,
+    q_transition: Selector,
+
+    // one entry per access in issue order: (addr, value, counter,
+    // is_write). Drained by `finalize`, which derives the sorted side
+    // and can only run once every access has been witnessed.
+    #[allow(clippy::type_complexity)]
+    accesses: RefCell<Vec<(u64, Value<F>, u64, bool)>>,
+    next_counter: RefCell<u64>,
+    // current contents, so `read` can return the last value written --
+    // this is host-side bookkeeping only, not part of the circuit
+    mem: RefCell<HashMap<u64, Value<F>>>,
+}
+
+impl<F: PrimeField> MemoryChip<F> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let new_rw_table = |meta: &mut ConstraintSystem<F>| RwTable {
+            addr: meta.advice_column(),
+            value: meta.advice_column(),
+            counter: meta.advice_column(),
+            is_write: meta.advice_column(),
+        };
+
+        let unsorted = new_rw_table(meta);
+        let sorted = new_rw_table(meta);
+        let same_addr = meta.advice_column();
+        let counter_delta_table = meta.lookup_table_column();
+        let counter_index = meta.fixed_column();
+
+        meta.enable_equality(unsorted.value);
+
+        let s_shuffle = meta.complex_selector();
+        let s_stable = meta.complex_selector();
+        let q_first = meta.complex_selector();
+        let q_transition = meta.complex_selector();
+
+        // `unsorted.counter` must equal the row's real position in issue
+        // order, not an arbitrary witness: `op` pins `counter_index` to
+        // that position every time it assigns a row
+        meta.create_gate("unsorted counter is real issue order", |meta| {
+            let s_shuffle = meta.query_selector(s_shuffle);
+            let counter = meta.query_advice(unsorted.counter, Rotation::cur());
+            let counter_index = meta.query_fixed(counter_index, Rotation::cur());
+            vec![s_shuffle * (counter - counter_index)]
+        });
+
+        // `unsorted` and `sorted` are the same multiset of
+        // (addr, value, counter, is_write) tuples
+        meta.shuffle("memory trace shuffle", |meta| {
+            let s_shuffle = meta.query_selector(s_shuffle);
+            let s_stable = meta.query_selector(s_stable);
+
+            let addr = meta.query_advice(unsorted.addr, Rotation::cur());
+            let value = meta.query_advice(unsorted.value, Rotation::cur());
+            let counter = meta.query_advice(unsorted.counter, Rotation::cur());
+            let is_write = meta.query_advice(unsorted.is_write, Rotation::cur());
+
+            let s_addr = meta.query_advice(sorted.addr, Rotation::cur());
+            let s_value = meta.query_advice(sorted.value, Rotation::cur());
+            let s_counter = meta.query_advice(sorted.counter, Rotation::cur());
+            let s_is_write = meta.query_advice(sorted.is_write, Rotation::cur());
+
+            vec![
+                (s_shuffle.clone() * addr, s_stable.clone() * s_addr),
+                (s_shuffle.clone() * value, s_stable.clone() * s_value),
+                (s_shuffle.clone() * counter, s_stable.clone() * s_counter),
+                (s_shuffle * is_write, s_stable * s_is_write),
+            ]
+        });
+
+        meta.create_gate("sorted trace booleans", |meta| {
+            let s_stable = meta.query_selector(s_stable);
+            let is_write = meta.query_advice(sorted.is_write, Rotation::cur());
+            let same = meta.query_advice(same_addr, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            vec![
+                s_stable.clone() * is_write.clone() * (one.clone() - is_write),
+                s_stable * same.clone() * (one - same),
+            ]
+        });
+
+        meta.create_gate(
+            "sorted trace first row is a write or a read of zero",
+            |meta| {
+                let q_first = meta.query_selector(q_first);
+                let is_write = meta.query_advice(sorted.is_write, Rotation::cur());
+                let value = meta.query_advice(sorted.value, Rotation::cur());
+                let one = Expression::Constant(F::ONE);
+                vec![q_first * (one - is_write) * value]
+            },
+        );
+
+        meta.create_gate("sorted trace transition", |meta| {
+            let q_transition = meta.query_selector(q_transition);
+
+            let addr_cur = meta.query_advice(sorted.addr, Rotation::cur());
+            let addr_prev = meta.query_advice(sorted.addr, Rotation::prev());
+            let value_cur = meta.query_advice(sorted.value, Rotation::cur());
+            let value_prev = meta.query_advice(sorted.value, Rotation::prev());
+            let is_write_cur = meta.query_advice(sorted.is_write, Rotation::cur());
+            let same = meta.query_advice(same_addr, Rotation::cur());
+
+            let one = Expression::Constant(F::ONE);
+
+            // `same` can only claim equality when the addresses truly match
+            let same_implies_equal_addr = same.clone() * (addr_cur - addr_prev);
+
+            // same address, read: value must repeat the previous row
+            let read_repeats_value =
+                same.clone() * (one.clone() - is_write_cur.clone()) * (value_cur - value_prev);
+
+            // address changed: the first access must be a write, or a
+            // read of zero (a write is unconstrained here regardless of
+            // `value_cur`; a read must find `value_cur = 0`)
+            let first_access_ok = (one.clone() - same) * (one - is_write_cur) * value_cur;
+
+            vec![
+                q_transition.clone() * same_implies_equal_addr,
+                q_transition.clone() * read_repeats_value,
+                q_transition * first_access_ok,
+            ]
+        });
+
+        // same address: the counter must *strictly* increase, not merely
+        // differ. `counter_cur - counter_prev - 1` is looked up against a
+        // table of `0..MAX_COUNTER_DELTA`, which only contains a valid
+        // entry when the difference is non-negative (and bounded), ruling
+        // out both a repeated counter and a decreasing one.
+        meta.lookup(
+            "sorted counter strictly increases on repeat address",
+            |meta| {
+                let q_transition = meta.query_selector(q_transition);
+                let same = meta.query_advice(same_addr, Rotation::cur());
+                let counter_cur = meta.query_advice(sorted.counter, Rotation::cur());
+                let counter_prev = meta.query_advice(sorted.counter, Rotation::prev());
+                let one = Expression::Constant(F::ONE);
+
+                vec![(
+                    q_transition * same * (counter_cur - counter_prev - one),
+                    counter_delta_table,
+                )]
+            },
+        );
+
+        Self {
+            unsorted,
+            sorted,
+            same_addr,
+            counter_delta_table,
+            counter_index,
+            s_shuffle,
+            s_stable,
+            q_first,
+            q_transition,
+            accesses: RefCell::new(Vec::new()),
+            next_counter: RefCell::new(0),
+            mem: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn op(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        addr: u64,
+        value: Value<F>,
+        is_write: bool,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let counter = {
+            let mut next = self.next_counter.borrow_mut();
+            let counter = *next;
+            *next += 1;
+            counter
+        };
+
+        let cell = layouter.assign_region(
+            || "memory access",
+            |mut region| {
+                self.s_shuffle.enable(&mut region, 0)?;
+                region.assign_fixed(
+                    || "counter index",
+                    self.counter_index,
+                    0,
+                    || Value::known(F::from(counter)),
+                )?;
+                region.assign_advice(
+                    || "addr",
+                    self.unsorted.addr,
+                    0,
+                    || Value::known(F::from(addr)),
+                )?;
+                let cell = region.assign_advice(|| "value", self.unsorted.value, 0, || value)?;
+                region.assign_advice(
+                    || "counter",
+                    self.unsorted.counter,
+                    0,
+                    || Value::known(F::from(counter)),
+                )?;
+                region.assign_advice(
+                    || "is_write",
+                    self.unsorted.is_write,
+                    0,
+                    || Value::known(if is_write { F::ONE } else { F::ZERO }),
+                )?;
+                Ok(cell)
+            },
+        )?;
+
+        self.mem.borrow_mut().insert(addr, value);
+        self.accesses
+            .borrow_mut()
+            .push((addr, value, counter, is_write));
+
+        Ok(cell)
+    }
+
+    fn write(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        addr: u64,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.op(layouter, addr, value, true)
+    }
+
+    // returns the last value written to `addr`, or zero if this is the
+    // first access to it -- the "first access is a write or a read of
+    // zero" gate is exactly what makes that zero sound
+    fn read(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        addr: u64,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let value = self
+            .mem
+            .borrow()
+            .get(&addr)
+            .copied()
+            .unwrap_or(Value::known(F::ZERO));
+        self.op(layouter, addr, value, false)
+    }
+
+    // Must be called once, after every `read`/`write` has been witnessed:
+    // sorts the recorded accesses by `(addr, counter)` and assigns the
+    // `sorted` side of the shuffle together with the consistency gates.
+    fn finalize(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        let mut sorted = self.accesses.take();
+        sorted.sort_by_key(|&(addr, _, counter, _)| (addr, counter));
+
+        // populate the fixed table with every value in `0..MAX_COUNTER_DELTA`,
+        // so the "sorted counter strictly increases on repeat address"
+        // lookup above has something to check against
+        layouter.assign_table(
+            || "counter delta range-check table",
+            |mut table| {
+                for i in 0..MAX_COUNTER_DELTA {
+                    table.assign_cell(
+                        || "counter-delta-table-value",
+                        self.counter_delta_table,
+                        i as usize,
+                        || Value::known(F::from(i)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+
+        layouter.assign_region(
+            || "memory trace sorted",
+            |mut region| {
+                for (i, &(addr, value, counter, is_write)) in sorted.iter().enumerate() {
+                    self.s_stable.enable(&mut region, i)?;
+                    region.assign_advice(
+                        || "sorted addr",
+                        self.sorted.addr,
+                        i,
+                        || Value::known(F::from(addr)),
+                    )?;
+                    region.assign_advice(|| "sorted value", self.sorted.value, i, || value)?;
+                    region.assign_advice(
+                        || "sorted counter",
+                        self.sorted.counter,
+                        i,
+                        || Value::known(F::from(counter)),
+                    )?;
+                    region.assign_advice(
+                        || "sorted is_write",
+                        self.sorted.is_write,
+                        i,
+                        || Value::known(if is_write { F::ONE } else { F::ZERO }),
+                    )?;
+
+                    if i == 0 {
+                        region.assign_advice(
+                            || "same addr",
+                            self.same_addr,
+                            i,
+                            || Value::known(F::ZERO),
+                        )?;
+                        self.q_first.enable(&mut region, i)?;
+                    } else {
+                        let (prev_addr, _, _, _) = sorted[i - 1];
+                        let same = addr == prev_addr;
+                        region.assign_advice(
+                            || "same addr",
+                            self.same_addr,
+                            i,
+                            || Value::known(if same { F::ONE } else { F::ZERO }),
+                        )?;
+
+                        self.q_transition.enable(&mut region, i)?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+// ANCHOR_END: memory_chip
+
+// end of file marker: "dummy padding character"
 const EOF: usize = 0xFFFF;
 
-// conversion of the regular expression: a+b+c
-const REGEX: [(usize, usize, Option<char>); 6] = [
-    (ST_A, ST_A, Some('a')),    // you can stay in ST_A by reading 'a'
-    (ST_A, ST_B, Some('a')),    // or move to ST_B by reading 'a'
-    (ST_B, ST_B, Some('b')),    // you can stay in ST_B by reading 'b'
-    (ST_B, ST_C, Some('b')),    // or move to ST_C by reading 'b'
-    (ST_C, ST_DONE, Some('c')), // you can move to ST_DONE by reading 'c'
-    (ST_DONE, ST_DONE, None),   // you can stay in ST_DONE by reading EOF
-];
-// ANCHOR_END: regex
+// ANCHOR: dfa
+// A tiny regex compiler: a pattern is a sequence of (char, one-or-more?)
+// groups, e.g. `a+b+c` compiles to [('a', true), ('b', true), ('c',
+// false)]. Each group owns a distinct state, and the only ambiguity -
+// staying in a group's state vs advancing out of it on the same
+// character - is resolved by the prover's witnessed trace (which always
+// maximizes repeats before advancing), not by the table itself, so no
+// NFA-to-DFA subset construction is needed for this fragment of regex.
+// That greedy, non-backtracking trace can't witness a `+` group
+// immediately followed by a literal group on the same character (e.g.
+// `a+a` matching `aa`), so `compile_regex` rejects such patterns
+// outright rather than silently compiling an automaton the trace can't
+// match against. Anything outside the fragment (alternation, character
+// classes, `*`/`?`, groups, …) is likewise rejected rather than silently
+// mis-compiled.
+#[derive(Clone, Debug)]
+struct Dfa {
+    groups: Vec<(char, bool)>, // (character, one-or-more)
+    transitions: Vec<(usize, usize, Option<char>)>,
+    start: usize,
+    done: usize,
+}
+
+impl Dfa {
+    // the state reached by advancing out of group `i` (groups are
+    // 0-indexed, states are 1-indexed: group `i`'s own state is `i + 1`)
+    fn next_state(&self, i: usize) -> usize {
+        if i + 1 == self.groups.len() {
+            self.done
+        } else {
+            i + 2
+        }
+    }
+}
+
+fn compile_regex(pattern: &str) -> Dfa {
+    let mut groups = Vec::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        assert!(
+            c.is_ascii_alphanumeric(),
+            "unsupported regex feature {:?} in {:?}: only literal characters with an optional \
+             trailing '+' are supported (no NFA-to-DFA subset construction is implemented)",
+            c,
+            pattern
+        );
+        let plus = chars.peek() == Some(&'+');
+        if plus {
+            chars.next();
+        }
+        groups.push((c, plus));
+    }
+    assert!(!groups.is_empty(), "regex pattern must not be empty");
+    for w in groups.windows(2) {
+        let (prev_c, prev_plus) = w[0];
+        let (next_c, _) = w[1];
+        assert!(
+            !(prev_plus && prev_c == next_c),
+            "ambiguous pattern {:?}: a '+' group immediately followed by a \
+             literal group on the same character ({:?}) has witness traces \
+             the greedy, non-backtracking trace() can't always find - split \
+             them apart or use a single group instead",
+            pattern,
+            prev_c
+        );
+    }
+
+    // states are 1-indexed per group (matching the original hand-built
+    // a+b+c automaton's ST_A=1/ST_B=2/ST_C=3 convention); state 0 is
+    // never used, so it doubles as the `q_regex = 0` padding key
+    let start = 1;
+    let done = groups.len() + 1;
+
+    let mut dfa = Dfa {
+        groups,
+        transitions: Vec::new(),
+        start,
+        done,
+    };
+
+    for i in 0..dfa.groups.len() {
+        let (c, plus) = dfa.groups[i];
+        let cur = i + 1;
+        let nxt = dfa.next_state(i);
+        if plus {
+            dfa.transitions.push((cur, cur, Some(c))); // stay: consume another c
+        }
+        dfa.transitions.push((cur, nxt, Some(c))); // advance to the next group
+    }
+    dfa.transitions.push((done, done, None)); // EOF self-loop once matched
+
+    dfa
+}
 
-const MAX_STR_LEN: usize = 20;
+// Derives the per-row state trace witnessing that `input` matches the
+// compiled pattern, following the same "stay until the last repeated
+// character, then advance" rule as the hand-written trace this replaces.
+// Returns `None` if `input` doesn't match.
+fn trace(dfa: &Dfa, input: &str) -> Option<Vec<usize>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut sts = vec![dfa.start];
+    let mut pos = 0;
+
+    for (i, &(c, plus)) in dfa.groups.iter().enumerate() {
+        let nxt = dfa.next_state(i);
+
+        if chars.get(pos) != Some(&c) {
+            return None; // every group needs at least one matching char
+        }
+        pos += 1;
+
+        if plus {
+            while chars.get(pos) == Some(&c) {
+                sts.push(i + 1); // stay: another repeat is coming
+                pos += 1;
+            }
+        }
+        sts.push(nxt); // advance out of this group
+    }
+
+    if pos != chars.len() {
+        return None; // leftover characters the pattern doesn't account for
+    }
+    Some(sts)
+}
+// ANCHOR_END: dfa
+
+// ANCHOR: precompute_steps
+// Number of rows handed to each worker thread when `multicore` is enabled.
+#[cfg(feature = "multicore")]
+const STEP_CHUNK_SIZE: usize = 1 << 10;
+
+// `Region` isn't `Send` (same constraint `instances.rs`'s `precompute_rows`
+// runs into for the Fibonacci circuit), so the stepping loop's
+// `assign_advice` calls still have to run on a single thread, in one
+// "regex" region -- the lookup's `Rotation::next()` needs `st`/`ch` to
+// stay row-adjacent within it. What a rayon thread pool *can* do is
+// derive the per-row `(st, ch)` witness values up front: split the
+// stepping range into independent chunks, fill each chunk's slice of the
+// output vectors concurrently, then hand the fully-materialized vectors
+// back to the single assigning thread. A real forked layouter -- running
+// *independent* per-match regions through a rayon pool, mirroring
+// upstream's `SingleChipLayouter` thread-pooled `assign_region` -- is the
+// natural next step this sets up for, but this circuit only ever
+// witnesses one match per proof, so there's only one region to fork.
+#[cfg(feature = "multicore")]
+fn precompute_steps<F: Field>(
+    sts: &Value<Vec<usize>>,
+    str: &Value<String>,
+    max_len: usize,
+    done: usize,
+) -> (Vec<Value<F>>, Vec<Value<F>>) {
+    use rayon::prelude::*;
+
+    let chars: Value<Vec<char>> = str.as_ref().map(|s| s.chars().collect());
+
+    let mut st_vals = vec![Value::unknown(); max_len];
+    let mut ch_vals = vec![Value::unknown(); max_len];
+
+    st_vals
+        .par_chunks_mut(STEP_CHUNK_SIZE)
+        .zip(ch_vals.par_chunks_mut(STEP_CHUNK_SIZE))
+        .enumerate()
+        .for_each(|(chunk_idx, (st_chunk, ch_chunk))| {
+            let base = chunk_idx * STEP_CHUNK_SIZE;
+            for (offset, (st_slot, ch_slot)) in
+                st_chunk.iter_mut().zip(ch_chunk.iter_mut()).enumerate()
+            {
+                let i = base + offset;
+                *st_slot = sts
+                    .as_ref()
+                    .map(|s| F::from(s.get(i).cloned().unwrap_or(done) as u64));
+                *ch_slot = chars.as_ref().map(|c| {
+                    c.get(i)
+                        .map(|&c| F::from(c as u64))
+                        .unwrap_or(F::from(EOF as u64))
+                });
+            }
+        });
+
+    (st_vals, ch_vals)
+}
+// ANCHOR_END: precompute_steps
+
+// Exercises `MemoryChip` end-to-end: two writes, a read back of each, an
+// overwrite, and a read of the overwritten address -- enough to walk
+// every branch of the sorted-trace transition gate (first access is a
+// write, a read repeating the previous value, and a second write to an
+// already-touched address).
+#[derive(Clone, Debug)]
+struct MemoryDemoConfig<F: Field> {
+    chip: MemoryChip<F>,
+}
+
+struct MemoryDemoCircuit<F: Field> {
+    _ph: PhantomData<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for MemoryDemoCircuit<F> {
+    type Config = MemoryDemoConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        MemoryDemoCircuit { _ph: PhantomData }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        MemoryDemoConfig {
+            chip: MemoryChip::configure(meta),
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = config.chip;
+
+        chip.write(&mut layouter, 0, Value::known(F::from(5)))?;
+        chip.write(&mut layouter, 1, Value::known(F::from(7)))?;
+
+        let r0 = chip.read(&mut layouter, 0)?;
+        r0.value().assert_if_known(|v| **v == F::from(5));
+
+        chip.write(&mut layouter, 0, Value::known(F::from(9)))?;
+        let r0 = chip.read(&mut layouter, 0)?;
+        r0.value().assert_if_known(|v| **v == F::from(9));
+
+        let r1 = chip.read(&mut layouter, 1)?;
+        r1.value().assert_if_known(|v| **v == F::from(7));
+
+        chip.finalize(&mut layouter)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RegexParams {
+    pattern: String,
+    max_len: usize,
+}
 
 struct TestCircuit<F: Field> {
     _ph: PhantomData<F>,
+    pattern: String,
+    max_len: usize,
     str: Value<String>,
-    sts: Value<Vec<usize>>,
 }
 
 #[derive(Clone, Debug)]
 struct TestConfig<F: Field + Clone> {
     _ph: PhantomData<F>,
+    dfa: Dfa,
+    max_len: usize,
     q_match: Selector,
     q_regex: Selector,  // enable the regex gate
     st: Column<Advice>, // current state of automaton
@@ -74,17 +670,35 @@ struct TestConfig<F: Field + Clone> {
 impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
     type Config = TestConfig<F>;
     type FloorPlanner = SimpleFloorPlanner;
+    type Params = RegexParams;
 
     fn without_witnesses(&self) -> Self {
         TestCircuit {
             _ph: PhantomData,
-            str: Value::unknown(), // the string
-            sts: Value::unknown(), // state of the automaton
+            pattern: self.pattern.clone(),
+            max_len: self.max_len,
+            str: Value::unknown(),
+        }
+    }
+
+    // the regex and the maximum string length are runtime parameters:
+    // they size the lookup table and the stepping region below rather
+    // than being baked into the binary as consts
+    fn params(&self) -> Self::Params {
+        RegexParams {
+            pattern: self.pattern.clone(),
+            max_len: self.max_len,
         }
     }
 
+    fn configure(_meta: &mut ConstraintSystem<F>) -> Self::Config {
+        unreachable!("TestCircuit::Params != (); configure_with_params is called instead")
+    }
+
     // ANCHOR: columns
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+    fn configure_with_params(meta: &mut ConstraintSystem<F>, params: Self::Params) -> Self::Config {
+        let dfa = compile_regex(&params.pattern);
+
         let q_regex = meta.complex_selector();
         let q_match = meta.complex_selector();
 
@@ -121,6 +735,8 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
 
         TestConfig {
             _ph: PhantomData,
+            dfa,
+            max_len: params.max_len,
             q_regex,
             st,
             ch,
@@ -137,6 +753,16 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
         config: Self::Config, //
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
+        let max_len = config.max_len;
+
+        // derive the witness state trace from the input string instead of
+        // requiring the caller to hand-write it
+        let sts: Value<Vec<usize>> = self.str.as_ref().map(|s| {
+            trace(&config.dfa, s)
+                .unwrap_or_else(|| panic!("{:?} does not match the compiled regex", s))
+        });
+        let done = config.dfa.done;
+
         // assign the transition table
         layouter.assign_table(
             || "table",
@@ -147,7 +773,7 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
                     // (0, 0, 0) is in the table to account for q_regex = 0
                     (F::ZERO, F::ZERO, F::ZERO),
                 ];
-                for tx in REGEX.iter() {
+                for tx in config.dfa.transitions.iter() {
                     let (st_cur, st_nxt, ch) = tx;
                     transitions.push((
                         F::from(*st_cur as u64),
@@ -184,73 +810,72 @@ impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
             },
         )?;
 
+        // with `multicore`, the per-row `(st, ch)` witness values are
+        // derived up front across a rayon thread pool; without it, each
+        // row's values are derived inline as they're assigned below
+        #[cfg(feature = "multicore")]
+        let (st_vals, ch_vals) = precompute_steps::<F>(&sts, &self.str, max_len, done);
+
         // create a region which can check the regex expression
         // note: you could have multiple regions to check
         // the same regex at basically no additional cost
         layouter.assign_region(
             || "regex",
             |mut region| {
-                // at offset 0, the state is ST_START
+                // at offset 0, the state is the DFA's start state
                 region.assign_fixed(
                     || "initial state",
                     config.fix_st,
                     0,
-                    || Value::known(F::from(ST_START as u64)),
+                    || Value::known(F::from(config.dfa.start as u64)),
                 )?;
                 config.q_match.enable(&mut region, 0)?;
 
                 // assign each step
-                for i in 0..MAX_STR_LEN {
+                for i in 0..max_len {
                     // enable the regex automaton
                     config.q_regex.enable(&mut region, i)?;
 
                     // state
-                    region.assign_advice(
-                        || "st",
-                        config.st,
-                        i,
-                        || {
-                            self.sts.as_ref().map(|s| {
-                                F::from(
-                                    s.get(i) //
-                                        .cloned()
-                                        .unwrap_or(ST_DONE)
-                                        as u64,
-                                )
-                            })
-                        },
-                    )?;
+                    #[cfg(feature = "multicore")]
+                    let st_val = st_vals[i];
+                    #[cfg(not(feature = "multicore"))]
+                    let st_val = sts.as_ref().map(|s| {
+                        F::from(
+                            s.get(i) //
+                                .cloned()
+                                .unwrap_or(done) as u64,
+                        )
+                    });
+                    region.assign_advice(|| "st", config.st, i, || st_val)?;
 
                     // character
-                    region.assign_advice(
-                        || "ch",
-                        config.ch,
-                        i,
-                        || {
-                            self.str.as_ref().map(|s| {
-                                s.chars()
-                                    .nth(i)
-                                    .map(|c| F::from(c as u64))
-                                    .unwrap_or(F::from(EOF as u64))
-                            })
-                        },
-                    )?;
+                    #[cfg(feature = "multicore")]
+                    let ch_val = ch_vals[i];
+                    #[cfg(not(feature = "multicore"))]
+                    let ch_val = self.str.as_ref().map(|s| {
+                        s.chars()
+                            .nth(i)
+                            .map(|c| F::from(c as u64))
+                            .unwrap_or(F::from(EOF as u64))
+                    });
+                    region.assign_advice(|| "ch", config.ch, i, || ch_val)?;
                 }
 
-                // at offset MAX_STR_LEN, the state is ST_START
+                // at offset max_len, the state is done
                 region.assign_advice(
                     || "st",
                     config.st,
-                    MAX_STR_LEN,
-                    || Value::known(F::from(ST_DONE as u64)),
+                    max_len,
+                    || Value::known(F::from(done as u64)),
                 )?;
                 region.assign_fixed(
                     || "final state",
                     config.fix_st,
-                    MAX_STR_LEN,
-                    || Value::known(F::from(ST_DONE as u64)),
+                    max_len,
+                    || Value::known(F::from(done as u64)),
                 )?;
-                config.q_match.enable(&mut region, MAX_STR_LEN)?;
+                config.q_match.enable(&mut region, max_len)?;
 
                 Ok(())
             },
@@ -266,20 +891,16 @@ fn main() {
     // run the MockProver
     let circuit = TestCircuit::<Fr> {
         _ph: PhantomData,
-        // the string to match
+        pattern: "a+b+c".to_string(),
+        max_len: 20,
+        // the string to match; the state trace is derived automatically
         str: Value::known("aaabbbc".to_string()),
-        // manually create a trace of the state transitions
-        sts: Value::known(vec![
-            ST_A,    // ST_A -a-> ST_A (START)
-            ST_A,    // ST_A -a-> ST_A
-            ST_A,    // ST_A -a-> ST_A
-            ST_B,    // ST_A -a-> ST_B
-            ST_B,    // ST_B -b-> ST_B
-            ST_B,    // ST_B -b-> ST_B
-            ST_C,    // ST_B -b-> ST_C
-            ST_DONE, // ST_C -c-> ST_DONE
-        ]),
     };
     let prover = MockProver::run(8, &circuit, vec![]).unwrap();
     prover.verify().unwrap();
+
+    // demonstrate the RwTable-backed memory-consistency subsystem
+    let memory_demo_circuit = MemoryDemoCircuit::<Fr> { _ph: PhantomData };
+    let prover = MockProver::run(7, &memory_demo_circuit, vec![]).unwrap();
+    prover.verify().unwrap();
 }